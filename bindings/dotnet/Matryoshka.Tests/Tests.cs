@@ -82,6 +82,34 @@ namespace Matryoshka.Tests
             }
         }
 
+        [Theory]
+        [InlineData(new byte[] { }, -1)]
+        [InlineData(new byte[] { 42, 32, 44, 45, 46 }, 2)]
+        public void TestStream(byte[] data, int chunk_size) {
+            var file_system = new FileSystem(":memory:");
+
+            using (var tmp_dir = new TemporaryDirectory()) {
+                var input_file = tmp_dir.GetFile("input_file", data);
+                var file = file_system.Push("folder/file", input_file, chunk_size);
+                Assert.Equal(data.Length, file.Length);
+
+                using (var stream = file.OpenRead())
+                using (var memory = new MemoryStream()) {
+                    Assert.Equal(data.Length, stream.Length);
+                    stream.CopyTo(memory);
+                    Assert.Equal(data, memory.ToArray());
+                    Assert.Equal(data.Length, stream.Position);
+
+                    // Seek back and read the tail again
+                    Assert.Equal(data.Length / 2, stream.Seek(-(data.Length - data.Length / 2), SeekOrigin.End));
+                    var buffer = new byte[data.Length];
+                    Assert.Equal(data.Length - data.Length / 2, stream.Read(buffer, 0, buffer.Length));
+                    Assert.Equal(data[(data.Length / 2)..], buffer[..(data.Length - data.Length / 2)]);
+                    Assert.Equal(0, stream.Read(buffer, 0, buffer.Length));
+                }
+            }
+        }
+
         [Fact]
         public void TestFind() {
             var file_system = new FileSystem(":memory:");
@@ -101,6 +129,33 @@ namespace Matryoshka.Tests
                 Assert.Equal(2, file_system.Find("folder?/file1").Count);
                 Assert.Equal(2, file_system.Find("*/file1").Count);
                 Assert.Equal(1, file_system.Find("folder2/*").Count);
+
+                // A missing pattern is reported instead of finding nothing
+                Assert.Throws<MatryoshkaException>(() => {
+                    file_system.Find();
+                });
+            }
+        }
+
+        [Fact]
+        public void TestRename() {
+            var file_system = new FileSystem(":memory:");
+
+            using (var tmp_dir = new TemporaryDirectory()) {
+                var input_file = tmp_dir.GetFile("input_file", new byte[] { 42, 32, 44 });
+                file_system.Push("folder1/file", input_file, -1);
+                file_system.Push("folder2/file", input_file, -1);
+
+                // It is forbidden to override included files
+                Assert.Throws<MatryoshkaException>(() => {
+                    file_system.Rename("folder1/file", "folder2/file");
+                });
+
+                file_system.Rename("folder1/file", "folder3/file");
+                Assert.Equal(3, file_system.Open("folder3/file").Size);
+                Assert.Throws<MatryoshkaException>(() => {
+                    file_system.Open("folder1/file");
+                });
             }
         }
     }
@@ -46,11 +46,26 @@ namespace Matryoshka {
         [DllImport("matryoshka")]
         public static extern int Find(FileSystem* file_system, string path, [MarshalAs(UnmanagedType.FunctionPtr)]FindCallback callback);
 
+        [DllImport("matryoshka")]
+        public static extern int FindEx(FileSystem* file_system, IntPtr path, [MarshalAs(UnmanagedType.FunctionPtr)]FindCallback callback, Status** status);
+
+        [DllImport("matryoshka")]
+        public static extern int FindEx(FileSystem* file_system, string path, [MarshalAs(UnmanagedType.FunctionPtr)]FindCallback callback, Status** status);
+
         [DllImport("matryoshka")]
         public static extern int GetSize(FileSystem* file_system, FileHandle* file);
 
+        [DllImport("matryoshka")]
+        public static extern long GetSize64(FileSystem* file_system, FileHandle* file);
+
+        [DllImport("matryoshka")]
+        public static extern int ReadInto(FileSystem* file_system, FileHandle* file, long offset, byte* buffer, int buffer_len, Status** status);
+
         [DllImport("matryoshka")]
         public static extern int Delete(FileSystem* file_system, FileHandle* file);
+
+        [DllImport("matryoshka")]
+        public static extern int Rename(FileSystem* file_system, string from_path, string to_path, Status** status);
     }
 
     /// <summary>
@@ -210,14 +225,30 @@ namespace Matryoshka {
             }
         }
 
+        public void Rename(string from_path, string to_path) {
+            unsafe {
+                Native.Status* status;
+                if (Native.Rename(handle_.GetHandle(), from_path, to_path, &status) == 0) {
+                    using (handles.StatusHandle handle = new handles.StatusHandle(status)) {
+                        throw new MatryoshkaException(handle);
+                    }
+                }
+            }
+        }
+
         public List<string> Find(string path = null) {
             List<string> files = new List<string>();
             unsafe {
                 Native.FileSystem* file_system = handle_.GetHandle();
-                if (path == null) {
-                    Native.Find(file_system, IntPtr.Zero, x => files.Add(Marshal.PtrToStringAnsi(x)));
-                } else {
-                    Native.Find(file_system, path, x => files.Add(Marshal.PtrToStringAnsi(x)));
+                FindCallback callback = x => files.Add(Marshal.PtrToStringAnsi(x));
+                Native.Status* status;
+                int num_paths = path == null
+                    ? Native.FindEx(file_system, IntPtr.Zero, callback, &status)
+                    : Native.FindEx(file_system, path, callback, &status);
+                if (num_paths < 0) {
+                    using (handles.StatusHandle handle = new handles.StatusHandle(status)) {
+                        throw new MatryoshkaException(handle);
+                    }
                 }
             }
             return files;
@@ -267,8 +298,126 @@ namespace Matryoshka {
             }
         }
 
+        public long Length {
+            get {
+                unsafe {
+                    return Native.GetSize64(parent_.GetHandle(), handle_.GetHandle());
+                }
+            }
+        }
+
+        /// <summary>
+        /// Open a read-only stream over the content of the file.
+        /// </summary>
+        public System.IO.Stream OpenRead() {
+            return new VirtualFileStream(this);
+        }
+
+        internal int ReadInto(long position, byte[] buffer, int offset, int count) {
+            if (count == 0) {
+                return 0;
+            }
+
+            unsafe {
+                Native.Status* status;
+                int num_bytes;
+                fixed (byte* data = &buffer[offset]) {
+                    num_bytes = Native.ReadInto(parent_.GetHandle(), handle_.GetHandle(), position, data, count, &status);
+                }
+                if (num_bytes < 0) {
+                    using (handles.StatusHandle handle = new handles.StatusHandle(status)) {
+                        throw new MatryoshkaException(handle);
+                    }
+                }
+                return num_bytes;
+            }
+        }
+
         public override string ToString() {
             return path_;
         }
     }
+
+    /// <summary>
+    /// A read-only stream over a file in the virtual file system.
+    /// </summary>
+    public class VirtualFileStream : System.IO.Stream {
+        private readonly File file_;
+        private long position_;
+
+        internal VirtualFileStream(File file) {
+            file_ = file;
+            position_ = 0;
+        }
+
+        public override bool CanRead {
+            get { return true; }
+        }
+
+        public override bool CanSeek {
+            get { return true; }
+        }
+
+        public override bool CanWrite {
+            get { return false; }
+        }
+
+        public override long Length {
+            get {
+                long length = file_.Length;
+                if (length < 0) {
+                    throw new System.IO.IOException("Querying the size of the file failed");
+                }
+                return length;
+            }
+        }
+
+        public override long Position {
+            get { return position_; }
+            set {
+                if (value < 0) {
+                    throw new ArgumentOutOfRangeException(nameof(value));
+                }
+                position_ = value;
+            }
+        }
+
+        public override int Read(byte[] buffer, int offset, int count) {
+            if (buffer == null) {
+                throw new ArgumentNullException(nameof(buffer));
+            }
+            if (offset < 0 || count < 0 || buffer.Length - offset < count) {
+                throw new ArgumentOutOfRangeException();
+            }
+
+            int num_bytes = file_.ReadInto(position_, buffer, offset, count);
+            position_ += num_bytes;
+            return num_bytes;
+        }
+
+        public override long Seek(long offset, System.IO.SeekOrigin origin) {
+            switch (origin) {
+                case System.IO.SeekOrigin.Begin:
+                    Position = offset;
+                    break;
+                case System.IO.SeekOrigin.Current:
+                    Position = position_ + offset;
+                    break;
+                case System.IO.SeekOrigin.End:
+                    Position = Length + offset;
+                    break;
+            }
+            return position_;
+        }
+
+        public override void Flush() { }
+
+        public override void SetLength(long value) {
+            throw new NotSupportedException();
+        }
+
+        public override void Write(byte[] buffer, int offset, int count) {
+            throw new NotSupportedException();
+        }
+    }
 }
\ No newline at end of file
@@ -1,3 +1,4 @@
+import io
 import unittest
 from pathlib import Path
 import faulthandler
@@ -6,6 +7,7 @@ from tempfile import TemporaryDirectory
 from matryoshka import Matryoshka
 from file_system import FileSystem
 from file import File
+from exception import MatryoshkaException
 
 
 class TestMatryoshka(unittest.TestCase):
@@ -52,6 +54,23 @@ class TestMatryoshka(unittest.TestCase):
 
         output_file.unlink()
 
+    def test_read(self):
+        example_path = Path("folder1", "file")
+
+        with FileSystem(":memory:", self.matryoshka) as fs:
+            with File.create(fs, example_path, self.example_file, 3):
+                pass
+            with File(fs, example_path) as file:
+                self.assertEqual(file.read(), b"1234")
+                self.assertEqual(file.read(), b"")
+                self.assertEqual(file.seek(1), 1)
+                self.assertEqual(file.read(2), b"23")
+                self.assertEqual(file.tell(), 3)
+                self.assertEqual(file.seek(-2, io.SEEK_END), 2)
+                self.assertEqual(file.read(10), b"34")
+                self.assertEqual(file.seek(-1, io.SEEK_CUR), 3)
+                self.assertEqual(file.read(), b"4")
+
     def test_find(self):
         with FileSystem(":memory:", self.matryoshka) as fs:
             with File.create(fs, Path("folder1", "file"), self.example_file):
@@ -62,6 +81,23 @@ class TestMatryoshka(unittest.TestCase):
             files = File.find(fs, Path("folder*", "file"))
             self.assertEqual(len(files), 2)
 
+    def test_rename(self):
+        old_path = Path("folder1", "file")
+        new_path = Path("folder2", "file")
+
+        with FileSystem(":memory:", self.matryoshka) as fs:
+            with File.create(fs, old_path, self.example_file):
+                pass
+
+            fs.rename(old_path, new_path)
+            with File(fs, new_path) as file:
+                self.assertEqual(file.size, 4)
+            with self.assertRaises(MatryoshkaException):
+                with File(fs, old_path):
+                    pass
+            with self.assertRaises(MatryoshkaException):
+                fs.rename(old_path, new_path)
+
 
 if __name__ == "__main__":
     faulthandler.enable()
@@ -1,4 +1,5 @@
 import ctypes
+import io
 from pathlib import Path
 from typing import Sequence, Optional
 
@@ -23,6 +24,9 @@ class File(ApiElement):
     # The type of callback used for extracting found paths.
     FIND_CALLBACK = ctypes.CFUNCTYPE(None, ctypes.c_char_p)
 
+    # The maximal number of bytes read by a single call, as the length of the buffer is a C int.
+    MAX_READ_SIZE = 2 ** 31 - 1
+
     @classmethod
     def create(
         cls,
@@ -73,24 +77,31 @@ class File(ApiElement):
         :return: A file which is not opened.
         """
 
-        file_system.matryoshka.library.Find.restype = File.HANDLE_TYPE
-        file_system.matryoshka.library.Find.argtypes = (
-            FileSystem.HANDLE_TYPE,
-            ctypes.c_char_p,
-            File.FIND_CALLBACK,
-        )
-
         paths = []
 
         def add_path(file_name: bytes) -> None:
             parts = file_name.decode(encoding="ascii").split("/")
             paths.append(Path(*parts))
 
-        file_system.matryoshka.library.Find(
-            file_system.handle,
-            "/".join(virtual_path.parts).encode("ascii"),
-            File.FIND_CALLBACK(add_path),
-        )
+        with Status(file_system.matryoshka) as status:
+            file_system.matryoshka.library.FindEx.restype = ctypes.c_int
+            file_system.matryoshka.library.FindEx.argtypes = (
+                FileSystem.HANDLE_TYPE,
+                ctypes.c_char_p,
+                File.FIND_CALLBACK,
+                ctypes.POINTER(Status.HANDLE_TYPE),
+            )
+
+            num_paths = file_system.matryoshka.library.FindEx(
+                file_system.handle,
+                "/".join(virtual_path.parts).encode("ascii"),
+                File.FIND_CALLBACK(add_path),
+                ctypes.byref(status.handle),
+            )
+
+            if num_paths < 0:
+                raise MatryoshkaException(status)
+
         return [cls(file_system, path) for path in paths]
 
     def __init__(
@@ -111,6 +122,7 @@ class File(ApiElement):
         self.handle = (
             existing_handle if existing_handle is not None else File.HANDLE_TYPE()
         )
+        self.position = 0
 
     @classmethod
     def initialize(cls, matryoshka: Matryoshka):
@@ -130,8 +142,21 @@ class File(ApiElement):
             ctypes.c_char_p,
         )
 
-        matryoshka.library.GetSize.restype = ctypes.c_int
-        matryoshka.library.GetSize.argtypes = (FileSystem.HANDLE_TYPE, File.HANDLE_TYPE)
+        matryoshka.library.GetSize64.restype = ctypes.c_longlong
+        matryoshka.library.GetSize64.argtypes = (
+            FileSystem.HANDLE_TYPE,
+            File.HANDLE_TYPE,
+        )
+
+        matryoshka.library.ReadInto.restype = ctypes.c_int
+        matryoshka.library.ReadInto.argtypes = (
+            FileSystem.HANDLE_TYPE,
+            File.HANDLE_TYPE,
+            ctypes.c_longlong,
+            ctypes.POINTER(ctypes.c_char),
+            ctypes.c_int,
+            ctypes.POINTER(Status.HANDLE_TYPE),
+        )
 
     def __enter__(self):
         if not self.handle:
@@ -176,6 +201,71 @@ class File(ApiElement):
             if status:
                 raise MatryoshkaException(status)
 
+    def read(self, size: int = -1) -> bytes:
+        """
+        Read from the current position, which is advanced afterwards.
+        :param size: The maximal number of bytes to read. Values < 0 will read until the end of the file.
+        :return: The bytes read, which are empty at the end of the file.
+        """
+
+        if not self:
+            raise ValueError("The file is not open")
+
+        if size < 0:
+            size = max(self.size - self.position, 0)
+        chunks = []
+        with Status(self.matryoshka) as status:
+            while size > 0:
+                chunk_size = min(size, File.MAX_READ_SIZE)
+                buffer = ctypes.create_string_buffer(chunk_size)
+                num_bytes = self.matryoshka.library.ReadInto(
+                    self.file_system.handle,
+                    self.handle,
+                    self.position,
+                    buffer,
+                    chunk_size,
+                    ctypes.byref(status.handle),
+                )
+                if num_bytes < 0:
+                    raise MatryoshkaException(status)
+
+                self.position += num_bytes
+                size -= num_bytes
+                chunks.append(buffer.raw[:num_bytes])
+                if num_bytes < chunk_size:
+                    break
+
+        return b"".join(chunks)
+
+    def seek(self, offset: int, whence: int = io.SEEK_SET) -> int:
+        """
+        Change the current position like the seek of Python's file objects.
+        :param offset: The offset relative to the reference point.
+        :param whence: The reference point, i.e. the start, the current position, or the end of the file.
+        :return: The new position.
+        """
+
+        if whence == io.SEEK_SET:
+            position = offset
+        elif whence == io.SEEK_CUR:
+            position = self.position + offset
+        elif whence == io.SEEK_END:
+            position = self.size + offset
+        else:
+            raise ValueError(f"Invalid reference point '{whence}'")
+
+        if position < 0:
+            raise ValueError("Negative position")
+        self.position = position
+        return self.position
+
+    def tell(self) -> int:
+        """
+        Return the current position.
+        :return: The current position in bytes.
+        """
+        return self.position
+
     @property
     def size(self) -> int:
         """
@@ -185,4 +275,4 @@ class File(ApiElement):
         if not self:
             raise ValueError("The file is not open")
 
-        return self.matryoshka.library.GetSize(self.file_system.handle, self.handle)
+        return self.matryoshka.library.GetSize64(self.file_system.handle, self.handle)
@@ -1,4 +1,5 @@
 import ctypes
+from pathlib import Path
 
 from matryoshka import Matryoshka
 from status import Status
@@ -42,6 +43,14 @@ class FileSystem(ApiElement):
             ctypes.POINTER(FileSystem.FileSystem)
         ]
 
+        matryoshka.library.Rename.restype = ctypes.c_int
+        matryoshka.library.Rename.argtypes = [
+            FileSystem.HANDLE_TYPE,
+            ctypes.c_char_p,
+            ctypes.c_char_p,
+            ctypes.POINTER(Status.HANDLE_TYPE),
+        ]
+
     def __enter__(self):
         if not self.handle:
             with Status(self.matryoshka) as status:
@@ -61,3 +70,22 @@ class FileSystem(ApiElement):
 
     def __bool__(self):
         return bool(self.handle)
+
+    def rename(self, from_path: Path, to_path: Path) -> None:
+        """
+        Rename a file in the virtual file system.
+        :param from_path: The current path in the virtual file system.
+        :param to_path: The new path in the virtual file system, which must not exist yet.
+        """
+
+        if not self:
+            raise ValueError("The file system is not open")
+
+        with Status(self.matryoshka) as status:
+            if not self.matryoshka.library.Rename(
+                self.handle,
+                "/".join(from_path.parts).encode("ascii"),
+                "/".join(to_path.parts).encode("ascii"),
+                ctypes.byref(status.handle),
+            ):
+                raise MatryoshkaException(status)
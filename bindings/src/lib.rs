@@ -2,13 +2,15 @@
 
 extern crate static_vcruntime;
 
+use std::any::Any;
 use std::convert::TryInto;
 use std::ffi::{CStr, CString};
-use std::os::raw::{c_char, c_int};
+use std::os::raw::{c_char, c_int, c_longlong};
+use std::panic::{catch_unwind, AssertUnwindSafe};
 use std::ptr::{null, null_mut};
 
 use matryoshka_sqlite::{
-    errors::{DatabaseError, Error},
+    errors::{DatabaseError, Error, LoadingError},
     Database, File, FileSystem as RawFileSystem, Handle as RawHandle,
 };
 
@@ -22,22 +24,46 @@ impl From<*mut *mut Status> for Environment {
 
 impl Environment {
     pub fn execute<T, C: FnOnce() -> Result<T, String>>(self, body: C) -> *mut T {
-        match body() {
+        match Environment::guard(body, Err) {
             Ok(value) => Box::into_raw(Box::new(value)),
             Err(error) => {
-                if !self.0.is_null() {
-                    let status = Environment::create_status(&error);
-                    unsafe {
-                        std::ptr::write(self.0, status);
-                    }
-                }
+                self.report(error);
                 null_mut()
             }
         }
     }
 
+    /// Run the body, converting a panic into the fallback as unwinding across the FFI boundary is undefined behavior.
+    pub fn guard<T, C: FnOnce() -> T, F: FnOnce(String) -> T>(body: C, on_panic: F) -> T {
+        catch_unwind(AssertUnwindSafe(body))
+            .unwrap_or_else(|payload| on_panic(Environment::panic_message(payload)))
+    }
+
+    fn panic_message(payload: Box<dyn Any + Send>) -> String {
+        let reason = match (
+            payload.downcast_ref::<&str>(),
+            payload.downcast_ref::<String>(),
+        ) {
+            (Some(reason), _) => reason,
+            (_, Some(reason)) => reason.as_str(),
+            _ => "Unknown reason",
+        };
+        format!("Internal error: {}", reason)
+    }
+
+    pub fn report<T: AsRef<str>>(self, error: T) {
+        if !self.0.is_null() {
+            let status = Environment::create_status(error);
+            unsafe {
+                std::ptr::write(self.0, status);
+            }
+        }
+    }
+
     pub fn create_status<T: AsRef<str>>(description: T) -> *mut Status {
-        let message = CString::new(description.as_ref()).expect("Found NULL");
+        // Panicking across the FFI boundary is undefined behavior, so NUL bytes are replaced.
+        let message = CString::new(description.as_ref().replace('\0', "\u{FFFD}"))
+            .expect("NUL bytes were replaced");
         Box::into_raw(Box::new(Status(message)))
     }
 
@@ -102,7 +128,7 @@ pub unsafe extern "C" fn Load(path: *const c_char, status: *mut *mut Status) ->
 /// @param file_system The virtual file system. Passing nullptr is a safe no-op.
 #[no_mangle]
 pub unsafe extern "C" fn DestroyFileSystem(file_system: *mut FileSystem) {
-    Environment::destroy(file_system)
+    Environment::guard(|| Environment::destroy(file_system), |_| ())
 }
 
 /// Destroy a status.
@@ -110,7 +136,7 @@ pub unsafe extern "C" fn DestroyFileSystem(file_system: *mut FileSystem) {
 /// @param status The status. Passing nullptr is a safe no-op.
 #[no_mangle]
 pub unsafe extern "C" fn DestroyStatus(status: *mut Status) {
-    Environment::destroy(status)
+    Environment::guard(|| Environment::destroy(status), |_| ())
 }
 
 /// Destroy a file handle.
@@ -118,7 +144,7 @@ pub unsafe extern "C" fn DestroyStatus(status: *mut Status) {
 /// @param file_handle The file handle. Passing nullptr is a safe no-op.
 #[no_mangle]
 pub unsafe extern "C" fn DestroyFileHandle(file_handle: *mut FileHandle) {
-    Environment::destroy(file_handle)
+    Environment::guard(|| Environment::destroy(file_handle), |_| ())
 }
 
 /// Return the error message associated with a status.
@@ -128,10 +154,13 @@ pub unsafe extern "C" fn DestroyFileHandle(file_handle: *mut FileHandle) {
 /// @return A human-readable description of the failure.
 #[no_mangle]
 pub unsafe extern "C" fn GetMessage(status: *const Status) -> *const c_char {
-    match status.as_ref() {
-        Some(value) => value.0.as_ptr(),
-        None => null(),
-    }
+    Environment::guard(
+        || match status.as_ref() {
+            Some(value) => value.0.as_ptr(),
+            None => null(),
+        },
+        |_| null(),
+    )
 }
 
 /// Open a existing file on the virtual file system.
@@ -179,6 +208,40 @@ pub unsafe extern "C" fn Push(
     file_path: *const c_char,
     chunk_size: c_int,
     status: *mut *mut Status,
+) -> *mut FileHandle {
+    PushEx(
+        file_system,
+        inner_path,
+        file_path,
+        chunk_size,
+        null_mut(),
+        status,
+    )
+}
+
+/// Push a file to the virtual file system, reporting its size.
+///
+/// @param file_system A pointer to the virtual file system.
+///
+/// @param inner_path The inner path on the virtual file system (mind the forward slashes as separators!)
+///
+/// @param file_path The path on the real file system.
+///
+/// @param chunk_size The proposed chunk size. Negative values will let the virtual file system choose.
+///
+/// @param out_size Contains the size of the file in bytes if and only if the return value is not nullptr. Setting this value to nullptr is safe and will not save the size.
+///
+/// @param status Contains the error code of the failure if and only if the return value is nullptr. Setting this value to nullptr is safe and will not save the error code.
+///
+/// @return A handle to the newly created file or nullptr on failure.
+#[no_mangle]
+pub unsafe extern "C" fn PushEx(
+    file_system: *mut FileSystem,
+    inner_path: *const c_char,
+    file_path: *const c_char,
+    chunk_size: c_int,
+    out_size: *mut c_longlong,
+    status: *mut *mut Status,
 ) -> *mut FileHandle {
     Environment::from(status).execute(|| {
         let file_system = file_system
@@ -197,10 +260,65 @@ pub unsafe extern "C" fn Push(
         let chunk_size = std::cmp::max(0, chunk_size) as usize;
         let file = File::create(&mut file_system.0, inner_path, local_file, chunk_size)
             .map_err(|error| error.error_message())?;
+        if !out_size.is_null() {
+            std::ptr::write(out_size, file.len() as c_longlong);
+        }
         Ok(FileHandle(file.handle()))
     })
 }
 
+/// Append data to an existing file on the virtual file system.
+///
+/// @param file_system A pointer to the virtual file system.
+///
+/// @param file_handle A handle to the file.
+///
+/// @param data A pointer to the bytes to append. It may be nullptr if data_len is 0.
+///
+/// @param data_len The number of bytes to append.
+///
+/// @param status Contains the error code of the failure if and only if the return value is -1. Setting this value to nullptr is safe and will not save the error code.
+///
+/// @return The new file size in bytes or -1 on failure.
+#[no_mangle]
+pub unsafe extern "C" fn PushAppend(
+    file_system: *mut FileSystem,
+    file_handle: *const FileHandle,
+    data: *const u8,
+    data_len: c_int,
+    status: *mut *mut Status,
+) -> c_longlong {
+    let append = || -> Result<usize, String> {
+        let file_system = file_system
+            .as_ref()
+            .ok_or_else(|| String::from("File system not specified"))?;
+        let file_handle = file_handle
+            .as_ref()
+            .ok_or_else(|| String::from("File handle not specified"))?;
+        let data = match (data.is_null(), data_len) {
+            (_, 0) => &[][..],
+            (false, data_len) if data_len > 0 => {
+                std::slice::from_raw_parts(data, data_len as usize)
+            }
+            _ => return Err(String::from("Data not specified")),
+        };
+
+        let mut file: File<_> = (&file_system.0, file_handle.0)
+            .try_into()
+            .map_err(|error: LoadingError| error.error_message())?;
+        file.append(data).map_err(|error| error.error_message())?;
+        Ok(file.len())
+    };
+
+    match Environment::guard(append, Err) {
+        Ok(size) => size as c_longlong,
+        Err(error) => {
+            Environment::from(status).report(error);
+            -1
+        }
+    }
+}
+
 /// Pull a file from the database into the virtual file system.
 ///
 /// @param file_system A pointer to the virtual file system.
@@ -216,50 +334,114 @@ pub unsafe extern "C" fn Pull(
     handle: *const FileHandle,
     file_path: *const c_char,
 ) -> *mut Status {
-    let file_system = match file_system.as_mut() {
-        Some(file_system) => file_system,
-        None => {
-            return Environment::create_status("File system not specified");
-        }
-    };
+    Environment::guard(
+        || {
+            let file_system = match file_system.as_mut() {
+                Some(file_system) => file_system,
+                None => {
+                    return Environment::create_status("File system not specified");
+                }
+            };
 
-    let handle = match handle.as_ref() {
-        Some(handle) => handle,
-        None => {
-            return Environment::create_status("File handle not specified");
-        }
-    };
+            let handle = match handle.as_ref() {
+                Some(handle) => handle,
+                None => {
+                    return Environment::create_status("File handle not specified");
+                }
+            };
 
-    let local_path = match Environment::parse_str(file_path) {
-        Ok(local_path) => local_path,
-        Err(error) => {
-            return Environment::create_status(error);
-        }
-    };
+            let local_path = match Environment::parse_str(file_path) {
+                Ok(local_path) => local_path,
+                Err(error) => {
+                    return Environment::create_status(error);
+                }
+            };
 
-    let virtual_file: File<_> = match (&file_system.0, handle.0).try_into() {
-        Ok(file) => file,
-        Err(error) => {
-            return Environment::create_status(error.error_message());
-        }
-    };
+            let virtual_file: File<_> = match (&file_system.0, handle.0).try_into() {
+                Ok(file) => file,
+                Err(error) => {
+                    return Environment::create_status(error.error_message());
+                }
+            };
+
+            let local_file = match std::fs::OpenOptions::new()
+                .write(true)
+                .truncate(true)
+                .create(true)
+                .open(local_path)
+            {
+                Ok(file) if file.set_len(virtual_file.len() as u64).is_ok() => file,
+                _ => {
+                    return Environment::create_status("Unable to create the local file");
+                }
+            };
 
-    let local_file = match std::fs::OpenOptions::new()
-        .write(true)
-        .truncate(true)
-        .create(true)
-        .open(local_path)
-    {
-        Ok(file) if file.set_len(virtual_file.len() as u64).is_ok() => file,
-        _ => {
-            return Environment::create_status("Unable to create the local file");
-        }
+            match virtual_file.random_read(local_file, 0, virtual_file.len()) {
+                Ok(num_bytes) if num_bytes == virtual_file.len() => null_mut(),
+                Err(error) => Environment::create_status(error.error_message()),
+                _ => Environment::create_status("Less than expected bytes were written."),
+            }
+        },
+        Environment::create_status,
+    )
+}
+
+/// Read a range of a file into a buffer.
+///
+/// @param file_system A pointer to the virtual file system.
+///
+/// @param file_handle A handle to the file.
+///
+/// @param offset The position in the file to start reading at.
+///
+/// @param buffer A pointer to the buffer the bytes are written into.
+///
+/// @param buffer_len The size of the buffer. At most this number of bytes is read.
+///
+/// @param status Contains the error code of the failure if and only if the return value is -1. Setting this value to nullptr is safe and will not save the error code.
+///
+/// @return The number of bytes read, which is 0 at the end of the file, or -1 on failure.
+#[no_mangle]
+pub unsafe extern "C" fn ReadInto(
+    file_system: *const FileSystem,
+    file_handle: *const FileHandle,
+    offset: c_longlong,
+    buffer: *mut u8,
+    buffer_len: c_int,
+    status: *mut *mut Status,
+) -> c_int {
+    let read = || -> Result<usize, String> {
+        let file_system = file_system
+            .as_ref()
+            .ok_or_else(|| String::from("File system not specified"))?;
+        let file_handle = file_handle
+            .as_ref()
+            .ok_or_else(|| String::from("File handle not specified"))?;
+        let offset: usize = offset
+            .try_into()
+            .map_err(|_| String::from("Offset must not be negative"))?;
+        let buffer = match (buffer.is_null(), buffer_len) {
+            (_, 0) => &mut [][..],
+            (false, buffer_len) if buffer_len > 0 => {
+                std::slice::from_raw_parts_mut(buffer, buffer_len as usize)
+            }
+            _ => return Err(String::from("Buffer not specified")),
+        };
+
+        let file: File<_> = (&file_system.0, file_handle.0)
+            .try_into()
+            .map_err(|error: LoadingError| error.error_message())?;
+        let length = std::cmp::min(buffer.len(), file.len().saturating_sub(offset));
+        file.random_read(&mut buffer[..length], offset, length)
+            .map_err(|error| error.error_message())
     };
 
-    match virtual_file.random_read(local_file, 0, virtual_file.len()) {
-        Ok(num_bytes) if num_bytes == virtual_file.len() => null_mut(),
-        Err(error) => Environment::create_status(error.error_message()),
-        _ => Environment::create_status("Less than expected bytes were written."),
+    match Environment::guard(read, Err) {
+        Ok(num_bytes) => num_bytes as c_int,
+        Err(error) => {
+            Environment::from(status).report(error);
+            -1
+        }
     }
 }
 
@@ -275,28 +457,67 @@ pub unsafe extern "C" fn GetSize(
     file_system: *const FileSystem,
     file_handle: *const FileHandle,
 ) -> c_int {
-    let file_system = match file_system.as_ref() {
-        Some(file_system) => file_system,
-        None => {
-            return -1;
-        }
-    };
+    Environment::guard(
+        || {
+            let file_system = match file_system.as_ref() {
+                Some(file_system) => file_system,
+                None => {
+                    return -1;
+                }
+            };
 
-    let file_handle = match file_handle.as_ref() {
-        Some(file_system) => file_system,
-        None => {
-            return -1;
-        }
-    };
+            let file_handle = match file_handle.as_ref() {
+                Some(file_system) => file_system,
+                None => {
+                    return -1;
+                }
+            };
 
-    let file: File<_> = match (&file_system.0, file_handle.0).try_into() {
-        Ok(file) => file,
-        Err(_) => {
-            return -1;
-        }
-    };
+            let file: File<_> = match (&file_system.0, file_handle.0).try_into() {
+                Ok(file) => file,
+                Err(_) => {
+                    return -1;
+                }
+            };
 
-    file.len() as c_int
+            file.len() as c_int
+        },
+        |_| -1,
+    )
+}
+
+/// Returns the size of a file, even if it exceeds the range of an int.
+///
+/// @param file_system A pointer to the virtual file system.
+///
+/// @param file A handle to the file.
+///
+/// @return File size in bytes or -1 on failure.
+#[no_mangle]
+pub unsafe extern "C" fn GetSize64(
+    file_system: *const FileSystem,
+    file_handle: *const FileHandle,
+) -> c_longlong {
+    Environment::guard(
+        || {
+            let (file_system, file_handle) = match (file_system.as_ref(), file_handle.as_ref()) {
+                (Some(file_system), Some(file_handle)) => (file_system, file_handle),
+                _ => {
+                    return -1;
+                }
+            };
+
+            let file: File<_> = match (&file_system.0, file_handle.0).try_into() {
+                Ok(file) => file,
+                Err(_) => {
+                    return -1;
+                }
+            };
+
+            file.len() as c_longlong
+        },
+        |_| -1,
+    )
 }
 
 /// Delete a file. The file handle must not be used after the call but still needs to be freed.
@@ -308,30 +529,75 @@ pub unsafe extern "C" fn GetSize(
 /// @return 1 if operation was successful, 0 otherwise.
 #[no_mangle]
 pub unsafe extern "C" fn Delete(file_system: *mut FileSystem, file: *mut FileHandle) -> c_int {
-    let file_system = match file_system.as_ref() {
-        Some(file_system) => file_system,
-        None => {
-            return 0;
-        }
-    };
+    Environment::guard(
+        || {
+            let file_system = match file_system.as_ref() {
+                Some(file_system) => file_system,
+                None => {
+                    return 0;
+                }
+            };
 
-    let file_handle = match file.as_ref() {
-        Some(file_system) => file_system.0,
-        None => {
-            return 0;
-        }
-    };
+            let file_handle = match file.as_ref() {
+                Some(file_system) => file_system.0,
+                None => {
+                    return 0;
+                }
+            };
 
-    let file: File<_> = match (&file_system.0, file_handle).try_into() {
-        Ok(file) => file,
-        Err(_) => {
-            return 0;
-        }
+            let file: File<_> = match (&file_system.0, file_handle).try_into() {
+                Ok(file) => file,
+                Err(_) => {
+                    return 0;
+                }
+            };
+
+            match file.delete() {
+                true => 1,
+                false => 0,
+            }
+        },
+        |_| 0,
+    )
+}
+
+/// Rename a file. The destination must not exist yet.
+///
+/// @param file_system A pointer to the virtual file system.
+///
+/// @param from_path The current path on the virtual file system (mind the forward slashes as separators!)
+///
+/// @param to_path The new path on the virtual file system.
+///
+/// @param status Contains the error code of the failure if and only if the return value is 0. Setting this value to nullptr is safe and will not save the error code.
+///
+/// @return 1 if operation was successful, 0 otherwise.
+#[no_mangle]
+pub unsafe extern "C" fn Rename(
+    file_system: *mut FileSystem,
+    from_path: *const c_char,
+    to_path: *const c_char,
+    status: *mut *mut Status,
+) -> c_int {
+    let rename = || -> Result<(), String> {
+        let file_system = file_system
+            .as_mut()
+            .ok_or_else(|| String::from("File system not specified"))?;
+        let from_path = Environment::parse_str(from_path)?;
+        let to_path = Environment::parse_str(to_path)?;
+        file_system
+            .0
+            .rename(from_path, to_path)
+            .map_err(|error| error.error_message())?;
+        Ok(())
     };
 
-    match file.delete() {
-        true => 1,
-        false => 0,
+    match Environment::guard(rename, Err) {
+        Ok(_) => 1,
+        Err(error) => {
+            Environment::from(status).report(error);
+            0
+        }
     }
 }
 
@@ -343,35 +609,59 @@ pub unsafe extern "C" fn Delete(file_system: *mut FileSystem, file: *mut FileHan
 ///
 /// @param callback A callback for each path found.
 ///
-/// @return The number of paths found.
+/// @return The number of paths found. Failures are reported as 0 as well - use FindEx to detect them.
 #[no_mangle]
 pub unsafe extern "C" fn Find(
     file_system: *mut FileSystem,
     path: *const c_char,
     callback: unsafe extern "C" fn(*const c_char),
 ) -> c_int {
-    let file_system = match file_system.as_ref() {
-        Some(file_system) => file_system,
-        None => {
-            return 0;
-        }
-    };
-    let path = match Environment::parse_str(path) {
-        Ok(path) => path,
-        _ => {
-            return 0;
-        }
-    };
+    match FindEx(file_system, path, callback, null_mut()) {
+        -1 => 0,
+        num_paths => num_paths,
+    }
+}
 
-    let paths: Vec<CString> = match file_system.0.find(path).map(|paths| {
-        paths
+/// Search for a specific file(s), reporting failures.
+///
+/// @param file_system A pointer to the virtual file system.
+///
+/// @param path The path supporting glob-like placeholders.
+///
+/// @param callback A callback for each path found.
+///
+/// @param status Contains the error code of the failure if and only if the return value is -1. Setting this value to nullptr is safe and will not save the error code.
+///
+/// @return The number of paths found or -1 on failure.
+#[no_mangle]
+pub unsafe extern "C" fn FindEx(
+    file_system: *mut FileSystem,
+    path: *const c_char,
+    callback: unsafe extern "C" fn(*const c_char),
+    status: *mut *mut Status,
+) -> c_int {
+    let paths = || -> Result<Vec<CString>, String> {
+        let file_system = file_system
+            .as_ref()
+            .ok_or_else(|| String::from("File system not specified"))?;
+        let path = Environment::parse_str(path)?;
+        file_system
+            .0
+            .find(path)
+            .map_err(|error| format!("{}", error))?
             .into_iter()
-            .map(|path| CString::new(path).expect("NULL found"))
+            .map(|path| {
+                // Only a corrupt database contains paths with NUL bytes, which C cannot represent.
+                CString::new(path).map_err(|_| String::from("Path contains NUL bytes"))
+            })
             .collect()
-    }) {
+    };
+
+    let paths = match Environment::guard(paths, Err) {
         Ok(paths) => paths,
-        _ => {
-            return 0;
+        Err(error) => {
+            Environment::from(status).report(error);
+            return -1;
         }
     };
 
@@ -381,3 +671,65 @@ pub unsafe extern "C" fn Find(
 
     paths.len() as c_int
 }
+
+#[cfg(test)]
+mod tests {
+    use std::ffi::CStr;
+    use std::ptr::null_mut;
+
+    use super::{DestroyStatus, Environment, GetMessage, Status};
+
+    #[test]
+    fn test_guard() {
+        assert_eq!(Environment::guard(|| 42, |_| -1), 42);
+
+        // Panics are converted into the error code, keeping their message
+        let mut message = String::new();
+        let code = Environment::guard(
+            || -> i32 { panic!("broken invariant") },
+            |reason| {
+                message = reason;
+                -1
+            },
+        );
+        assert_eq!(code, -1);
+        assert_eq!(message, "Internal error: broken invariant");
+        assert_eq!(
+            Environment::guard(
+                || -> i32 { panic!("{}", 42) },
+                |reason| {
+                    assert_eq!(reason, "Internal error: 42");
+                    -1
+                }
+            ),
+            -1
+        );
+
+        // Executed bodies report the panic as status
+        let mut status: *mut Status = null_mut();
+        let result = Environment::from(&mut status as *mut _)
+            .execute(|| -> Result<i32, String> { panic!("broken invariant") });
+        assert!(result.is_null());
+        assert!(!status.is_null());
+        unsafe {
+            assert_eq!(
+                CStr::from_ptr(GetMessage(status)).to_str(),
+                Ok("Internal error: broken invariant")
+            );
+            DestroyStatus(status);
+        }
+    }
+
+    #[test]
+    fn test_status_with_nul() {
+        let status = Environment::create_status("invalid\0message");
+        assert!(!status.is_null());
+        unsafe {
+            assert_eq!(
+                CStr::from_ptr(GetMessage(status)).to_str(),
+                Ok("invalid\u{FFFD}message")
+            );
+            DestroyStatus(status);
+        }
+    }
+}
@@ -66,6 +66,10 @@ fn test_io(inner_path: &str, data: &[u8], chunk_size: i32) {
             unsafe { matryoshka::GetSize(file_system, file_handle) },
             data.len() as i32
         );
+        assert_eq!(
+            unsafe { matryoshka::GetSize64(file_system, file_handle) },
+            data.len() as i64
+        );
 
         // Pull file from virtual file system
         let mut output_path = tmp_dir.path().to_path_buf();
@@ -91,5 +95,400 @@ fn test_io(inner_path: &str, data: &[u8], chunk_size: i32) {
         // Test delete
         assert_eq!(unsafe { matryoshka::Delete(file_system, file_handle) }, 1);
         assert_eq!(unsafe { matryoshka::Delete(file_system, file_handle) }, 0);
+        assert_eq!(
+            unsafe { matryoshka::GetSize64(file_system, file_handle) },
+            -1
+        );
+    }
+}
+
+#[test_case(&[], -1; "0 bytes")]
+#[test_case(&[42u8, 43, 44], 2; "3 bytes")]
+fn test_push_ex(data: &[u8], chunk_size: i32) {
+    let database_path = CString::new(":memory:").expect("Valid database path");
+    let inner_path = CString::new("folder/file").expect("Valid inner path");
+    let file_system = unsafe { matryoshka::Load(database_path.as_ptr(), null_mut()) };
+    assert!(!file_system.is_null());
+
+    let tmp_dir = tempfile::TempDir::new().expect("Unable to create temporary directory");
+    let input_path = tmp_dir.path().join("input.file");
+    std::fs::write(&input_path, data).expect("Writing input file failed");
+    let input_path =
+        CString::new(input_path.to_str().expect("Invalid TMP path")).expect("NULL in path");
+
+    let mut size: std::os::raw::c_longlong = -1;
+    let file_handle = unsafe {
+        matryoshka::PushEx(
+            file_system,
+            inner_path.as_ptr(),
+            input_path.as_ptr(),
+            chunk_size,
+            &mut size,
+            null_mut(),
+        )
+    };
+    assert!(!file_handle.is_null(), "Push failed");
+    assert_eq!(size, data.len() as i64);
+
+    // Failures leave the size untouched
+    let mut status: *mut Status = null_mut();
+    size = -1;
+    let duplicate = unsafe {
+        matryoshka::PushEx(
+            file_system,
+            inner_path.as_ptr(),
+            input_path.as_ptr(),
+            chunk_size,
+            &mut size,
+            &mut status,
+        )
+    };
+    assert!(duplicate.is_null());
+    assert_ne!(status, null_mut());
+    assert_eq!(size, -1);
+
+    unsafe {
+        matryoshka::DestroyStatus(status);
+        matryoshka::DestroyFileHandle(file_handle);
+        matryoshka::DestroyFileSystem(file_system);
+    }
+}
+
+#[test]
+fn test_push_append() {
+    let database_path = CString::new(":memory:").expect("Valid database path");
+    let inner_path = CString::new("folder/file").expect("Valid inner path");
+    let file_system = unsafe { matryoshka::Load(database_path.as_ptr(), null_mut()) };
+    assert!(!file_system.is_null());
+
+    // Push an empty file
+    let tmp_dir = tempfile::TempDir::new().expect("Unable to create temporary directory");
+    let input_path = tmp_dir.path().join("input.file");
+    std::fs::File::create(&input_path).expect("Creating input file failed");
+    let input_path =
+        CString::new(input_path.to_str().expect("Invalid TMP path")).expect("NULL in path");
+    let file_handle = unsafe {
+        matryoshka::Push(
+            file_system,
+            inner_path.as_ptr(),
+            input_path.as_ptr(),
+            2,
+            null_mut(),
+        )
+    };
+    assert!(!file_handle.is_null(), "Push failed");
+
+    // Append twice
+    let mut status: *mut Status = null_mut();
+    for (data, size) in [(&[1u8, 2, 3][..], 3i64), (&[4u8, 5][..], 5i64)].iter() {
+        assert_eq!(
+            unsafe {
+                matryoshka::PushAppend(
+                    file_system,
+                    file_handle,
+                    data.as_ptr(),
+                    data.len() as i32,
+                    &mut status,
+                )
+            },
+            *size
+        );
+        assert_eq!(status, null_mut());
+    }
+    assert_eq!(
+        unsafe { matryoshka::PushAppend(file_system, file_handle, null_mut(), 1, &mut status) },
+        -1
+    );
+    assert_ne!(status, null_mut());
+
+    // Pull and verify the final content
+    let output_path = tmp_dir.path().join("output.file");
+    let pull_status = unsafe {
+        let output_path =
+            CString::new(output_path.to_str().expect("Invalid TMP path")).expect("NULL in path");
+        matryoshka::Pull(file_system, file_handle, output_path.as_ptr())
+    };
+    assert!(pull_status.is_null(), "Pull failed");
+    assert_eq!(
+        std::fs::read(output_path).expect("Reading local file failed"),
+        vec![1u8, 2, 3, 4, 5]
+    );
+
+    unsafe {
+        matryoshka::DestroyStatus(status);
+        matryoshka::DestroyFileHandle(file_handle);
+        matryoshka::DestroyFileSystem(file_system);
+    }
+}
+
+#[test]
+fn test_read_into() {
+    let database_path = CString::new(":memory:").expect("Valid database path");
+    let inner_path = CString::new("folder/file").expect("Valid inner path");
+    let file_system = unsafe { matryoshka::Load(database_path.as_ptr(), null_mut()) };
+    assert!(!file_system.is_null());
+
+    let tmp_dir = tempfile::TempDir::new().expect("Unable to create temporary directory");
+    let input_path = tmp_dir.path().join("input.file");
+    std::fs::write(&input_path, &[1u8, 2, 3, 4, 5]).expect("Writing input file failed");
+    let input_path =
+        CString::new(input_path.to_str().expect("Invalid TMP path")).expect("NULL in path");
+    let file_handle = unsafe {
+        matryoshka::Push(
+            file_system,
+            inner_path.as_ptr(),
+            input_path.as_ptr(),
+            2,
+            null_mut(),
+        )
+    };
+    assert!(!file_handle.is_null(), "Push failed");
+
+    // Reads are limited by both the buffer and the end of the file
+    let mut status: *mut Status = null_mut();
+    let mut buffer = [0u8; 3];
+    for (offset, expected) in [
+        (0, &[1u8, 2, 3][..]),
+        (3, &[4u8, 5][..]),
+        (5, &[][..]),
+        (9, &[][..]),
+    ]
+    .iter()
+    {
+        let num_bytes = unsafe {
+            matryoshka::ReadInto(
+                file_system,
+                file_handle,
+                *offset,
+                buffer.as_mut_ptr(),
+                buffer.len() as i32,
+                &mut status,
+            )
+        };
+        assert_eq!(num_bytes, expected.len() as i32);
+        assert_eq!(&buffer[..expected.len()], *expected);
+        assert_eq!(status, null_mut());
+    }
+
+    assert_eq!(
+        unsafe {
+            matryoshka::ReadInto(
+                file_system,
+                file_handle,
+                -1,
+                buffer.as_mut_ptr(),
+                buffer.len() as i32,
+                &mut status,
+            )
+        },
+        -1
+    );
+    assert_ne!(status, null_mut());
+
+    unsafe {
+        matryoshka::DestroyStatus(status);
+        matryoshka::DestroyFileHandle(file_handle);
+        matryoshka::DestroyFileSystem(file_system);
+    }
+}
+
+#[test]
+fn test_rename() {
+    let database_path = CString::new(":memory:").expect("Valid database path");
+    let file_system = unsafe { matryoshka::Load(database_path.as_ptr(), null_mut()) };
+    assert!(!file_system.is_null());
+
+    let tmp_dir = tempfile::TempDir::new().expect("Unable to create temporary directory");
+    let input_path = tmp_dir.path().join("input.file");
+    std::fs::write(&input_path, &[42u8, 43, 44]).expect("Writing input file failed");
+    let input_path =
+        CString::new(input_path.to_str().expect("Invalid TMP path")).expect("NULL in path");
+
+    let paths: Vec<CString> = ["old", "new", "other"]
+        .iter()
+        .map(|path| CString::new(*path).expect("Valid inner path"))
+        .collect();
+    for path in [&paths[0], &paths[2]].iter() {
+        let file_handle = unsafe {
+            matryoshka::Push(
+                file_system,
+                path.as_ptr(),
+                input_path.as_ptr(),
+                -1,
+                null_mut(),
+            )
+        };
+        assert!(!file_handle.is_null(), "Push failed");
+        unsafe { matryoshka::DestroyFileHandle(file_handle) };
+    }
+
+    // Renaming onto an existing file fails
+    let mut status: *mut Status = null_mut();
+    assert_eq!(
+        unsafe {
+            matryoshka::Rename(
+                file_system,
+                paths[0].as_ptr(),
+                paths[2].as_ptr(),
+                &mut status,
+            )
+        },
+        0
+    );
+    assert_ne!(status, null_mut());
+    unsafe { matryoshka::DestroyStatus(status) };
+    status = null_mut();
+
+    assert_eq!(
+        unsafe {
+            matryoshka::Rename(
+                file_system,
+                paths[0].as_ptr(),
+                paths[1].as_ptr(),
+                &mut status,
+            )
+        },
+        1
+    );
+    assert_eq!(status, null_mut());
+
+    // Only the new path resolves
+    let old_handle = unsafe { matryoshka::Open(file_system, paths[0].as_ptr(), &mut status) };
+    assert!(old_handle.is_null());
+    assert_ne!(status, null_mut());
+    let new_handle = unsafe { matryoshka::Open(file_system, paths[1].as_ptr(), null_mut()) };
+    assert!(!new_handle.is_null());
+    assert_eq!(unsafe { matryoshka::GetSize(file_system, new_handle) }, 3);
+
+    // Renaming a missing file fails
+    assert_eq!(
+        unsafe {
+            matryoshka::Rename(
+                file_system,
+                paths[0].as_ptr(),
+                paths[1].as_ptr(),
+                null_mut(),
+            )
+        },
+        0
+    );
+
+    unsafe {
+        matryoshka::DestroyStatus(status);
+        matryoshka::DestroyFileHandle(new_handle);
+        matryoshka::DestroyFileSystem(file_system);
+    }
+}
+
+#[test]
+fn test_find_nul() {
+    let tmp_dir = tempfile::TempDir::new().expect("Unable to create temporary directory");
+    let database_path = tmp_dir.path().join("database.sqlite");
+    let database_path_raw =
+        CString::new(database_path.to_str().expect("Invalid TMP path")).expect("NULL in path");
+    let pattern = CString::new("*").expect("Valid pattern");
+
+    let file_system = unsafe { matryoshka::Load(database_path_raw.as_ptr(), null_mut()) };
+    assert!(!file_system.is_null());
+
+    // Insert a path C cannot represent behind the back of the file system
+    matryoshka_sqlite::Database::open(&database_path)
+        .expect("Opening database failed")
+        .execute(
+            "INSERT INTO Matryoshka_Meta_0 (path, type, chunk_size) VALUES ('a' || char(0) || 'b', 1, 1)",
+            [],
+        )
+        .expect("Inserting path failed");
+
+    let mut status: *mut Status = null_mut();
+    assert_eq!(
+        unsafe { matryoshka::FindEx(file_system, pattern.as_ptr(), ignore_path, &mut status) },
+        -1
+    );
+    assert_ne!(status, null_mut());
+    assert_eq!(
+        unsafe { matryoshka::Find(file_system, pattern.as_ptr(), ignore_path) },
+        0
+    );
+
+    unsafe {
+        matryoshka::DestroyStatus(status);
+        matryoshka::DestroyFileSystem(file_system);
+    }
+}
+
+#[test]
+fn test_find_blob_path() {
+    let tmp_dir = tempfile::TempDir::new().expect("Unable to create temporary directory");
+    let database_path = tmp_dir.path().join("database.sqlite");
+    let database_path_raw =
+        CString::new(database_path.to_str().expect("Invalid TMP path")).expect("NULL in path");
+    let pattern = CString::new("*").expect("Valid pattern");
+
+    let file_system = unsafe { matryoshka::Load(database_path_raw.as_ptr(), null_mut()) };
+    assert!(!file_system.is_null());
+
+    // A path stored as BLOB cannot be read as text, which must be reported instead of aborting
+    matryoshka_sqlite::Database::open(&database_path)
+        .expect("Opening database failed")
+        .execute(
+            "INSERT INTO Matryoshka_Meta_0 (path, type, chunk_size) VALUES (X'6162', 1, 1)",
+            [],
+        )
+        .expect("Inserting path failed");
+
+    let mut status: *mut Status = null_mut();
+    assert_eq!(
+        unsafe { matryoshka::FindEx(file_system, pattern.as_ptr(), ignore_path, &mut status) },
+        -1
+    );
+    assert_ne!(status, null_mut());
+
+    unsafe {
+        matryoshka::DestroyStatus(status);
+        matryoshka::DestroyFileSystem(file_system);
+    }
+}
+
+unsafe extern "C" fn ignore_path(_path: *const std::os::raw::c_char) {}
+
+#[test]
+fn test_find_failure() {
+    let tmp_dir = tempfile::TempDir::new().expect("Unable to create temporary directory");
+    let database_path = tmp_dir.path().join("database.sqlite");
+    let database_path_raw =
+        CString::new(database_path.to_str().expect("Invalid TMP path")).expect("NULL in path");
+    let pattern = CString::new("*").expect("Valid pattern");
+
+    let file_system = unsafe { matryoshka::Load(database_path_raw.as_ptr(), null_mut()) };
+    assert!(!file_system.is_null());
+
+    // Zero matches are no failure
+    let mut status: *mut Status = null_mut();
+    assert_eq!(
+        unsafe { matryoshka::FindEx(file_system, pattern.as_ptr(), ignore_path, &mut status) },
+        0
+    );
+    assert_eq!(status, null_mut());
+
+    // Corrupt the file system behind its back
+    matryoshka_sqlite::Database::open(&database_path)
+        .expect("Opening database failed")
+        .execute("DROP TABLE Matryoshka_Meta_0", [])
+        .expect("Dropping table failed");
+
+    assert_eq!(
+        unsafe { matryoshka::FindEx(file_system, pattern.as_ptr(), ignore_path, &mut status) },
+        -1
+    );
+    assert_ne!(status, null_mut());
+    assert_eq!(
+        unsafe { matryoshka::Find(file_system, pattern.as_ptr(), ignore_path) },
+        0
+    );
+
+    unsafe {
+        matryoshka::DestroyStatus(status);
+        matryoshka::DestroyFileSystem(file_system);
     }
 }
@@ -2,21 +2,39 @@ use std::convert::TryInto;
 use std::fmt::{Debug, Display, Formatter, Result as FmtResult};
 use std::io::{Error as IoError, ErrorKind};
 
-use rusqlite::Error as WrappedError;
+use rusqlite::{Error as WrappedError, ErrorCode};
 
-use super::DatabaseError;
+use super::{DatabaseError, PathError};
 
 /// An error occurring during the creation of a file in the virtual file system.
 #[derive(Debug, PartialEq)]
 pub enum CreationError {
     /// A file already exists under this path.
     FileExists,
+    /// The normalized path is outside of the directory it was restricted to.
+    OutsideScope,
+    /// The path is not acceptable for a new entry.
+    InvalidPath(PathError),
     /// The data source raised an error.
     SourceError(ErrorKind),
+    /// A chunk of the given size exceeds the maximal size of a BLOB allowed by SQLite.
+    ChunkTooLarge(usize),
     /// A general database error from SQLite.
     DatabaseError(DatabaseError),
 }
 
+impl CreationError {
+    /// Convert an error raised while storing a chunk of the given size.
+    pub(crate) fn from_chunk_error(error: WrappedError, chunk_size: usize) -> Self {
+        match error {
+            WrappedError::SqliteFailure(error, _) if error.code == ErrorCode::TooBig => {
+                CreationError::ChunkTooLarge(chunk_size)
+            }
+            error => error.into(),
+        }
+    }
+}
+
 impl super::Error for CreationError {}
 
 impl From<WrappedError> for CreationError {
@@ -25,6 +43,12 @@ impl From<WrappedError> for CreationError {
     }
 }
 
+impl From<PathError> for CreationError {
+    fn from(error: PathError) -> Self {
+        CreationError::InvalidPath(error)
+    }
+}
+
 impl From<IoError> for CreationError {
     fn from(error: IoError) -> Self {
         CreationError::SourceError(error.kind())
@@ -36,6 +60,15 @@ impl Display for CreationError {
         f.write_str("Error during file creation: ")?;
         match self {
             CreationError::FileExists => write!(f, "File does already exists"),
+            CreationError::OutsideScope => {
+                write!(f, "The path escapes the directory it is restricted to")
+            }
+            CreationError::InvalidPath(error) => write!(f, "{}", error),
+            CreationError::ChunkTooLarge(size) => write!(
+                f,
+                "A chunk of {} bytes exceeds the limits of the underlying database",
+                size
+            ),
             CreationError::SourceError(error) => {
                 write!(f, "The data source failed ('{:?}')", error)
             }
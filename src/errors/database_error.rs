@@ -16,6 +16,22 @@ impl DatabaseError {
     pub const MISSING_MESSAGE: &'static str = "<Unknown SQLite error>";
     /// Panic message returned if this library does not handle and logic error correctly.
     pub const LOGIC_ERROR_MESSAGE: &'static str = "Logic error during database access";
+
+    /// Create an error for a database file which could not be opened, i.e. due to a path SQLite cannot represent.
+    pub(crate) fn cannot_open(message: String) -> Self {
+        Self {
+            error: SQLiteError::CannotOpen,
+            message: Some(message),
+        }
+    }
+
+    /// Check whether the error is transient, i.e. retrying the operation later might succeed.
+    pub fn is_transient(&self) -> bool {
+        matches!(
+            self.error,
+            SQLiteError::DatabaseBusy | SQLiteError::DatabaseLocked
+        )
+    }
 }
 
 impl Debug for DatabaseError {
@@ -45,7 +61,66 @@ impl TryFrom<WrappedError> for DatabaseError {
                 error: error.code,
                 message,
             }),
+            // A corrupt or externally modified database may contain values of unexpected types.
+            error @ WrappedError::InvalidColumnType(..)
+            | error @ WrappedError::FromSqlConversionFailure(..)
+            | error @ WrappedError::IntegralValueOutOfRange(..) => Ok(Self {
+                error: SQLiteError::TypeMismatch,
+                message: Some(error.to_string()),
+            }),
+            // Names passed to SQLite, i.e. of functions, must not contain NUL bytes.
+            error @ WrappedError::NulError(..) => Ok(Self {
+                error: SQLiteError::ApiMisuse,
+                message: Some(error.to_string()),
+            }),
             _ => Err(()),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::convert::TryInto;
+    use std::ffi::CString;
+
+    use rusqlite::{ffi, types::Type};
+
+    use super::{DatabaseError, SQLiteError, WrappedError};
+
+    fn create_error(code: i32) -> DatabaseError {
+        WrappedError::SqliteFailure(ffi::Error::new(code), None)
+            .try_into()
+            .expect("Conversion of SQLite error failed")
+    }
+
+    #[test]
+    fn test_transient() {
+        assert!(create_error(ffi::SQLITE_BUSY).is_transient());
+        assert!(create_error(ffi::SQLITE_LOCKED).is_transient());
+        assert!(!create_error(ffi::SQLITE_CONSTRAINT).is_transient());
+        assert!(!create_error(ffi::SQLITE_CORRUPT).is_transient());
+    }
+
+    #[test]
+    fn test_type_mismatch() {
+        let error: DatabaseError =
+            WrappedError::InvalidColumnType(0, String::from("path"), Type::Blob)
+                .try_into()
+                .expect("Conversion of type error failed");
+        assert_eq!(error.error, SQLiteError::TypeMismatch);
+        assert!(error.message.is_some());
+
+        let error: Result<DatabaseError, ()> = WrappedError::QueryReturnedNoRows.try_into();
+        assert!(error.is_err());
+    }
+
+    #[test]
+    fn test_nul_error() {
+        let nul_error = CString::new("bad\0name").expect_err("NUL byte was accepted");
+        let error: DatabaseError = WrappedError::NulError(nul_error)
+            .try_into()
+            .expect("Conversion of NUL error failed");
+        assert_eq!(error.error, SQLiteError::ApiMisuse);
+        assert!(error.message.is_some());
+    }
+}
@@ -10,6 +10,12 @@ use super::DatabaseError;
 pub enum LoadingError {
     /// The requested file is not found in the virtual file system.
     FileNotFound,
+    /// Resolving the path required following too many symbolic links, most likely due to a cycle.
+    TooManySymlinks,
+    /// The file is opened exclusively elsewhere.
+    FileLocked,
+    /// Ignoring the case, the path matches multiple files but none exactly.
+    AmbiguousPath,
     /// A general database error from SQLite.
     DatabaseError(DatabaseError),
 }
@@ -27,6 +33,16 @@ impl Display for LoadingError {
         f.write_str("Error during file loading: ")?;
         match self {
             LoadingError::FileNotFound => write!(f, "The requested file does not exist"),
+            LoadingError::TooManySymlinks => {
+                write!(
+                    f,
+                    "Too many levels of symbolic links while resolving the path"
+                )
+            }
+            LoadingError::FileLocked => write!(f, "The file is opened exclusively elsewhere"),
+            LoadingError::AmbiguousPath => {
+                write!(f, "The path matches multiple files differing in case")
+            }
             LoadingError::DatabaseError(error) => {
                 write!(f, "The underlying database failed ('{}')", error)
             }
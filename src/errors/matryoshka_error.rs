@@ -0,0 +1,137 @@
+use std::fmt::{Debug, Display, Formatter, Result as FmtResult};
+
+use super::{
+    CreationError, DatabaseError, FileSystemError, LoadingError, ReadError, TransferError,
+};
+
+/// Any error occurring while accessing the virtual file system.
+///
+/// This allows returning a single error type while still being able to match the specific cause.
+#[derive(Debug, PartialEq)]
+pub enum MatryoshkaError {
+    /// An error during the access of the virtual file system.
+    FileSystem(FileSystemError),
+    /// An error during the creation of a file.
+    Creation(CreationError),
+    /// An error during the loading of a file.
+    Loading(LoadingError),
+    /// An error during the reading of a file.
+    Read(ReadError),
+    /// An error during the transfer of a file between file systems.
+    Transfer(TransferError),
+    /// A general database error from SQLite.
+    Database(DatabaseError),
+}
+
+impl super::Error for MatryoshkaError {}
+
+impl From<FileSystemError> for MatryoshkaError {
+    fn from(error: FileSystemError) -> Self {
+        MatryoshkaError::FileSystem(error)
+    }
+}
+
+impl From<CreationError> for MatryoshkaError {
+    fn from(error: CreationError) -> Self {
+        MatryoshkaError::Creation(error)
+    }
+}
+
+impl From<LoadingError> for MatryoshkaError {
+    fn from(error: LoadingError) -> Self {
+        MatryoshkaError::Loading(error)
+    }
+}
+
+impl From<ReadError> for MatryoshkaError {
+    fn from(error: ReadError) -> Self {
+        MatryoshkaError::Read(error)
+    }
+}
+
+impl From<TransferError> for MatryoshkaError {
+    fn from(error: TransferError) -> Self {
+        MatryoshkaError::Transfer(error)
+    }
+}
+
+impl From<DatabaseError> for MatryoshkaError {
+    fn from(error: DatabaseError) -> Self {
+        MatryoshkaError::Database(error)
+    }
+}
+
+impl Display for MatryoshkaError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        match self {
+            MatryoshkaError::FileSystem(error) => Display::fmt(error, f),
+            MatryoshkaError::Creation(error) => Display::fmt(error, f),
+            MatryoshkaError::Loading(error) => Display::fmt(error, f),
+            MatryoshkaError::Read(error) => Display::fmt(error, f),
+            MatryoshkaError::Transfer(error) => Display::fmt(error, f),
+            MatryoshkaError::Database(error) => Display::fmt(error, f),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::convert::TryInto;
+
+    use rusqlite::{ffi, Error as WrappedError};
+
+    use super::super::Error;
+    use super::{
+        CreationError, DatabaseError, FileSystemError, LoadingError, MatryoshkaError, ReadError,
+        TransferError,
+    };
+
+    #[test]
+    fn test_conversion() {
+        let error: MatryoshkaError = FileSystemError::NoFileSystem.into();
+        assert!(matches!(
+            error,
+            MatryoshkaError::FileSystem(FileSystemError::NoFileSystem)
+        ));
+
+        let error: MatryoshkaError = CreationError::FileExists.into();
+        assert!(matches!(
+            error,
+            MatryoshkaError::Creation(CreationError::FileExists)
+        ));
+
+        let error: MatryoshkaError = LoadingError::FileNotFound.into();
+        assert!(matches!(
+            error,
+            MatryoshkaError::Loading(LoadingError::FileNotFound)
+        ));
+
+        let error: MatryoshkaError = ReadError::OutOfBounds.into();
+        assert!(matches!(
+            error,
+            MatryoshkaError::Read(ReadError::OutOfBounds)
+        ));
+
+        let error: MatryoshkaError = TransferError::Creation(CreationError::FileExists).into();
+        assert!(matches!(
+            error,
+            MatryoshkaError::Transfer(TransferError::Creation(CreationError::FileExists))
+        ));
+
+        let database_error: DatabaseError =
+            WrappedError::SqliteFailure(ffi::Error::new(ffi::SQLITE_BUSY), None)
+                .try_into()
+                .expect("Conversion of SQLite error failed");
+        let error: MatryoshkaError = database_error.into();
+        assert!(matches!(error, MatryoshkaError::Database(_)));
+    }
+
+    #[test]
+    fn test_message() {
+        let error: MatryoshkaError = LoadingError::FileNotFound.into();
+        assert_eq!(
+            error.error_message(),
+            LoadingError::FileNotFound.error_message()
+        );
+    }
+}
@@ -7,13 +7,21 @@ mod database_error;
 mod creation_error;
 mod file_system_error;
 mod loading_error;
+mod matryoshka_error;
+mod path_error;
+mod process_error;
 mod read_error;
+mod transfer_error;
 
 pub use self::creation_error::CreationError;
 pub use self::database_error::DatabaseError;
 pub use self::file_system_error::FileSystemError;
 pub use self::loading_error::LoadingError;
+pub use self::matryoshka_error::MatryoshkaError;
+pub use self::path_error::PathError;
+pub use self::process_error::ProcessError;
 pub use self::read_error::ReadError;
+pub use self::transfer_error::TransferError;
 
 /// An error occurring while accessing the virtual file system.
 pub trait Error: PartialEq + Debug + Display {
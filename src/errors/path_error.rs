@@ -0,0 +1,29 @@
+use std::fmt::{Debug, Display, Formatter, Result as FmtResult};
+
+/// An error indicating that a path is not acceptable for a new entry in the virtual file system.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum PathError {
+    /// The path is empty after normalization and refers to the root.
+    Empty,
+    /// The normalized path exceeds the given maximal length in bytes.
+    TooLong(usize),
+    /// The path contains a control character like NUL.
+    ForbiddenCharacter(char),
+}
+
+impl super::Error for PathError {}
+
+impl Display for PathError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        f.write_str("Invalid path: ")?;
+        match self {
+            PathError::Empty => write!(f, "The path does not name an entry"),
+            PathError::TooLong(max_length) => {
+                write!(f, "The path is longer than {} bytes", max_length)
+            }
+            PathError::ForbiddenCharacter(value) => {
+                write!(f, "The path contains the forbidden character {:?}", value)
+            }
+        }
+    }
+}
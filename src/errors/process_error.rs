@@ -0,0 +1,32 @@
+use std::fmt::{Debug, Display, Formatter, Result as FmtResult};
+
+use super::ReadError;
+
+/// An error occurring while processing the content of a file with a user-supplied function.
+#[derive(Debug, PartialEq)]
+pub enum ProcessError<E> {
+    /// The content could not be read.
+    Read(ReadError),
+    /// The function processing the content failed.
+    Callback(E),
+}
+
+impl<E: PartialEq + Debug + Display> super::Error for ProcessError<E> {}
+
+impl<E> From<ReadError> for ProcessError<E> {
+    fn from(error: ReadError) -> Self {
+        ProcessError::Read(error)
+    }
+}
+
+impl<E: Display> Display for ProcessError<E> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        f.write_str("Error during file processing: ")?;
+        match self {
+            ProcessError::Read(error) => write!(f, "Reading the content failed ('{}')", error),
+            ProcessError::Callback(error) => {
+                write!(f, "Processing the content failed ('{}')", error)
+            }
+        }
+    }
+}
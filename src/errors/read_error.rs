@@ -2,7 +2,7 @@ use std::convert::TryInto;
 use std::fmt::{Debug, Display, Formatter, Result as FmtResult};
 use std::io::{Error as IoError, ErrorKind};
 
-use rusqlite::Error as WrappedError;
+use rusqlite::{Error as WrappedError, ErrorCode};
 
 use super::DatabaseError;
 
@@ -13,6 +13,12 @@ pub enum ReadError {
     OutOfBounds,
     /// The size of the indices or virtual files extend the bounds imposed by SQLite.
     FileSystemLimits,
+    /// The content of the file is not valid UTF-8.
+    InvalidUtf8,
+    /// The read was interrupted using an interrupt handle.
+    Interrupted,
+    /// The read did not finish before its deadline.
+    TimedOut,
     /// The sink written to raised an error.
     SinkError(ErrorKind),
     /// A general database error from SQLite.
@@ -23,7 +29,16 @@ impl super::Error for ReadError {}
 
 impl From<WrappedError> for ReadError {
     fn from(error: WrappedError) -> Self {
-        ReadError::DatabaseError(error.try_into().expect(DatabaseError::LOGIC_ERROR_MESSAGE))
+        match error {
+            WrappedError::SqliteFailure(error, _)
+                if error.code == ErrorCode::OperationInterrupted =>
+            {
+                ReadError::Interrupted
+            }
+            error => ReadError::DatabaseError(
+                error.try_into().expect(DatabaseError::LOGIC_ERROR_MESSAGE),
+            ),
+        }
     }
 }
 
@@ -42,6 +57,9 @@ impl Display for ReadError {
                 f,
                 "The underlying database does not allow files of such size"
             ),
+            ReadError::InvalidUtf8 => write!(f, "The content is not valid UTF-8"),
+            ReadError::Interrupted => write!(f, "The read was interrupted"),
+            ReadError::TimedOut => write!(f, "The read did not finish in time"),
             ReadError::SinkError(error) => write!(f, "The data destination failed ('{:?}')", error),
             ReadError::DatabaseError(error) => {
                 write!(f, "The underlying database failed ('{}')", error)
@@ -0,0 +1,45 @@
+use std::fmt::{Debug, Display, Formatter, Result as FmtResult};
+
+use super::{CreationError, DatabaseError, LoadingError};
+
+/// An error occurring during the transfer of a file between two virtual file systems.
+#[derive(Debug, PartialEq)]
+pub enum TransferError {
+    /// The file could not be loaded from the source.
+    Loading(LoadingError),
+    /// The file could not be created in the destination. The source is left untouched.
+    Creation(CreationError),
+    /// The file was copied to the destination, but could not be deleted from the source.
+    Deletion(DatabaseError),
+}
+
+impl super::Error for TransferError {}
+
+impl From<LoadingError> for TransferError {
+    fn from(error: LoadingError) -> Self {
+        TransferError::Loading(error)
+    }
+}
+
+impl From<CreationError> for TransferError {
+    fn from(error: CreationError) -> Self {
+        TransferError::Creation(error)
+    }
+}
+
+impl Display for TransferError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        f.write_str("Error during file transfer: ")?;
+        match self {
+            TransferError::Loading(error) => write!(f, "Loading the source failed ('{}')", error),
+            TransferError::Creation(error) => {
+                write!(f, "Creating the destination failed ('{}')", error)
+            }
+            TransferError::Deletion(error) => write!(
+                f,
+                "The file was copied, but deleting the source failed ('{}')",
+                error
+            ),
+        }
+    }
+}
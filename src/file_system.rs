@@ -1,17 +1,39 @@
 //! The "safe and rusty" implementation of the virtual file system.
 
 use std::borrow::BorrowMut;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
 use std::convert::{TryFrom, TryInto};
+use std::fmt::{Debug, Formatter, Result as FmtResult};
+use std::hash::Hasher;
 use std::io::{Error as IoError, ErrorKind, Read, Result as IoResult, Write};
+use std::ops::{Deref, DerefMut};
+use std::panic::UnwindSafe;
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use sha2::{Digest, Sha256};
 
 use rusqlite::{
-    limits::Limit, params, Connection as Database, DatabaseName, Error as RusqliteError, ErrorCode,
-    OptionalExtension,
+    ffi,
+    functions::{Context, FunctionFlags},
+    limits::Limit,
+    named_params, params, params_from_iter,
+    types::ToSql,
+    Connection as Database, DatabaseName, Error as RusqliteError, ErrorCode, InterruptHandle,
+    OpenFlags, OptionalExtension, Statement, Transaction,
 };
 
+#[cfg(feature = "serialize")]
+use super::util::{deserialize, serialize};
 use super::{
-    errors::{CreationError, DatabaseError, Error, FileSystemError, LoadingError, ReadError},
-    util::{Availability, MetaData, VirtualPath},
+    errors::{
+        CreationError, DatabaseError, Error, FileSystemError, LoadingError, MatryoshkaError,
+        PathError, ProcessError, ReadError, TransferError,
+    },
+    util::{
+        compress, decompress, Availability, CheckpointMode, ChunkLayout, ChunkStats, Glob,
+        LoadOptions, MetaData, PathCollation, PathKind, StorageStats, VirtualPath,
+    },
     Handle,
 };
 
@@ -22,42 +44,256 @@ mod constants {
     pub const MATRYOSHKA_TABLE: &str = "Matryoshka_Meta_0";
     // One day, that might be derived directly from a const function.
     pub const DATA_TABLE: &str = "Matryoshka_Data";
+    pub const TRASH_TABLE: &str = "Matryoshka_Trash_0";
 
     pub const FILE_ID: u32 = 1;
+    pub const SYMLINK_ID: u32 = 3;
+    pub const TRASH_ID: u32 = 4;
+
+    // Bits of the flags column describing how a file is stored.
+    pub const COMPRESSED_FLAG: i64 = 1;
+    pub const LOCKED_FLAG: i64 = 2;
+
+    // Symbolic links are followed up to this depth before assuming a cycle.
+    pub const MAX_SYMLINK_DEPTH: usize = 32;
 
     pub const DEFAULT_BYTE_BLOB_SIZE: usize = 33554432; // 32MB
+    pub const DEFAULT_READ_BUFFER_LIMIT: usize = 1048576; // 1MB
+
+    // Additional space relative to the payload reserved for pages, indices, and the journal of SQLite.
+    pub const CAPACITY_MARGIN_DIVISOR: u64 = 10;
 
+    // The modification time in milliseconds since the UNIX epoch is set by SQLite, as it is missing in older file systems.
+    const SQL_NOW: &str = "CAST((julianday('now') - 2440587.5) * 86400000 AS INTEGER)";
+    const META_COLUMNS: &str = formatcp!(
+        "type INTEGER, flags INTEGER, chunk_size INTEGER NOT NULL, modified_at INTEGER DEFAULT ({})",
+        SQL_NOW
+    );
     pub const SQL_CREATE_META: &str = formatcp!(
-        "CREATE TABLE {} (id INTEGER PRIMARY KEY, path TEXT UNIQUE NOT NULL, type INTEGER, flags INTEGER, chunk_size INTEGER NOT NULL)",
+        "CREATE TABLE {} (id INTEGER PRIMARY KEY, path TEXT UNIQUE NOT NULL, {})",
+        MATRYOSHKA_TABLE,
+        META_COLUMNS
+    );
+    // The collation is part of the schema, so all lookups and the unique constraint respect it without further ado.
+    pub const SQL_CREATE_META_NOCASE: &str = formatcp!(
+        "CREATE TABLE {} (id INTEGER PRIMARY KEY, path TEXT UNIQUE NOT NULL COLLATE NOCASE, {})",
+        MATRYOSHKA_TABLE,
+        META_COLUMNS
+    );
+    pub const SQL_PATH_COLLATION: &str = formatcp!(
+        "SELECT info.coll FROM pragma_index_list('{}') AS list, pragma_index_xinfo(list.name) AS info WHERE list.origin = 'u' AND info.name = 'path'",
         MATRYOSHKA_TABLE
     );
+    pub const SQL_TOUCH: &str = formatcp!(
+        "UPDATE {} SET modified_at = {} WHERE id = ?",
+        MATRYOSHKA_TABLE,
+        SQL_NOW
+    );
     pub const SQL_CREATE_DATA: &str = formatcp!(
         "CREATE TABLE IF NOT EXISTS {} (chunk_id INTEGER PRIMARY KEY, file_id INTEGER NOT NULL, chunk_num INTEGER NOT NULL, data BLOB NOT NULL, CONSTRAINT unq UNIQUE (file_id, chunk_num), FOREIGN KEY(file_id) REFERENCES {} (id) ON DELETE CASCADE ON UPDATE CASCADE)",
         DATA_TABLE,
         MATRYOSHKA_TABLE
     );
+    pub const SQL_CREATE_TRASH: &str = formatcp!(
+        "CREATE TABLE IF NOT EXISTS {} (trash_id INTEGER PRIMARY KEY, file_id INTEGER UNIQUE NOT NULL, path TEXT NOT NULL, type INTEGER, FOREIGN KEY(file_id) REFERENCES {} (id) ON DELETE CASCADE ON UPDATE CASCADE)",
+        TRASH_TABLE,
+        MATRYOSHKA_TABLE
+    );
     pub const SQL_CREATE_HANDLE: &str = formatcp!(
-        "INSERT INTO {} (path, type, chunk_size) VALUES (?, ?, ?)",
+        "INSERT INTO {} (path, type, chunk_size, flags) VALUES (?, ?, ?, ?)",
         MATRYOSHKA_TABLE
     );
     pub const SQL_CREATE_BLOB: &str = formatcp!(
         "INSERT INTO {} (file_id, chunk_num, data) VALUES (?, ?, ?)",
         DATA_TABLE
     );
-    pub const SQL_GET_HANDLE: &str = formatcp!(
-        "SELECT id FROM {} WHERE path = ? AND type = ?",
+    pub const SQL_GET_CHUNK: &str = formatcp!(
+        "SELECT data, COALESCE({meta}.flags, 0) FROM {data} INNER JOIN {meta} ON {meta}.id = {data}.file_id WHERE file_id = ? AND chunk_num = ?",
+        data = DATA_TABLE,
+        meta = MATRYOSHKA_TABLE
+    );
+    pub const SQL_UPDATE_CHUNK: &str =
+        formatcp!("UPDATE {} SET data = ? WHERE chunk_id = ?", DATA_TABLE);
+    pub const SQL_GET_LAYOUT: &str = formatcp!(
+        "SELECT chunk_size, COALESCE(flags, 0) FROM {} WHERE id = ? AND type = ?",
+        MATRYOSHKA_TABLE
+    );
+    pub const SQL_GET_CHUNK_IDS: &str = formatcp!(
+        "SELECT chunk_id FROM {} WHERE file_id = ? ORDER BY chunk_num ASC",
+        DATA_TABLE
+    );
+    pub const SQL_GET_LAST_CHUNK: &str = formatcp!(
+        "SELECT chunk_id, chunk_num, data FROM {} WHERE file_id = ? ORDER BY chunk_num DESC LIMIT 1",
+        DATA_TABLE
+    );
+    pub const SQL_GET_CHUNK_DATA: &str =
+        formatcp!("SELECT data FROM {} WHERE chunk_id = ?", DATA_TABLE);
+    pub const SQL_DELETE_CHUNK: &str = formatcp!("DELETE FROM {} WHERE chunk_id = ?", DATA_TABLE);
+    // During compaction, new chunks are numbered negatively to avoid collisions with the old ones.
+    pub const SQL_RENUMBER_CHUNKS: &str = formatcp!(
+        "UPDATE {} SET chunk_num = -1 - chunk_num WHERE file_id = ?",
+        DATA_TABLE
+    );
+    // Setting the flag only succeeds if it was unset before, making the lock atomic.
+    pub const SQL_LOCK: &str = formatcp!(
+        "UPDATE {meta} SET flags = COALESCE(flags, 0) | {flag} WHERE id = ? AND COALESCE(flags, 0) & {flag} = 0",
+        meta = MATRYOSHKA_TABLE,
+        flag = LOCKED_FLAG
+    );
+    pub const SQL_UNLOCK: &str = formatcp!(
+        "UPDATE {meta} SET flags = flags & ~{flag} WHERE id = ? AND COALESCE(flags, 0) & {flag} != 0",
+        meta = MATRYOSHKA_TABLE,
+        flag = LOCKED_FLAG
+    );
+    pub const SQL_GET_FLAGS: &str = formatcp!(
+        "SELECT COALESCE(flags, 0) FROM {} WHERE id = ?",
         MATRYOSHKA_TABLE
     );
+    pub const SQL_GET_HANDLE: &str =
+        formatcp!("SELECT id, type FROM {} WHERE path = ?", MATRYOSHKA_TABLE);
+    pub const SQL_GET_PATH_NOCASE: &str = formatcp!(
+        "SELECT path FROM {} WHERE path = ? COLLATE NOCASE AND type IN ({}, {}) LIMIT 2",
+        MATRYOSHKA_TABLE,
+        FILE_ID,
+        SYMLINK_ID
+    );
+    pub const SQL_GET_SYMLINK_TARGET: &str = formatcp!(
+        "SELECT CAST(data AS TEXT) FROM {} WHERE file_id = ? AND chunk_num = 0",
+        DATA_TABLE
+    );
     pub const SQL_GLOB: &str = formatcp!(
         "SELECT path FROM {} WHERE path GLOB ? AND type = ?",
         MATRYOSHKA_TABLE
     );
-    pub const SQL_SIZE: &str = formatcp!(
-        "SELECT COALESCE(SUM(LENGTH(data)), -1) FROM {} WHERE file_id = ?",
+    pub const SQL_GLOB_AFTER: &str = formatcp!(
+        "SELECT path FROM {} WHERE path GLOB ? AND type = ? AND path > ? ORDER BY path ASC LIMIT 1",
+        MATRYOSHKA_TABLE
+    );
+    pub const SQL_FIND_ANY: &str = formatcp!(
+        "SELECT DISTINCT path FROM {} WHERE type = {}",
+        MATRYOSHKA_TABLE,
+        FILE_ID
+    );
+    pub const SQL_ROOTS: &str = formatcp!(
+        "SELECT DISTINCT CASE WHEN instr(path, ?2) > 0 THEN substr(path, 1, instr(path, ?2) - 1) ELSE path END AS root FROM {} WHERE type = ?1 ORDER BY root ASC",
+        MATRYOSHKA_TABLE
+    );
+    pub const SQL_MODIFIED_SINCE: &str = formatcp!(
+        "SELECT path FROM {} WHERE type = ? AND modified_at >= ? ORDER BY path ASC",
+        MATRYOSHKA_TABLE
+    );
+    // Paths below a directory lie between "<directory>/" and "<directory>0" for the separator '/', as '0' follows it.
+    // Unlike matching substrings, such a range is looked up in the unique index of the paths.
+    pub const SQL_PREFIX: &str = formatcp!(
+        "SELECT path FROM {} WHERE type = :type AND path >= :lower AND path < :upper ORDER BY path ASC",
+        MATRYOSHKA_TABLE
+    );
+    // The empty path refers to the root, which is the prefix of all files.
+    pub const SQL_PATH_KIND: &str = formatcp!(
+        "SELECT EXISTS(SELECT 1 FROM {meta} WHERE type = :type AND path = :path), EXISTS(SELECT 1 FROM {meta} WHERE type = :type AND (:path = '' OR (path >= :lower AND path < :upper)))",
+        meta = MATRYOSHKA_TABLE
+    );
+    pub const SQL_ALL_PATHS: &str = formatcp!(
+        "SELECT path FROM {} WHERE type = ? ORDER BY path ASC",
+        MATRYOSHKA_TABLE
+    );
+    // For compressed files, the size of the last chunk must be determined after decompressing it. The query is
+    // completed by a list of placeholders for the handles and the grouping by them.
+    pub const SQL_SIZES: &str = formatcp!(
+        "SELECT {meta}.id, COALESCE(SUM(LENGTH(data)), 0), COALESCE({meta}.flags, 0), MAX(chunk_num), {meta}.chunk_size FROM {meta} LEFT JOIN {data} ON {meta}.id = {data}.file_id WHERE {meta}.type = {file} AND {meta}.id IN",
+        data = DATA_TABLE,
+        meta = MATRYOSHKA_TABLE,
+        file = FILE_ID
+    );
+    pub const SQL_SIZES_GROUPING: &str = formatcp!("GROUP BY {}.id", MATRYOSHKA_TABLE);
+    pub const SQL_SIZE: &str = formatcp!("{} (?) {}", SQL_SIZES, SQL_SIZES_GROUPING);
+    pub const SQL_LAYOUT: &str = formatcp!(
+        "SELECT chunk_id, chunk_num, LENGTH(data) FROM {} WHERE file_id = ? ORDER BY chunk_num ASC",
+        DATA_TABLE
+    );
+    pub const SQL_STORED_SIZE: &str = formatcp!(
+        "SELECT COALESCE(SUM(LENGTH(data)), 0) FROM {} WHERE file_id = ?",
+        DATA_TABLE
+    );
+    pub const SQL_EXISTS: &str = formatcp!(
+        "SELECT EXISTS (SELECT 1 FROM {} WHERE id = ? AND type = ?)",
+        MATRYOSHKA_TABLE
+    );
+    // A left join is required to keep empty files without any data. Like for SQL_SIZES, the last chunk of compressed
+    // files must be decompressed afterwards. Without a pattern, all files are listed.
+    pub const SQL_LIST_WITH_SIZES: &str = formatcp!(
+        "SELECT {meta}.path, {meta}.id, COALESCE(SUM(LENGTH({data}.data)), 0), COALESCE({meta}.flags, 0), MAX(chunk_num), {meta}.chunk_size FROM {meta} LEFT JOIN {data} ON {meta}.id = {data}.file_id WHERE {meta}.type = :type AND (:pattern IS NULL OR {meta}.path GLOB :pattern) GROUP BY {meta}.id ORDER BY {meta}.path ASC",
+        data = DATA_TABLE,
+        meta = MATRYOSHKA_TABLE
+    );
+    pub const SQL_LIST_FILES: &str = formatcp!(
+        "SELECT id, path FROM {} WHERE type = ? ORDER BY path ASC",
+        MATRYOSHKA_TABLE
+    );
+    pub const SQL_SAMPLE_UNCOMPRESSED: &str = formatcp!(
+        "SELECT id FROM {} WHERE type = ? AND COALESCE(flags, 0) & {} = 0 ORDER BY path ASC LIMIT ?",
+        MATRYOSHKA_TABLE,
+        COMPRESSED_FLAG
+    );
+    pub const SQL_OPTIMIZE: &str = "ANALYZE; PRAGMA optimize;";
+    pub const SQL_LIST_BY_INSERTION: &str = formatcp!(
+        "SELECT path FROM {} WHERE type = ? ORDER BY id ASC",
+        MATRYOSHKA_TABLE
+    );
+    pub const SQL_MAIN_FILE: &str = "SELECT file FROM pragma_database_list WHERE name = 'main'";
+    pub const SQL_PAYLOAD_SIZE: &str =
+        formatcp!("SELECT COALESCE(SUM(LENGTH(data)), 0) FROM {}", DATA_TABLE);
+    pub const SQL_CHUNK_STATS: &str = formatcp!(
+        "SELECT COUNT(*), COALESCE(SUM(LENGTH(data)), 0), COALESCE(MAX(LENGTH(data)), 0) FROM {}",
         DATA_TABLE
     );
     pub const SQL_DELETE: &str = formatcp!("DELETE FROM {} WHERE id = ?", MATRYOSHKA_TABLE);
-    pub const SQL_GET_BLOBS: &str = formatcp!("SELECT chunk_id, chunk_num, {meta}.chunk_size FROM {data}
+    pub const SQL_DELETE_MATCHING: &str = formatcp!(
+        "DELETE FROM {} WHERE path GLOB ? AND type = ?",
+        MATRYOSHKA_TABLE
+    );
+    pub const SQL_GET_MATCHING: &str = formatcp!(
+        "SELECT id FROM {} WHERE path GLOB ? AND type = ?",
+        MATRYOSHKA_TABLE
+    );
+    pub const SQL_TRASH_ENTRY: &str = formatcp!(
+        "INSERT INTO {} (file_id, path, type) SELECT id, path, type FROM {} WHERE id = ? AND type != ?",
+        TRASH_TABLE,
+        MATRYOSHKA_TABLE
+    );
+    // Valid virtual paths never start with a slash. Therefore, trashed entries never collide with existing ones.
+    pub const SQL_TRASH_HANDLE: &str = formatcp!(
+        "UPDATE {} SET path = '/trash/' || id, type = ? WHERE id = ?",
+        MATRYOSHKA_TABLE
+    );
+    pub const SQL_GET_TRASHED: &str = formatcp!(
+        "SELECT file_id, type FROM {} WHERE path = ? ORDER BY trash_id DESC LIMIT 1",
+        TRASH_TABLE
+    );
+    pub const SQL_RESTORE_HANDLE: &str = formatcp!(
+        "UPDATE {} SET path = ?, type = ? WHERE id = ?",
+        MATRYOSHKA_TABLE
+    );
+    pub const SQL_RENAME: &str = formatcp!("UPDATE {} SET path = ? WHERE id = ?", MATRYOSHKA_TABLE);
+    pub const SQL_GET_PATH: &str = formatcp!("SELECT path FROM {} WHERE id = ?", MATRYOSHKA_TABLE);
+    pub const SQL_RESTORE_ENTRY: &str = formatcp!("DELETE FROM {} WHERE file_id = ?", TRASH_TABLE);
+    pub const SQL_EMPTY_TRASH: &str = formatcp!("DELETE FROM {} WHERE type = ?", MATRYOSHKA_TABLE);
+    pub const PRECOMPILED_COMMANDS: [&str; 10] = [
+        SQL_GET_HANDLE,
+        SQL_GET_SYMLINK_TARGET,
+        SQL_CREATE_HANDLE,
+        SQL_GLOB,
+        SQL_ROOTS,
+        SQL_PREFIX,
+        SQL_SIZE,
+        SQL_DELETE,
+        SQL_GET_BLOBS,
+        SQL_GET_CHUNK,
+    ];
+    // Room for statements cached by the users or less frequent operations without evicting the precompiled ones.
+    pub const STATEMENT_CACHE_SLACK: usize = 16;
+
+    pub const SQL_GET_BLOBS: &str = formatcp!("SELECT chunk_id, chunk_num, {meta}.chunk_size, COALESCE({meta}.flags, 0) FROM {data}
         INNER JOIN {meta} ON {meta}.id={data}.file_id
         WHERE file_id = :handle AND chunk_num BETWEEN cast((:index / {meta}.chunk_size) as int) AND cast(((:index + :size - 1) / {meta}.chunk_size) as int)
         ORDER BY chunk_num ASC",
@@ -67,10 +303,75 @@ mod constants {
 }
 
 /// A virtual file system in a SQLite database.
+///
+/// The database is borrowed anew for each operation and never beyond it. Therefore, it is safe to use types checking
+/// their borrows at runtime. Creating files borrows it mutably exactly once, without borrowing it immutably in between.
+/// Modifying operations taking the file system by shared reference, i.e. appending, writing in place, deleting or
+/// compacting, only borrow it immutably and use an unchecked transaction instead.
 #[derive(Debug)]
 pub struct FileSystem<D> {
     database: D,
     meta_data: MetaData,
+    path_collation: PathCollation,
+    use_trash: bool,
+    read_buffer_limit: usize,
+    separator: char,
+}
+
+impl FileSystem<Database> {
+    /// Load the virtual file system from a serialized database, i.e. as created by [to_bytes](Self::to_bytes).
+    ///
+    /// The database is kept in memory and changes are not written back. Empty data is treated as an empty database.
+    #[cfg(feature = "serialize")]
+    pub fn from_bytes(
+        data: Vec<u8>,
+        create_file_system: bool,
+    ) -> Result<FileSystem<Database>, FileSystemError> {
+        let mut database = Database::open_in_memory()?;
+        if !data.is_empty() {
+            deserialize(&mut database, &data)?;
+        }
+        FileSystem::load(database, create_file_system)
+    }
+
+    /// Load the virtual file system from the database file at the path, accessing it with the SQLite VFS of the options
+    /// if one is set.
+    ///
+    /// The database file is only created if the file system may be created as well. Paths and VFS names SQLite cannot
+    /// represent, i.e. containing NUL bytes, are reported as [DatabaseError](FileSystemError::DatabaseError).
+    pub fn open_with<P: AsRef<std::path::Path>>(
+        path: P,
+        options: &LoadOptions,
+    ) -> Result<FileSystem<Database>, FileSystemError> {
+        let mut flags = OpenFlags::default();
+        if !options.create_file_system {
+            flags.remove(OpenFlags::SQLITE_OPEN_CREATE);
+        }
+        let database = match &options.vfs {
+            Some(vfs) => Database::open_with_flags_and_vfs(path, flags, vfs),
+            None => Database::open_with_flags(path, flags),
+        }
+        .map_err(|error| match error {
+            // Paths and VFS names containing NUL bytes or invalid Unicode never reach SQLite.
+            error @ RusqliteError::NulError(..) | error @ RusqliteError::InvalidPath(..) => {
+                FileSystemError::DatabaseError(DatabaseError::cannot_open(error.to_string()))
+            }
+            error => error.into(),
+        })?;
+        FileSystem::load_with(database, options)
+    }
+
+    /// Load the virtual file system from the database file at the path, accessing it with the named SQLite VFS.
+    ///
+    /// The database file is only created if the file system may be created as well. See [open_with](Self::open_with)
+    /// for combining the VFS with other options.
+    pub fn load_with_vfs<P: AsRef<std::path::Path>>(
+        path: P,
+        vfs: &str,
+        create_file_system: bool,
+    ) -> Result<FileSystem<Database>, FileSystemError> {
+        FileSystem::open_with(path, LoadOptions::new().create(create_file_system).vfs(vfs))
+    }
 }
 
 impl<D> FileSystem<D>
@@ -78,11 +379,107 @@ where
     D: BorrowMut<Database>,
 {
     /// Load the virtual file system from an SQLite database.
-    pub fn load(
-        mut database: D,
+    pub fn load(database: D, create_file_system: bool) -> Result<FileSystem<D>, FileSystemError> {
+        FileSystem::load_with(database, LoadOptions::new().create(create_file_system))
+    }
+
+    /// Load the virtual file system from an SQLite database using the given options.
+    ///
+    /// The VFS of the options is ignored, as the database is already opened. Use [open_with](FileSystem::open_with)
+    /// for opening a database file with it.
+    pub fn load_with(database: D, options: &LoadOptions) -> Result<FileSystem<D>, FileSystemError> {
+        let availability = MetaData::from_database(database.borrow());
+        FileSystem::initialize(database, availability, options)
+    }
+
+    /// Load the virtual file system from an SQLite database, using a specific page size if the file system is created.
+    ///
+    /// See [LoadOptions::page_size] for the details and for combining it with other options.
+    pub fn load_with_page_size(
+        database: D,
+        create_file_system: bool,
+        page_size: Option<u32>,
+    ) -> Result<FileSystem<D>, FileSystemError> {
+        let mut options = LoadOptions::new();
+        options.create(create_file_system);
+        if let Some(page_size) = page_size {
+            options.page_size(page_size);
+        }
+        FileSystem::load_with(database, &options)
+    }
+
+    /// Load the virtual file system from an SQLite database, enabling incremental vacuuming if the file system is created.
+    ///
+    /// See [LoadOptions::incremental_vacuum] for the details and for combining it with other options.
+    pub fn load_with_incremental_vacuum(
+        database: D,
+        create_file_system: bool,
+    ) -> Result<FileSystem<D>, FileSystemError> {
+        FileSystem::load_with(
+            database,
+            LoadOptions::new()
+                .create(create_file_system)
+                .incremental_vacuum(true),
+        )
+    }
+
+    /// Load the virtual file system from an SQLite database, comparing paths with the given collation if the file system is created.
+    ///
+    /// See [LoadOptions::collation] for the details and for combining it with other options.
+    pub fn load_with_collation(
+        database: D,
+        create_file_system: bool,
+        collation: PathCollation,
+    ) -> Result<FileSystem<D>, FileSystemError> {
+        FileSystem::load_with(
+            database,
+            LoadOptions::new()
+                .create(create_file_system)
+                .collation(collation),
+        )
+    }
+
+    /// Load the virtual file system from an SQLite database without changing the capacity of its statement cache.
+    ///
+    /// See [LoadOptions::keep_statement_cache] for the details and for combining it with other options.
+    pub fn load_keeping_statement_cache(
+        database: D,
         create_file_system: bool,
     ) -> Result<FileSystem<D>, FileSystemError> {
-        let meta_data = match MetaData::from_database(database.borrow()) {
+        FileSystem::load_with(
+            database,
+            LoadOptions::new()
+                .create(create_file_system)
+                .keep_statement_cache(true),
+        )
+    }
+
+    /// Load a specific version of the virtual file system from an SQLite database.
+    ///
+    /// Unlike `load`, more recent versions in the same database are ignored. This allows multiple versions to live side by side, i.e. during a migration.
+    pub fn load_version(
+        database: D,
+        version: u32,
+        create_file_system: bool,
+    ) -> Result<FileSystem<D>, FileSystemError> {
+        if version != constants::CURRENT_MATRYOSHKA_VERSION {
+            return Err(FileSystemError::UnsupportedVersion(version));
+        }
+
+        let availability = MetaData::from_database_version(database.borrow(), version);
+        FileSystem::initialize(
+            database,
+            availability,
+            LoadOptions::new().create(create_file_system),
+        )
+    }
+
+    fn initialize(
+        mut database: D,
+        availability: Availability,
+        options: &LoadOptions,
+    ) -> Result<FileSystem<D>, FileSystemError> {
+        let meta_data = match availability {
             Availability::Available(meta_data)
                 if meta_data.version() == constants::CURRENT_MATRYOSHKA_VERSION =>
             {
@@ -91,9 +488,26 @@ where
             Availability::Available(meta_data) => {
                 Err(FileSystemError::UnsupportedVersion(meta_data.version()))
             }
-            Availability::Missing if create_file_system => {
+            Availability::Missing if options.create_file_system => {
+                if let Some(page_size) = options.page_size {
+                    database
+                        .borrow()
+                        .pragma_update(None, "page_size", &page_size)?;
+                }
+                if options.incremental_vacuum {
+                    database
+                        .borrow()
+                        .pragma_update(None, "auto_vacuum", &"INCREMENTAL")?;
+                }
+
                 let transaction = database.borrow_mut().transaction()?;
-                transaction.execute(constants::SQL_CREATE_META, [])?;
+                transaction.execute(
+                    match options.collation {
+                        PathCollation::Binary => constants::SQL_CREATE_META,
+                        PathCollation::NoCase => constants::SQL_CREATE_META_NOCASE,
+                    },
+                    [],
+                )?;
                 transaction.execute(constants::SQL_CREATE_DATA, [])?;
                 transaction.commit()?;
                 Ok(MetaData::from_version(
@@ -101,23 +515,24 @@ where
                 ))
             }
             Availability::Missing => Err(FileSystemError::NoFileSystem),
-            Availability::Error(error) => Err(error.into()),
+            Availability::Error(error) => Err(FileSystemError::DatabaseError(error)),
         }?;
 
-        // Pre-compile the primary SQL commands
-        const PRECOMPILED_COMMANDS: [&str; 6] = [
-            constants::SQL_GET_HANDLE,
-            constants::SQL_CREATE_HANDLE,
-            constants::SQL_GLOB,
-            constants::SQL_SIZE,
-            constants::SQL_DELETE,
-            constants::SQL_GET_BLOBS,
-        ];
-
-        database
+        let path_collation = database
             .borrow()
-            .set_prepared_statement_cache_capacity(PRECOMPILED_COMMANDS.len());
-        for statement in &PRECOMPILED_COMMANDS {
+            .query_row(constants::SQL_PATH_COLLATION, [], |row| {
+                row.get::<_, String>(0)
+            })
+            .optional()?
+            .map_or(PathCollation::Binary, PathCollation::from_sql);
+
+        // Pre-compile the primary SQL commands
+        if options.resize_statement_cache {
+            database.borrow().set_prepared_statement_cache_capacity(
+                constants::PRECOMPILED_COMMANDS.len() + constants::STATEMENT_CACHE_SLACK,
+            );
+        }
+        for statement in &constants::PRECOMPILED_COMMANDS {
             database
                 .borrow()
                 .prepare_cached(statement)
@@ -127,601 +542,6517 @@ where
         Ok(FileSystem {
             database,
             meta_data,
+            path_collation,
+            use_trash: false,
+            read_buffer_limit: constants::DEFAULT_READ_BUFFER_LIMIT,
+            separator: '/',
         })
     }
 
-    /// Query the file system for those files with a specific GLOB pattern. Both the '?' and the '*' placeholder are supported
-    pub fn find<T: AsRef<str>>(&self, path: T) -> Result<Vec<String>, DatabaseError> {
-        let path: VirtualPath = path.as_ref().into();
-        let mut handle_query = self
-            .database
+    /// Returns the collation used for comparing paths.
+    pub fn path_collation(&self) -> PathCollation {
+        self.path_collation
+    }
+
+    /// Set the number of statements cached in addition to those precompiled by the file system.
+    ///
+    /// Statements cached by the users of [connection](Self::connection) share this space. Exceeding it
+    /// evicts statements which need to be compiled again.
+    pub fn set_statement_cache_slack(&self, slack: usize) {
+        self.database
             .borrow()
-            .prepare_cached(constants::SQL_GLOB)
-            .map_err(|error| error.try_into().expect(DatabaseError::LOGIC_ERROR_MESSAGE))?;
+            .set_prepared_statement_cache_capacity(constants::PRECOMPILED_COMMANDS.len() + slack);
+    }
 
-        // We must cache the result to avoid lifetime issues.
-        let result = handle_query
-            .query_map(params![path.as_ref(), constants::FILE_ID], |row| {
-                Ok(row.get_unwrap(0))
-            })
-            .map_err(|error| error.try_into().expect(DatabaseError::LOGIC_ERROR_MESSAGE))?
-            .map(|handle| handle.unwrap()) // The price we have to pay to get a iterator ...
-            .collect();
+    /// Set the maximal size of the buffer used for reading uncompressed files in bytes, i.e. on memory-constrained targets.
+    ///
+    /// Chunks larger than the limit are read in multiple steps. Compressed chunks are still decompressed as a whole. The
+    /// default is 1MB, while the limit is at least one byte.
+    pub fn set_read_buffer_limit(&mut self, limit: usize) {
+        self.read_buffer_limit = std::cmp::max(limit, 1);
+    }
 
-        Ok(result)
+    /// Set the separator splitting paths into directories for [roots](Self::roots), [list_dir](Self::list_dir),
+    /// [tree](Self::tree), [path_kind](Self::path_kind), [plan_delete_dir](Self::plan_delete_dir),
+    /// [parent](Self::parent) and [file_name](Self::file_name). The default is '/'.
+    ///
+    /// Paths are still normalized at '/' before being stored, while other separators are kept as part of their
+    /// components. The placeholders of SQLite's GLOB in [find](Self::find) match any character including the
+    /// separator, while [glob](Self::glob) and the remaining functions only consider '/'.
+    pub fn set_separator(&mut self, separator: char) {
+        self.separator = separator;
     }
 
-    fn create<T: Into<VirtualPath>, R: Read>(
-        &mut self,
-        path: T,
-        mut data: R,
-        chunk_size: usize,
-    ) -> Result<Handle, CreationError> {
-        let max_blob_size = self.database.borrow().limit(Limit::SQLITE_LIMIT_LENGTH);
-        let chunk_size = match chunk_size {
-            value if value > 0 && value <= max_blob_size as usize => value,
-            _ => constants::DEFAULT_BYTE_BLOB_SIZE,
-        };
+    /// Returns the separator splitting paths into directories. See [set_separator](Self::set_separator).
+    pub fn separator(&self) -> char {
+        self.separator
+    }
 
-        // Create the transaction to return safely on errors and prepare the statement.
-        let transaction = self.database.borrow_mut().transaction()?;
+    /// Returns the normalized path of the parent directory using the configured separator. An empty path has no parent.
+    pub fn parent<T: AsRef<str>>(&self, path: T) -> Option<VirtualPath> {
+        VirtualPath::from(path.as_ref()).parent_with_separator(self.separator)
+    }
 
-        let handle = {
-            let mut create_handle_statement =
-                transaction.prepare_cached(constants::SQL_CREATE_HANDLE)?;
-            let mut create_blob_statement =
-                transaction.prepare_cached(constants::SQL_CREATE_BLOB)?;
+    /// Returns the last component of the normalized path using the configured separator, if any.
+    pub fn file_name<T: AsRef<str>>(&self, path: T) -> Option<String> {
+        VirtualPath::from(path.as_ref())
+            .file_name_with_separator(self.separator)
+            .map(String::from)
+    }
 
-            let handle = match create_handle_statement.insert(params![
-                path.into().as_ref(),
-                constants::FILE_ID,
-                chunk_size as i32
-            ]) {
-                Ok(handle) => handle,
-                Err(RusqliteError::SqliteFailure(error, _))
-                    if error.code == ErrorCode::ConstraintViolation =>
-                {
-                    return Err(CreationError::FileExists);
-                }
-                Err(error) => {
-                    return Err(error.into());
-                }
-            };
+    /// Get a handle for interrupting the running query from another thread, i.e. a long read.
+    ///
+    /// Interrupted reads fail with [ReadError::Interrupted].
+    pub fn interrupt_handle(&self) -> InterruptHandle {
+        self.database.borrow().get_interrupt_handle()
+    }
 
-            let mut buffer = vec![0u8; chunk_size];
-            let mut chunk_index = 0u32;
-            loop {
-                match data.read(buffer.as_mut()) {
-                    Ok(size) => {
-                        create_blob_statement.execute(params![
-                            handle,
-                            chunk_index,
-                            &buffer[0..size]
-                        ])?;
-                        if size != chunk_size {
-                            break;
-                        }
-                        chunk_index += 1;
-                    }
-                    Err(error) if error.kind() == ErrorKind::Interrupted => {
-                        // Just try again...
-                    }
-                    Err(error) => {
-                        return Err(error.into());
-                    }
-                }
-            }
+    /// Register a scalar SQL function taking the given number of arguments, usable in custom queries on the [connection](Self::connection).
+    ///
+    /// A negative number of arguments allows any number of them. Registering a function of the same name and number of
+    /// arguments again replaces it. Names containing a NUL byte are rejected.
+    pub fn register_scalar_function<F, T>(
+        &self,
+        name: &str,
+        n_args: i32,
+        func: F,
+    ) -> Result<(), DatabaseError>
+    where
+        F: FnMut(&Context<'_>) -> Result<T, RusqliteError> + Send + UnwindSafe + 'static,
+        T: ToSql,
+    {
+        self.database
+            .borrow()
+            .create_scalar_function(name, n_args, FunctionFlags::SQLITE_UTF8, func)
+            .map_err(|error| error.try_into().expect(DatabaseError::LOGIC_ERROR_MESSAGE))
+    }
 
-            handle
-        };
+    /// Access the underlying database, i.e. for running custom queries.
+    pub fn connection(&self) -> &Database {
+        self.database.borrow()
+    }
 
-        transaction.commit()?;
-        Ok(Handle(handle))
+    /// Access the underlying database mutably, i.e. for attaching custom tables.
+    ///
+    /// Modifying the tables of Matryoshka directly is unsupported and may corrupt the file system.
+    pub fn connection_mut(&mut self) -> &mut Database {
+        self.database.borrow_mut()
     }
 
-    fn open<T: Into<VirtualPath>>(&self, path: T) -> Result<Option<Handle>, DatabaseError> {
-        let mut handle_query = self
+    /// Unwrap the file system, returning the underlying database.
+    pub fn into_inner(self) -> D {
+        self.database
+    }
+
+    /// Export the whole underlying database as a single blob, i.e. for snapshots.
+    ///
+    /// The image matches the file SQLite would store on disk and works for both file and in-memory databases. Only the
+    /// main database is exported, attached ones are not. As SQLite is called directly, the "serialize" feature requires a
+    /// version of it providing sqlite3_serialize, i.e. 3.36 or newer.
+    #[cfg(feature = "serialize")]
+    pub fn serialize(&self) -> Result<Vec<u8>, DatabaseError> {
+        serialize(self.database.borrow())
+            .map_err(|error| error.try_into().expect(DatabaseError::LOGIC_ERROR_MESSAGE))
+    }
+
+    /// Serialize the underlying database into bytes, as it would be stored on disk. Same as [serialize](Self::serialize).
+    #[cfg(feature = "serialize")]
+    pub fn to_bytes(&self) -> Result<Vec<u8>, DatabaseError> {
+        self.serialize()
+    }
+
+    /// Check whether the underlying database is only stored in memory, i.e. opened as ":memory:".
+    ///
+    /// If the database could not be queried, it is assumed to be persistent.
+    pub fn is_in_memory(&self) -> bool {
+        // SQLite reports an empty file name for in-memory and temporary databases.
+        self.database
+            .borrow()
+            .query_row(constants::SQL_MAIN_FILE, [], |row| {
+                Ok(row.get_unwrap::<_, String>(0).is_empty())
+            })
+            .unwrap_or(false)
+    }
+
+    /// Check whether the volume of the underlying database is able to hold the given number of bytes.
+    ///
+    /// A margin for the overhead of SQLite is added. In-memory databases and volumes not reporting their free space
+    /// are assumed to be large enough.
+    pub fn check_capacity(&self, required: u64) -> Result<bool, DatabaseError> {
+        let path: String = self
             .database
             .borrow()
-            .prepare_cached(constants::SQL_GET_HANDLE)
+            .query_row(constants::SQL_MAIN_FILE, [], |row| Ok(row.get_unwrap(0)))
             .map_err(|error| error.try_into().expect(DatabaseError::LOGIC_ERROR_MESSAGE))?;
-        handle_query
-            .query_row(params![path.into().as_ref(), constants::FILE_ID], |row| {
-                Ok(Handle(row.get_unwrap(0)))
-            })
-            .optional()
-            .map_err(|error| error.try_into().expect(DatabaseError::LOGIC_ERROR_MESSAGE))
+        if path.is_empty() {
+            return Ok(true);
+        }
+
+        let required = required.saturating_add(required / constants::CAPACITY_MARGIN_DIVISOR);
+        let directory = std::path::Path::new(&path)
+            .parent()
+            .unwrap_or_else(|| std::path::Path::new("."));
+        Ok(fs2::available_space(directory)
+            .map(|available| available >= required)
+            .unwrap_or(true))
     }
 
-    fn read<W: Write>(
-        &self,
-        handle: Handle,
-        mut sink: W,
-        index: usize,
-        length: usize,
-    ) -> Result<usize, ReadError> {
-        let index = i64::try_from(index).map_err(|_| ReadError::FileSystemLimits)?;
+    /// Query the page size of the underlying database in bytes.
+    pub fn page_size(&self) -> Result<u32, DatabaseError> {
+        self.database
+            .borrow()
+            .pragma_query_value(None, "page_size", |row| Ok(row.get_unwrap(0)))
+            .map_err(|error| error.try_into().expect(DatabaseError::LOGIC_ERROR_MESSAGE))
+    }
 
-        // Check length and exit early if not data is of interest
-        let length = i64::try_from(length).map_err(|_| ReadError::FileSystemLimits)?;
-        if length == 0 {
-            return Ok(0);
+    /// Transfer the content of the write-ahead log into the database, i.e. to keep the "-wal" file from growing.
+    ///
+    /// If the database does not use WAL mode, nothing happens. Failing to complete a checkpoint other than a passive one
+    /// due to concurrent connections is reported as a busy database.
+    pub fn checkpoint(&self, mode: CheckpointMode) -> Result<(), DatabaseError> {
+        let is_busy = self
+            .database
+            .borrow()
+            .query_row(
+                &format!("PRAGMA wal_checkpoint({})", mode.as_sql()),
+                [],
+                |row| Ok(row.get_unwrap::<_, i64>(0) != 0),
+            )
+            .map_err(|error| error.try_into().expect(DatabaseError::LOGIC_ERROR_MESSAGE))?;
+        match is_busy {
+            true => Err(
+                RusqliteError::SqliteFailure(ffi::Error::new(ffi::SQLITE_BUSY), None)
+                    .try_into()
+                    .expect(DatabaseError::LOGIC_ERROR_MESSAGE),
+            ),
+            false => Ok(()),
         }
+    }
 
-        // Prepare the statements regarding the blobs
-        let mut blobs_statement = self
+    /// Ensure all committed changes are durably written to disk.
+    ///
+    /// In WAL mode, commits are not synced with "synchronous = NORMAL". Therefore, a full checkpoint syncs the log and the
+    /// database. In other journal modes, commits are already synced unless "synchronous = OFF" disabled it explicitly.
+    pub fn sync(&self) -> Result<(), DatabaseError> {
+        let journal_mode: String = self
             .database
             .borrow()
-            .prepare_cached(constants::SQL_GET_BLOBS)?;
-
-        // Let SQLite calculate all the key characteristics
-        let mut chuck_size: Option<i64> = None;
-        let mut blob_iter = blobs_statement
-            .query_map(
-                &[
-                    (":handle", &handle.0),
-                    (":index", &index),
-                    (":size", &length),
-                ],
-                |row| {
-                    Ok(match chuck_size {
-                        Some(chunk_size) => (0usize, row.get_unwrap(0), chunk_size),
-                        None => {
-                            let raw_chunk_size: i64 = row.get_unwrap(2);
-                            let chunk_num: i64 = row.get_unwrap(1);
-                            chuck_size = Some(raw_chunk_size);
-                            let offset: i64 = index - (chunk_num * raw_chunk_size);
-                            (offset as usize, row.get_unwrap(0), raw_chunk_size)
-                        }
-                    })
-                },
-            )?
-            .map(|blob_index| blob_index.unwrap());
-
-        // Initialize the chunk: Chunk size must always be equal or larger to the biggest blob.
-        let first_blob = blob_iter.next().ok_or(ReadError::OutOfBounds)?;
-        let mut buffer = vec![0u8; first_blob.2 as usize];
+            .pragma_query_value(None, "journal_mode", |row| row.get(0))
+            .map_err(|error| error.try_into().expect(DatabaseError::LOGIC_ERROR_MESSAGE))?;
+        match journal_mode.eq_ignore_ascii_case("wal") {
+            true => self.checkpoint(CheckpointMode::Full),
+            false => Ok(()),
+        }
+    }
 
-        let mut bytes_read = 0i64;
-        let mut blob_cache: Option<rusqlite::blob::Blob> = None;
-        for (index, (first_index, blob_id, _)) in
-            std::iter::once(first_blob).chain(blob_iter).enumerate()
-        {
-            let blob = match blob_cache {
-                None => self.database.borrow().blob_open(
-                    DatabaseName::Main,
-                    constants::DATA_TABLE,
-                    "data",
-                    blob_id,
-                    true,
-                ),
-                Some(mut blob) => blob.reopen(blob_id).map(|_| blob),
-            }?;
-
-            let blob_size = blob.size() as i64;
-            let mut num_bytes = std::cmp::min(blob_size, length - bytes_read);
-            if index == 0 {
-                num_bytes = std::cmp::min(blob_size - first_index as i64, num_bytes);
-                if num_bytes <= 0 {
-                    return Err(ReadError::OutOfBounds);
-                }
-            }
+    /// Gather statistics on the indices of the tables, allowing SQLite to plan queries more efficiently.
+    ///
+    /// This is worthwhile after many files were created or deleted, i.e. after an import, and before closing long-living
+    /// connections. As all tables are analyzed, it may take a while on large file systems.
+    pub fn optimize(&self) -> Result<(), DatabaseError> {
+        self.database
+            .borrow()
+            .execute_batch(constants::SQL_OPTIMIZE)
+            .map_err(|error| error.try_into().expect(DatabaseError::LOGIC_ERROR_MESSAGE))
+    }
 
-            // Read data into the buffer
-            blob.read_at_exact(&mut buffer[..num_bytes as usize], first_index)?;
+    /// Reclaim up to the given number of free pages, or all of them, without rewriting the whole database.
+    ///
+    /// This requires the file system to be created with [LoadOptions::incremental_vacuum], i.e. by
+    /// [load_with_incremental_vacuum](Self::load_with_incremental_vacuum). Otherwise, nothing happens.
+    pub fn incremental_vacuum(&self, pages: Option<u32>) -> Result<(), DatabaseError> {
+        let command = match pages {
+            Some(pages) => format!("PRAGMA incremental_vacuum({})", pages),
+            None => String::from("PRAGMA incremental_vacuum"),
+        };
+        // Each step of the statement frees a single page.
+        let vacuum = || -> Result<(), RusqliteError> {
+            let mut statement = self.database.borrow().prepare(&command)?;
+            let mut rows = statement.query([])?;
+            while rows.next()?.is_some() {}
+            Ok(())
+        };
+        vacuum().map_err(|error| error.try_into().expect(DatabaseError::LOGIC_ERROR_MESSAGE))
+    }
 
-            // Copy data to writer
-            sink.write_all(&buffer[..num_bytes as usize])?;
+    /// Query statistics regarding the storage used, i.e. to decide whether vacuuming is worthwhile.
+    pub fn storage_stats(&self) -> Result<StorageStats, DatabaseError> {
+        let database = self.database.borrow();
+        let query_pragma = |name: &str| -> Result<i64, RusqliteError> {
+            database.pragma_query_value(None, name, |row| Ok(row.get_unwrap(0)))
+        };
 
-            bytes_read += num_bytes;
-            blob_cache = Some(blob);
-        }
+        let stats = database
+            .query_row(constants::SQL_PAYLOAD_SIZE, [], |row| {
+                Ok(row.get_unwrap::<_, i64>(0))
+            })
+            .and_then(|payload_size| {
+                Ok(StorageStats::new(
+                    payload_size as u64,
+                    query_pragma("page_size")? as u64,
+                    query_pragma("page_count")? as u64,
+                    query_pragma("freelist_count")? as u64,
+                ))
+            })
+            .map_err(|error| error.try_into().expect(DatabaseError::LOGIC_ERROR_MESSAGE))?;
+        Ok(stats)
+    }
 
-        // Raise an out-of-bound error if the length it too large.
-        match bytes_read == length {
-            true => Ok(bytes_read as usize),
-            false => Err(ReadError::OutOfBounds),
-        }
+    /// Query aggregate statistics over all chunks stored, i.e. to check whether the chosen chunk sizes are sensible.
+    ///
+    /// Like in [storage_stats](Self::storage_stats), chunks of files in the trash are included.
+    pub fn chunk_stats(&self) -> Result<ChunkStats, DatabaseError> {
+        self.database
+            .borrow()
+            .query_row(constants::SQL_CHUNK_STATS, [], |row| {
+                Ok(ChunkStats::new(
+                    row.get::<_, i64>(0)? as u64,
+                    row.get::<_, i64>(1)? as u64,
+                    row.get::<_, i64>(2)? as u64,
+                ))
+            })
+            .map_err(|error| error.try_into().expect(DatabaseError::LOGIC_ERROR_MESSAGE))
     }
 
-    fn delete(&self, handle: Handle) -> Result<usize, DatabaseError> {
-        let mut delete_query = self
+    /// Query the file system for those files with a specific GLOB pattern. Both the '?' and the '*' placeholder are supported
+    pub fn find<T: AsRef<str>>(&self, path: T) -> Result<Vec<String>, DatabaseError> {
+        let path: VirtualPath = path.as_ref().into();
+        let mut handle_query = self
             .database
             .borrow()
-            .prepare_cached(constants::SQL_DELETE)
+            .prepare_cached(constants::SQL_GLOB)
             .map_err(|error| error.try_into().expect(DatabaseError::LOGIC_ERROR_MESSAGE))?;
-        delete_query
-            .execute(params![handle.0])
-            .map_err(|error| error.try_into().expect(DatabaseError::LOGIC_ERROR_MESSAGE))
+
+        // We must cache the result to avoid lifetime issues.
+        let result = handle_query
+            .query_map(params![path.as_ref(), constants::FILE_ID], |row| row.get(0))
+            .map_err(|error| error.try_into().expect(DatabaseError::LOGIC_ERROR_MESSAGE))?
+            .collect::<Result<_, _>>()
+            .map_err(|error| error.try_into().expect(DatabaseError::LOGIC_ERROR_MESSAGE))?;
+
+        Ok(result)
     }
 
-    fn size(&self, handle: Handle) -> Result<Option<usize>, DatabaseError> {
-        let mut handle_query = self
+    /// Iterate lazily over the files matching the GLOB pattern of [find](Self::find) in sorted order.
+    ///
+    /// The iterator owns its statement and fetches one path at a time. Therefore, the file system may be used
+    /// between the calls of `next`.
+    pub fn find_iter<T: AsRef<str>>(&self, path: T) -> Result<FindIter<'_>, DatabaseError> {
+        let statement = self
             .database
             .borrow()
-            .prepare_cached(constants::SQL_SIZE)
+            .prepare(constants::SQL_GLOB_AFTER)
             .map_err(|error| error.try_into().expect(DatabaseError::LOGIC_ERROR_MESSAGE))?;
-        handle_query
-            .query_row(params![handle.0], |row| {
-                let raw_size: i64 = row.get_unwrap(0);
-                match raw_size >= 0 {
-                    true => Ok(Some(raw_size as usize)),
-                    false => Ok(None),
-                }
-            })
-            .map_err(|error| error.try_into().expect(DatabaseError::LOGIC_ERROR_MESSAGE))
+        Ok(FindIter {
+            statement,
+            pattern: path.as_ref().into(),
+            last_path: Some(String::new()),
+        })
     }
-}
 
-/// A file stored in the virtual file system.
-#[derive(Debug)]
-pub struct File<'a, D> {
-    file_system: &'a FileSystem<D>,
-    handle: Handle,
-    size: usize,
-    current_index: usize,
-}
+    /// Query the sizes of multiple files at once, in the order of their handles. Handles not referring to a file,
+    /// i.e. missing ones, symbolic links, or files in the trash, are left out.
+    ///
+    /// Like [len](File::len), the sizes of compressed files are the original ones, which requires decompressing their
+    /// last chunk.
+    pub fn sizes(&self, handles: &[Handle]) -> Result<Vec<(Handle, u64)>, DatabaseError> {
+        let sizes = || -> Result<HashMap<i64, u64>, RusqliteError> {
+            let database = self.database.borrow();
+            let mut sizes = HashMap::with_capacity(handles.len());
+            let max_variables =
+                std::cmp::max(database.limit(Limit::SQLITE_LIMIT_VARIABLE_NUMBER), 1) as usize;
+            for batch in handles.chunks(max_variables) {
+                // Only the statement for a single handle is cached, as the others depend on the number of handles.
+                let (mut cached_query, mut uncached_query);
+                let size_query: &mut Statement = if batch.len() == 1 {
+                    cached_query = database.prepare_cached(constants::SQL_SIZE)?;
+                    &mut cached_query
+                } else {
+                    uncached_query = database.prepare(&format!(
+                        "{} ({}) {}",
+                        constants::SQL_SIZES,
+                        vec!["?"; batch.len()].join(", "),
+                        constants::SQL_SIZES_GROUPING
+                    ))?;
+                    &mut uncached_query
+                };
+                let mut rows =
+                    size_query.query(params_from_iter(batch.iter().map(|handle| handle.0)))?;
+                while let Some(row) = rows.next()? {
+                    let (handle, raw_size, flags, last_chunk, chunk_size): (
+                        i64,
+                        i64,
+                        i64,
+                        Option<u32>,
+                        i64,
+                    ) = (
+                        row.get(0)?,
+                        row.get(1)?,
+                        row.get(2)?,
+                        row.get(3)?,
+                        row.get(4)?,
+                    );
+                    let size = self.original_size(
+                        Handle(handle),
+                        raw_size,
+                        flags,
+                        last_chunk,
+                        chunk_size,
+                    )?;
+                    sizes.insert(handle, size);
+                }
+            }
+            Ok(sizes)
+        };
 
-impl<'a, D> File<'a, D>
-where
-    D: BorrowMut<Database>,
-{
-    /// Create a file in the virtual file system.
-    pub fn create<T: AsRef<str>, R: Read>(
-        file_system: &'a mut FileSystem<D>,
-        path: T,
-        data: R,
-        chunk_size: usize,
-    ) -> Result<File<'a, D>, CreationError> {
-        let handle = file_system.create(path.as_ref(), data, chunk_size)?;
-        let size = file_system
-            .size(handle)
-            .map_err(CreationError::DatabaseError)?
-            .expect("Missing file size for existing file");
-        Ok(File {
-            file_system,
-            handle,
-            size,
-            current_index: 0,
-        })
+        let sizes =
+            sizes().map_err(|error| error.try_into().expect(DatabaseError::LOGIC_ERROR_MESSAGE))?;
+        Ok(handles
+            .iter()
+            .filter_map(|handle| sizes.get(&handle.0).map(|size| (*handle, *size)))
+            .collect())
     }
 
-    /// Load a file from the virtual file system.
-    pub fn load<T: AsRef<str>>(
-        file_system: &'a FileSystem<D>,
-        path: T,
-    ) -> Result<File<'a, D>, LoadingError> {
-        match file_system.open(path.as_ref()) {
-            Ok(Some(handle)) => Ok(File {
-                file_system,
-                handle,
-                size: file_system
-                    .size(handle)
-                    .map_err(LoadingError::DatabaseError)?
-                    .expect("Missing file size for existing file"),
-                current_index: 0,
-            }),
-            Ok(None) => Err(LoadingError::FileNotFound),
-            Err(database_error) => Err(LoadingError::DatabaseError(database_error)),
+    /// Query the file system for those files matching any of the GLOB patterns of [find](Self::find).
+    ///
+    /// The paths are returned only once and in sorted order.
+    pub fn find_any<T: AsRef<str>>(&self, patterns: &[T]) -> Result<Vec<String>, DatabaseError> {
+        if patterns.is_empty() {
+            return Ok(Vec::new());
         }
+
+        // The statement is not cached as it depends on the number of patterns.
+        let query = format!(
+            "{} AND ({}) ORDER BY path ASC",
+            constants::SQL_FIND_ANY,
+            vec!["path GLOB ?"; patterns.len()].join(" OR ")
+        );
+        let patterns: Vec<VirtualPath> = patterns
+            .iter()
+            .map(|pattern| pattern.as_ref().into())
+            .collect();
+
+        let database = self.database.borrow();
+        let mut find_query = database
+            .prepare(&query)
+            .map_err(|error| error.try_into().expect(DatabaseError::LOGIC_ERROR_MESSAGE))?;
+        let result = find_query
+            .query_map(
+                params_from_iter(patterns.iter().map(|pattern| pattern.as_ref())),
+                |row| row.get(0),
+            )
+            .map_err(|error| error.try_into().expect(DatabaseError::LOGIC_ERROR_MESSAGE))?
+            .collect::<Result<_, _>>()
+            .map_err(|error| error.try_into().expect(DatabaseError::LOGIC_ERROR_MESSAGE))?;
+
+        Ok(result)
     }
 
-    /// Read the content of a file from the virtual file system.
+    /// Query the total size in bytes of those files matching the GLOB pattern of [find](Self::find).
     ///
-    /// This function does not(!) modify the internal position. In practise, using the Read trait might be more advantageous.
-    pub fn random_read<W: Write>(
-        &self,
-        sink: W,
-        index: usize,
-        length: usize,
-    ) -> Result<usize, ReadError> {
-        self.file_system.read(self.handle, sink, index, length)
+    /// Like [len](File::len), the original size of compressed files is used.
+    pub fn size_matching<T: AsRef<str>>(&self, pattern: T) -> Result<u64, DatabaseError> {
+        let pattern: VirtualPath = pattern.as_ref().into();
+        Ok(self
+            .files_with_sizes(Some(&pattern))?
+            .into_iter()
+            .map(|(_, size)| size)
+            .sum())
     }
 
-    /// Query the length of the file.
-    pub fn len(&self) -> usize {
-        self.size
+    /// Query the paths and sizes in bytes of those files matching the GLOB pattern of [find](Self::find), sorted by path.
+    ///
+    /// Like [len](File::len), the original size of compressed files is used.
+    pub fn list_with_sizes<T: AsRef<str>>(
+        &self,
+        pattern: T,
+    ) -> Result<Vec<(String, u64)>, DatabaseError> {
+        let pattern: VirtualPath = pattern.as_ref().into();
+        self.files_with_sizes(Some(&pattern))
     }
 
-    /// Checks whether the file is empty.
-    pub fn is_empty(&self) -> bool {
-        self.size == 0
+    /// Find the files whose size in bytes lies between the bounds, both inclusive, sorted by path.
+    ///
+    /// Like for [list_with_sizes](Self::list_with_sizes), the original size is used for compressed files.
+    pub fn find_by_size(&self, min: u64, max: Option<u64>) -> Result<Vec<String>, DatabaseError> {
+        Ok(self
+            .files_with_sizes(None)?
+            .into_iter()
+            .filter(|(_, size)| *size >= min && *size <= max.unwrap_or(u64::MAX))
+            .map(|(path, _)| path)
+            .collect())
     }
 
-    /// Query the raw underlying handle.
-    pub fn handle(&self) -> Handle {
-        self.handle
+    /// Query the path and the size of the largest file, preferring the first path on ties.
+    ///
+    /// Like for [list_with_sizes](Self::list_with_sizes), the original size is used for compressed files.
+    pub fn largest_file(&self) -> Result<Option<(String, u64)>, DatabaseError> {
+        let mut largest: Option<(String, u64)> = None;
+        for (path, size) in self.files_with_sizes(None)? {
+            let is_larger = match &largest {
+                Some((_, largest)) => size > *largest,
+                None => true,
+            };
+            if is_larger {
+                largest = Some((path, size));
+            }
+        }
+        Ok(largest)
     }
 
-    /// Delete the file from the virtual file system.
-    pub fn delete(self) -> bool {
-        self.file_system.delete(self.handle) == Ok(1)
+    /// Query the paths and original sizes of the files matching the pattern or of all files, sorted by path.
+    fn files_with_sizes(
+        &self,
+        pattern: Option<&VirtualPath>,
+    ) -> Result<Vec<(String, u64)>, DatabaseError> {
+        let list = || -> Result<Vec<(String, u64)>, RusqliteError> {
+            let database = self.database.borrow();
+            let mut list_query = database.prepare(constants::SQL_LIST_WITH_SIZES)?;
+            let mut rows = list_query.query(named_params! {
+                ":type": constants::FILE_ID,
+                ":pattern": pattern.map(|pattern| pattern.as_ref()),
+            })?;
+
+            let mut result = Vec::new();
+            while let Some(row) = rows.next()? {
+                let size = self.original_size(
+                    Handle(row.get(1)?),
+                    row.get(2)?,
+                    row.get(3)?,
+                    row.get(4)?,
+                    row.get(5)?,
+                )?;
+                result.push((row.get(0)?, size));
+            }
+            Ok(result)
+        };
+        list().map_err(|error| error.try_into().expect(DatabaseError::LOGIC_ERROR_MESSAGE))
     }
-}
 
-impl<'a, D: BorrowMut<Database>> Read for File<'a, D> {
-    fn read(&mut self, mut buf: &mut [u8]) -> IoResult<usize> {
-        let length = std::cmp::min(buf.len(), self.size - self.current_index);
-        match self
-            .file_system
-            .read(self.handle, &mut buf, self.current_index, length)
-        {
-            Ok(written_bytes) => {
-                self.current_index += written_bytes;
-                Ok(written_bytes)
+    /// Determine the original size of a file from the summed length of its chunks, its flags, the number of its
+    /// last chunk and its chunk size. For compressed files, the last chunk is decompressed.
+    fn original_size(
+        &self,
+        handle: Handle,
+        stored_size: i64,
+        flags: i64,
+        last_chunk: Option<u32>,
+        chunk_size: i64,
+    ) -> Result<u64, RusqliteError> {
+        match last_chunk {
+            Some(last_chunk) if flags & constants::COMPRESSED_FLAG != 0 => {
+                let last_size = self.chunk(handle, last_chunk)?.map_or(0, |data| data.len());
+                Ok(last_chunk as u64 * chunk_size as u64 + last_size as u64)
             }
-            Err(error) => Err(IoError::new(ErrorKind::Other, error.error_message())),
+            _ => Ok(stored_size as u64),
         }
     }
 
-    fn read_to_end(&mut self, buf: &mut Vec<u8>) -> IoResult<usize> {
-        *buf = vec![0u8; self.size - self.current_index];
-        self.read(&mut buf[..])
+    /// List the handles and paths of all files, sorted by path.
+    ///
+    /// Unlike [find](Self::find), the handles allow loading the files without looking up their paths again. The entries
+    /// are queried eagerly by this call, so the iterator does not keep the database busy. Use
+    /// [find_iter](Self::find_iter) for fetching one path at a time instead.
+    pub fn entries(&self) -> Result<impl Iterator<Item = (Handle, String)>, DatabaseError> {
+        let entries = || -> Result<Vec<(Handle, String)>, RusqliteError> {
+            // We must collect the result to avoid lifetime issues.
+            self.database
+                .borrow()
+                .prepare(constants::SQL_LIST_FILES)?
+                .query_map(params![constants::FILE_ID], |row| {
+                    Ok((Handle(row.get(0)?), row.get(1)?))
+                })?
+                .collect()
+        };
+        entries()
+            .map(Vec::into_iter)
+            .map_err(|error| error.try_into().expect(DatabaseError::LOGIC_ERROR_MESSAGE))
     }
-}
-
-impl<'a, D: BorrowMut<Database>> TryFrom<(&'a FileSystem<D>, Handle)> for File<'a, D> {
-    type Error = LoadingError;
 
-    fn try_from(value: (&'a FileSystem<D>, Handle)) -> Result<Self, Self::Error> {
-        let (file_system, handle) = value;
-        match file_system.size(handle) {
-            Ok(Some(size)) => Ok(File {
-                file_system,
-                handle,
-                size,
-                current_index: 0,
-            }),
-            Ok(None) => Err(LoadingError::FileNotFound),
-            Err(error) => Err(LoadingError::DatabaseError(error)),
-        }
+    /// List the paths of all files in the order they were created.
+    ///
+    /// Moving a file, i.e. using [rename_replace](Self::rename_replace), keeps its position.
+    pub fn entries_by_insertion(&self) -> Result<Vec<String>, DatabaseError> {
+        let entries = || -> Result<Vec<String>, RusqliteError> {
+            self.database
+                .borrow()
+                .prepare(constants::SQL_LIST_BY_INSERTION)?
+                .query_map(params![constants::FILE_ID], |row| row.get(0))?
+                .collect()
+        };
+        entries().map_err(|error| error.try_into().expect(DatabaseError::LOGIC_ERROR_MESSAGE))
     }
-}
-
-#[cfg(test)]
-mod tests {
-    use std::convert::TryInto;
 
-    use test_case::test_case;
+    /// Find the groups of files sharing the same content, each sorted by path.
+    ///
+    /// Only files of the same size are hashed, where each of them is streamed chunk by chunk through SHA-256 like in
+    /// [file_hashes](Self::file_hashes). Files are considered identical if their hashes match.
+    pub fn find_duplicates(&self) -> Result<Vec<Vec<String>>, ReadError> {
+        let files: BTreeMap<Handle, String> = self
+            .database
+            .borrow()
+            .prepare(constants::SQL_LIST_FILES)?
+            .query_map(params![constants::FILE_ID], |row| {
+                Ok((Handle(row.get(0)?), row.get(1)?))
+            })?
+            .collect::<Result<_, _>>()?;
+        let handles: Vec<Handle> = files.keys().copied().collect();
 
-    use super::super::errors::{CreationError, LoadingError, ReadError};
-    use super::{Database, File, FileSystem, FileSystemError, Handle};
-    use std::io::Read;
+        let mut files_by_size: BTreeMap<u64, Vec<Handle>> = BTreeMap::new();
+        for (handle, size) in self.sizes(&handles).map_err(ReadError::DatabaseError)? {
+            files_by_size.entry(size).or_default().push(handle);
+        }
 
-    #[test]
-    fn test_loading() {
-        let mut connection = Database::open_in_memory().expect("Open in-memory database failed");
+        let mut duplicates = Vec::new();
+        for (size, handles) in files_by_size
+            .into_iter()
+            .filter(|(_, handles)| handles.len() > 1)
         {
-            assert_eq!(
-                FileSystem::load(&mut connection, false).unwrap_err(),
-                FileSystemError::NoFileSystem
+            let mut files_by_hash: HashMap<[u8; 32], Vec<String>> = HashMap::new();
+            for handle in handles {
+                let mut hasher = Sha256::new();
+                for chunk in File::from_parts_unchecked(self, handle, size as usize).chunks() {
+                    hasher.update(&chunk?);
+                }
+                files_by_hash
+                    .entry(hasher.finalize().into())
+                    .or_default()
+                    .push(files[&handle].clone());
+            }
+            duplicates.extend(
+                files_by_hash
+                    .into_values()
+                    .filter(|paths| paths.len() > 1)
+                    .map(|mut paths| {
+                        paths.sort();
+                        paths
+                    }),
             );
         }
-        {
-            FileSystem::load(&mut connection, true).expect("Creating filesystem failed");
+        duplicates.sort();
+        Ok(duplicates)
+    }
+
+    /// Estimate the ratio of the compressed to the original size for up to the given number of uncompressed files.
+    ///
+    /// The chunks of the sampled files are compressed in memory like by [File::create_compressed], without modifying
+    /// the database. Values well below 1.0 indicate worthwhile savings. Without any data sampled, 1.0 is returned.
+    pub fn estimate_compression(&self, sample: usize) -> Result<f64, ReadError> {
+        let handles: Vec<i64> = self
+            .database
+            .borrow()
+            .prepare(constants::SQL_SAMPLE_UNCOMPRESSED)?
+            .query_map(
+                params![
+                    constants::FILE_ID,
+                    i64::try_from(sample).unwrap_or(i64::MAX)
+                ],
+                |row| row.get(0),
+            )?
+            .collect::<Result<_, _>>()?;
+
+        let (mut original_size, mut compressed_size) = (0u64, 0u64);
+        for handle in handles {
+            let handle = Handle(handle);
+            if let Some(size) = self.size(handle).map_err(ReadError::DatabaseError)? {
+                for chunk in File::from_parts_unchecked(self, handle, size).chunks() {
+                    let chunk = chunk?;
+                    original_size += chunk.len() as u64;
+                    compressed_size += compress(&chunk).len() as u64;
+                }
+            }
         }
-        {
-            FileSystem::load(&mut connection, false).expect("Loading created filesystem failed");
+
+        Ok(match original_size {
+            0 => 1.0,
+            _ => compressed_size as f64 / original_size as f64,
+        })
+    }
+
+    /// Calculate the SHA-256 hash of the content of each file, indexed by its path.
+    pub fn file_hashes(&self) -> Result<BTreeMap<String, [u8; 32]>, ReadError> {
+        let files: Vec<(i64, String)> = self
+            .database
+            .borrow()
+            .prepare(constants::SQL_LIST_FILES)?
+            .query_map(params![constants::FILE_ID], |row| {
+                Ok((row.get(0)?, row.get(1)?))
+            })?
+            .collect::<Result<_, _>>()?;
+
+        let mut hashes = BTreeMap::new();
+        for (handle, path) in files {
+            let handle = Handle(handle);
+            if let Some(size) = self.size(handle).map_err(ReadError::DatabaseError)? {
+                let mut hasher = Sha256::new();
+                for chunk in File::from_parts_unchecked(self, handle, size).chunks() {
+                    hasher.update(&chunk?);
+                }
+                hashes.insert(path, hasher.finalize().into());
+            }
         }
+        Ok(hashes)
     }
 
-    #[test_case(0, 0, 0, 0, false; "File size: 0, Chunk size: 0, First index: 0, Length: 0")]
-    #[test_case(1, 0, 0, 1, false; "File size: 1, Chunk size: 0, First index: 0, Length: 1")]
-    #[test_case(3, 0, 0, 3, false; "File size: 3, Chunk size: 0, First index: 0, Length: 3")]
-    #[test_case(0, 1, 0, 0, false; "File size: 0, Chunk size: 1, First index: 0, Length: 0")]
-    #[test_case(1, 1, 0, 1, false; "File size: 1, Chunk size: 1, First index: 0, Length: 1")]
-    #[test_case(3, 1, 0, 3, false; "File size: 3, Chunk size: 1, First index: 0, Length: 3")]
-    #[test_case(0, 3, 0, 0, false; "File size: 0, Chunk size: 3, First index: 0, Length: 0")]
-    #[test_case(1, 3, 0, 1, false; "File size: 1, Chunk size: 3, First index: 0, Length: 1")]
-    #[test_case(3, 3, 0, 3, false; "File size: 3, Chunk size: 3, First index: 0, Length: 3")]
-    #[test_case(0, 4, 0, 0, false; "File size: 0, Chunk size: 4, First index: 0, Length: 0")]
-    #[test_case(1, 4, 0, 1, false; "File size: 1, Chunk size: 4, First index: 0, Length: 1")]
-    #[test_case(3, 4, 0, 3, false; "File size: 3, Chunk size: 4, First index: 0, Length: 3")]
-    // Test random reads
-    #[test_case(3, 0, 1, 2, false; "File size: 3, Chunk size: 0, First index: 1, Length: 2")]
-    #[test_case(3, 1, 1, 2, false; "File size: 3, Chunk size: 1, First index: 1, Length: 2")]
-    #[test_case(3, 3, 1, 2, false; "File size: 3, Chunk size: 3, First index: 1, Length: 2")]
-    #[test_case(3, 4, 1, 2, false; "File size: 3, Chunk size: 4, First index: 1, Length: 2")]
-    #[test_case(3, 0, 2, 1, false; "File size: 3, Chunk size: 0, First index: 2, Length: 1")]
-    #[test_case(3, 1, 2, 1, false; "File size: 3, Chunk size: 1, First index: 2, Length: 1")]
-    #[test_case(3, 3, 2, 1, false; "File size: 3, Chunk size: 3, First index: 2, Length: 1")]
-    #[test_case(3, 4, 2, 1, false; "File size: 3, Chunk size: 4, First index: 2, Length: 1")]
-    #[test_case(6, 4, 2, 1, false; "File size: 4, Chunk size: 4, First index: 2, Length: 2")]
-    // Test out-of-bounds
-    #[test_case(0, 0, 0, 1, true; "File size: 0, Chunk size: 0, First index: 0, Length: 1 --> OUT OF BOUNDS!")]
-    #[test_case(1, 0, 1, 1, true; "File size: 1, Chunk size: 0, First index: 1, Length: 1 --> OUT OF BOUNDS!")]
-    #[test_case(1, 0, 1, 2, true; "File size: 1, Chunk size: 0, First index: 1, Length: 2 --> OUT OF BOUNDS!")]
-    #[test_case(3, 0, 1, 3, true; "File size: 3, Chunk size: 0, First index: 1, Length: 3 --> OUT OF BOUNDS!")]
-    #[test_case(3, 0, 2, 2, true; "File size: 3, Chunk size: 0, First index: 2, Length: 2 --> OUT OF BOUNDS!")]
-    #[test_case(0, 1, 0, 1, true; "File size: 0, Chunk size: 1, First index: 0, Length: 1 --> OUT OF BOUNDS!")]
-    #[test_case(1, 1, 1, 1, true; "File size: 1, Chunk size: 1, First index: 1, Length: 1 --> OUT OF BOUNDS!")]
-    #[test_case(1, 1, 1, 2, true; "File size: 1, Chunk size: 1, First index: 1, Length: 2 --> OUT OF BOUNDS!")]
-    #[test_case(3, 1, 1, 3, true; "File size: 3, Chunk size: 1, First index: 1, Length: 3 --> OUT OF BOUNDS!")]
-    #[test_case(3, 1, 2, 2, true; "File size: 3, Chunk size: 1, First index: 2, Length: 2 --> OUT OF BOUNDS!")]
-    #[test_case(0, 3, 0, 1, true; "File size: 0, Chunk size: 3, First index: 0, Length: 1 --> OUT OF BOUNDS!")]
-    #[test_case(1, 3, 1, 1, true; "File size: 1, Chunk size: 3, First index: 1, Length: 1 --> OUT OF BOUNDS!")]
-    #[test_case(1, 3, 1, 2, true; "File size: 1, Chunk size: 3, First index: 1, Length: 2 --> OUT OF BOUNDS!")]
-    #[test_case(3, 3, 1, 3, true; "File size: 3, Chunk size: 3, First index: 1, Length: 3 --> OUT OF BOUNDS!")]
-    #[test_case(3, 3, 2, 2, true; "File size: 3, Chunk size: 3, First index: 2, Length: 2 --> OUT OF BOUNDS!")]
-    #[test_case(0, 4, 0, 1, true; "File size: 0, Chunk size: 4, First index: 0, Length: 1 --> OUT OF BOUNDS!")]
-    #[test_case(1, 4, 1, 1, true; "File size: 1, Chunk size: 4, First index: 1, Length: 1 --> OUT OF BOUNDS!")]
-    #[test_case(1, 4, 1, 2, true; "File size: 1, Chunk size: 4, First index: 1, Length: 2 --> OUT OF BOUNDS!")]
-    #[test_case(3, 4, 1, 3, true; "File size: 3, Chunk size: 4, First index: 1, Length: 3 --> OUT OF BOUNDS!")]
-    #[test_case(3, 4, 2, 2, true; "File size: 3, Chunk size: 4, First index: 2, Length: 2 --> OUT OF BOUNDS!")]
-    // Special case: It is always save to read data of length 0
-    #[test_case(0, 0, 1, 0, false; "File size: 0, Chunk size: 0, First index: 1, Length: 0")]
-    #[test_case(0, 1, 1, 0, false; "File size: 0, Chunk size: 1, First index: 1, Length: 0")]
-    #[test_case(0, 3, 1, 0, false; "File size: 0, Chunk size: 3, First index: 1, Length: 0")]
-    #[test_case(0, 4, 1, 0, false; "File size: 0, Chunk size: 4, First index: 1, Length: 0")]
-    fn test_file_handling(
-        file_size: u8,
+    /// Calculate a hash over the paths and contents of all files, which is independent of the order of their creation.
+    ///
+    /// Two file systems with the same root contain the same files. Compare the [file hashes](Self::file_hashes) to find the differing files.
+    pub fn merkle_root(&self) -> Result<[u8; 32], ReadError> {
+        let mut hasher = Sha256::new();
+        for (path, hash) in self.file_hashes()? {
+            // The length prevents ambiguities between the path and the following hash.
+            hasher.update(&(path.len() as u64).to_le_bytes());
+            hasher.update(path.as_bytes());
+            hasher.update(&hash);
+        }
+        Ok(hasher.finalize().into())
+    }
+
+    /// Query the file system for those files with a specific GLOB pattern using the semantic of a shell.
+    ///
+    /// Unlike in [find](Self::find), both the '?' and the '*' placeholder do not match the separator '/'.
+    /// Therefore, "folder/*" only matches the immediate children of "folder". Use '**' to match across
+    /// separators, i.e. "folder/**" for all files below "folder".
+    pub fn glob<T: AsRef<str>>(&self, pattern: T) -> Result<Vec<String>, DatabaseError> {
+        let pattern: VirtualPath = pattern.as_ref().into();
+        let glob = Glob::new(pattern.as_ref());
+        let mut paths = self.find(glob.sql_pattern())?;
+        paths.retain(|path| glob.is_match(path));
+        Ok(paths)
+    }
+
+    /// Query the files created at or after the given point in time.
+    ///
+    /// File systems created by older versions of this library do not store timestamps. For them, an error is returned.
+    pub fn modified_since(&self, since: SystemTime) -> Result<Vec<String>, DatabaseError> {
+        let since = since
+            .duration_since(UNIX_EPOCH)
+            .map(|duration| duration.as_millis() as i64)
+            .unwrap_or(0);
+
+        // The statement is not cached as it could not be compiled for older file systems.
+        let mut modified_query = self
+            .database
+            .borrow()
+            .prepare(constants::SQL_MODIFIED_SINCE)
+            .map_err(|error| error.try_into().expect(DatabaseError::LOGIC_ERROR_MESSAGE))?;
+
+        let result = modified_query
+            .query_map(params![constants::FILE_ID, since], |row| row.get(0))
+            .map_err(|error| error.try_into().expect(DatabaseError::LOGIC_ERROR_MESSAGE))?
+            .collect::<Result<_, _>>()
+            .map_err(|error| error.try_into().expect(DatabaseError::LOGIC_ERROR_MESSAGE))?;
+
+        Ok(result)
+    }
+
+    /// Query the distinct top-level entries of the file system, i.e. the first segment of each path.
+    pub fn roots(&self) -> Result<Vec<String>, DatabaseError> {
+        let mut roots_query = self
+            .database
+            .borrow()
+            .prepare_cached(constants::SQL_ROOTS)
+            .map_err(|error| error.try_into().expect(DatabaseError::LOGIC_ERROR_MESSAGE))?;
+
+        let result = roots_query
+            .query_map(
+                params![constants::FILE_ID, self.separator.to_string()],
+                |row| row.get(0),
+            )
+            .map_err(|error| error.try_into().expect(DatabaseError::LOGIC_ERROR_MESSAGE))?
+            .collect::<Result<_, _>>()
+            .map_err(|error| error.try_into().expect(DatabaseError::LOGIC_ERROR_MESSAGE))?;
+
+        Ok(result)
+    }
+
+    /// Query whether the path refers to a file, to a directory containing files or to nothing at all.
+    ///
+    /// Symbolic links are not followed. The empty path refers to the root, which is only a directory if files exist.
+    pub fn path_kind<T: AsRef<str>>(&self, path: T) -> Result<PathKind, DatabaseError> {
+        let path: VirtualPath = path.as_ref().into();
+        let (lower, upper) = self.directory_bounds(path.as_ref());
+        let (is_file, is_directory): (bool, bool) = self
+            .database
+            .borrow()
+            .query_row(
+                constants::SQL_PATH_KIND,
+                named_params! {
+                    ":type": constants::FILE_ID,
+                    ":path": path.as_ref(),
+                    ":lower": lower,
+                    ":upper": upper
+                },
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .map_err(|error| error.try_into().expect(DatabaseError::LOGIC_ERROR_MESSAGE))?;
+        Ok(match (is_file, is_directory) {
+            (true, _) => PathKind::File,
+            (false, true) => PathKind::Directory,
+            (false, false) => PathKind::Missing,
+        })
+    }
+
+    /// List the names of the files and directories immediately below the directory, split at the configured separator.
+    ///
+    /// The names are sorted and contain each directory only once. The empty path refers to the root.
+    pub fn list_dir<T: AsRef<str>>(&self, path: T) -> Result<Vec<String>, DatabaseError> {
+        let directory: VirtualPath = path.as_ref().into();
+        let (prefix, paths) = match directory.as_ref().trim_end_matches(self.separator) {
+            "" => (String::new(), self.all_paths()?),
+            directory => (
+                format!("{}{}", directory, self.separator),
+                self.paths_below(directory)?,
+            ),
+        };
+
+        let mut names = BTreeSet::new();
+        for path in paths {
+            if let Some(remaining) = path.strip_prefix(prefix.as_str()) {
+                if let Some(name) = remaining.split(self.separator).next() {
+                    if !name.is_empty() {
+                        names.insert(name.to_string());
+                    }
+                }
+            }
+        }
+        Ok(names.into_iter().collect())
+    }
+
+    /// Determine the range of paths below a directory, i.e. from "<directory>/" to "<directory>0" for the separator '/'.
+    fn directory_bounds(&self, directory: &str) -> (String, String) {
+        // Without a following character, the range is closed by the largest one. Only paths containing it are missed.
+        let next_separator = (self.separator as u32 + 1..=char::MAX as u32)
+            .find_map(char::from_u32)
+            .unwrap_or(char::MAX);
+        (
+            format!("{}{}", directory, self.separator),
+            format!("{}{}", directory, next_separator),
+        )
+    }
+
+    fn paths_below(&self, directory: &str) -> Result<Vec<String>, DatabaseError> {
+        let (lower, upper) = self.directory_bounds(directory);
+        self.database
+            .borrow()
+            .prepare_cached(constants::SQL_PREFIX)
+            .and_then(|mut statement| {
+                statement
+                    .query_map(
+                        named_params! {":type": constants::FILE_ID, ":lower": lower, ":upper": upper},
+                        |row| row.get(0),
+                    )?
+                    .collect()
+            })
+            .map_err(|error| error.try_into().expect(DatabaseError::LOGIC_ERROR_MESSAGE))
+    }
+
+    fn all_paths(&self) -> Result<Vec<String>, DatabaseError> {
+        self.database
+            .borrow()
+            .prepare(constants::SQL_ALL_PATHS)
+            .and_then(|mut statement| {
+                statement
+                    .query_map(params![constants::FILE_ID], |row| row.get(0))?
+                    .collect()
+            })
+            .map_err(|error| error.try_into().expect(DatabaseError::LOGIC_ERROR_MESSAGE))
+    }
+
+    /// Group the names of all files by the path of their parent directory, i.e. for displaying a tree.
+    ///
+    /// Files in the root are listed under the empty path. Directories only containing other directories are missing.
+    pub fn tree(&self) -> Result<BTreeMap<String, Vec<String>>, DatabaseError> {
+        let mut tree: BTreeMap<String, Vec<String>> = BTreeMap::new();
+        for path in self.all_paths()? {
+            let (parent, name) = match path.rfind(self.separator) {
+                Some(index) => (&path[..index], &path[index + self.separator.len_utf8()..]),
+                None => ("", path.as_str()),
+            };
+            tree.entry(parent.to_string())
+                .or_default()
+                .push(name.to_string());
+        }
+        Ok(tree)
+    }
+
+    /// List the files which deleting the directory at the given path would remove, without modifying anything.
+    ///
+    /// Unlike `find`, the directory is matched literally. The empty path refers to the root and covers all files.
+    pub fn plan_delete_dir<T: AsRef<str>>(&self, path: T) -> Result<Vec<String>, DatabaseError> {
+        let path: VirtualPath = path.as_ref().into();
+        match path.as_ref() {
+            "" => self.all_paths(),
+            directory => self.paths_below(directory),
+        }
+    }
+
+    /// Move a file into another virtual file system, keeping its path, chunk size and compression.
+    ///
+    /// Symbolic links are moved themselves instead of their targets, which may dangle in the destination.
+    ///
+    /// As both file systems use different connections, the operation is not atomic: The file is copied first and only deleted from this file system afterwards. If the copy fails, the source is left untouched. If the deletion fails, the file exists in both file systems.
+    pub fn transfer_to<D2: BorrowMut<Database>, T: AsRef<str>>(
+        &mut self,
+        destination: &mut FileSystem<D2>,
+        path: T,
+    ) -> Result<Handle, TransferError> {
+        let path: VirtualPath = path.as_ref().into();
+        let database = self.database.borrow();
+        let (source_handle, entry_type): (i64, u32) = database
+            .query_row(constants::SQL_GET_HANDLE, params![path.as_ref()], |row| {
+                Ok((row.get(0)?, row.get(1)?))
+            })
+            .optional()
+            .map_err(LoadingError::from)?
+            .ok_or(LoadingError::FileNotFound)?;
+        let source_handle = Handle(source_handle);
+
+        let handle = match entry_type {
+            constants::SYMLINK_ID => {
+                let target: String = database
+                    .query_row(
+                        constants::SQL_GET_SYMLINK_TARGET,
+                        params![source_handle.0],
+                        |row| row.get(0),
+                    )
+                    .map_err(LoadingError::from)?;
+                destination.create_symlink(path.as_ref(), target)?
+            }
+            constants::FILE_ID => {
+                let (chunk_size, flags): (i64, i64) = database
+                    .query_row(
+                        constants::SQL_GET_LAYOUT,
+                        params![source_handle.0, constants::FILE_ID],
+                        |row| Ok((row.get(0)?, row.get(1)?)),
+                    )
+                    .map_err(LoadingError::from)?;
+                if flags & constants::LOCKED_FLAG != 0 {
+                    return Err(LoadingError::FileLocked.into());
+                }
+                let size = self
+                    .size(source_handle)
+                    .map_err(LoadingError::DatabaseError)?
+                    .ok_or(LoadingError::FileNotFound)?;
+                let file = File::from_parts_unchecked(self, source_handle, size);
+                let (handle, _) = destination.create_entry(
+                    path,
+                    constants::FILE_ID,
+                    flags & constants::COMPRESSED_FLAG,
+                    file,
+                    chunk_size as usize,
+                )?;
+                handle
+            }
+            _ => return Err(LoadingError::FileNotFound.into()),
+        };
+
+        match self.delete(source_handle) {
+            Ok(1) => Ok(handle),
+            Ok(_) => Err(TransferError::Loading(LoadingError::FileNotFound)),
+            Err(LoadingError::DatabaseError(error)) => Err(TransferError::Deletion(error)),
+            Err(error) => Err(TransferError::Loading(error)),
+        }
+    }
+
+    /// Create a file whose path must be located below the given directory after normalization.
+    ///
+    /// This prevents paths like "uploads/../secret" from escaping into other directories.
+    pub fn create_scoped<T: AsRef<str>, U: AsRef<str>, R: Read>(
+        &mut self,
+        base: T,
+        path: U,
+        data: R,
         chunk_size: usize,
-        index: usize,
+    ) -> Result<Handle, CreationError> {
+        let base: VirtualPath = base.as_ref().into();
+        let path: VirtualPath = path.as_ref().into();
+        if !path.is_below(&base) {
+            return Err(CreationError::OutsideScope);
+        }
+        self.create(path, data, chunk_size)
+            .map(|(handle, _)| handle)
+    }
+
+    /// Create a file of the given size filled with zeros, i.e. to overwrite its content later on.
+    ///
+    /// All chunks are allocated at once, so writes within the size never exceed the bounds of the file.
+    pub fn create_empty<T: AsRef<str>>(
+        &mut self,
+        path: T,
+        size: usize,
+        chunk_size: usize,
+    ) -> Result<File<'_, D>, CreationError> {
+        let zeros = std::io::repeat(0).take(size as u64);
+        File::create(self, path, zeros, chunk_size)
+    }
+
+    /// Copy a range of bytes from one file into an existing range of another file, returning the number of bytes copied.
+    ///
+    /// Both ranges must be within the bounds of their files, as files are never resized. Copying within a single
+    /// file is supported, even if the ranges overlap.
+    pub fn copy_range<T: AsRef<str>, U: AsRef<str>>(
+        &mut self,
+        source_path: T,
+        source_offset: usize,
         length: usize,
-        is_out_of_bounds: bool,
-    ) {
-        let data: Vec<_> = (0..file_size).into_iter().collect();
-        let path = "file";
-        let mut connection = Database::open_in_memory().expect("Open in-memory database failed");
-        let mut file_system =
-            FileSystem::load(&mut connection, true).expect("Creating filesystem failed");
+        destination_path: U,
+        destination_offset: usize,
+    ) -> Result<usize, MatryoshkaError> {
+        let source = File::load(self, source_path.as_ref())?;
+        let destination = File::load(self, destination_path.as_ref())?;
+        self.check_unlocked(destination.handle())?;
+        let is_in_bounds = |file: &File<'_, D>, offset: usize| matches!(offset.checked_add(length), Some(end) if end <= file.len());
+        if !is_in_bounds(&source, source_offset) || !is_in_bounds(&destination, destination_offset)
+        {
+            return Err(ReadError::OutOfBounds.into());
+        }
+
+        // The range is read completely beforehand to support overlapping ranges.
+        let mut buffer = Vec::with_capacity(length);
+        source.random_read(&mut buffer, source_offset, length)?;
+        self.write(destination.handle(), &buffer, destination_offset)
+            .map_err(|error| {
+                MatryoshkaError::Database(
+                    error.try_into().expect(DatabaseError::LOGIC_ERROR_MESSAGE),
+                )
+            })?;
+        Ok(length)
+    }
+
+    /// Create a symbolic link pointing to another path in the virtual file system.
+    ///
+    /// The target is stored as an absolute virtual path and does not need to exist at the time of creation.
+    pub fn create_symlink<T: AsRef<str>, U: AsRef<str>>(
+        &mut self,
+        link_path: T,
+        target_path: U,
+    ) -> Result<Handle, CreationError> {
+        let target_path: VirtualPath = target_path.as_ref().into();
+        let target = target_path.as_ref().as_bytes();
+        self.create_entry(
+            link_path.as_ref(),
+            constants::SYMLINK_ID,
+            0,
+            target,
+            std::cmp::max(target.len(), 1),
+        )
+        .map(|(handle, _)| handle)
+    }
+
+    /// Create an empty file if none exists at the path. Otherwise, its modification time is updated.
+    pub fn touch<T: AsRef<str>>(&mut self, path: T) -> Result<Handle, CreationError> {
+        match self.open(path.as_ref()) {
+            Ok(handle) => {
+                self.database
+                    .borrow()
+                    .execute(constants::SQL_TOUCH, params![handle.0])?;
+                Ok(handle)
+            }
+            Err(LoadingError::FileNotFound) => self
+                .create(path.as_ref(), &[][..], 1)
+                .map(|(handle, _)| handle),
+            Err(LoadingError::DatabaseError(error)) => Err(CreationError::DatabaseError(error)),
+            // Opening a file ignores its lock, so only symbolic link loops and ambiguous paths remain.
+            Err(_) => Err(CreationError::FileExists),
+        }
+    }
+
+    fn create<T: Into<VirtualPath>, R: Read>(
+        &mut self,
+        path: T,
+        data: R,
+        chunk_size: usize,
+    ) -> Result<(Handle, usize), CreationError> {
+        self.create_entry(path, constants::FILE_ID, 0, data, chunk_size)
+    }
+
+    fn create_compressed<T: Into<VirtualPath>, R: Read>(
+        &mut self,
+        path: T,
+        data: R,
+        chunk_size: usize,
+    ) -> Result<(Handle, usize), CreationError> {
+        self.create_entry(
+            path,
+            constants::FILE_ID,
+            constants::COMPRESSED_FLAG,
+            data,
+            chunk_size,
+        )
+    }
+
+    /// Create an entry of the given type, returning its handle and the number of bytes written.
+    fn create_entry<T: Into<VirtualPath>, R: Read>(
+        &mut self,
+        path: T,
+        entry_type: u32,
+        flags: i64,
+        data: R,
+        chunk_size: usize,
+    ) -> Result<(Handle, usize), CreationError> {
+        // Create the transaction to return safely on errors.
+        let transaction = self.database.borrow_mut().transaction()?;
+        let result = FileSystem::<D>::write_entry(
+            &transaction,
+            path.into(),
+            entry_type,
+            flags,
+            data,
+            chunk_size,
+        )?;
+        transaction.commit()?;
+        Ok(result)
+    }
+
+    /// Write an entry of the given type within an existing transaction.
+    fn write_entry<R: Read>(
+        transaction: &Database,
+        path: VirtualPath,
+        entry_type: u32,
+        flags: i64,
+        mut data: R,
+        chunk_size: usize,
+    ) -> Result<(Handle, usize), CreationError> {
+        let chunk_size = FileSystem::<D>::chunk_size(transaction, chunk_size);
+        let (handle, size) = {
+            let mut create_blob_statement =
+                transaction.prepare_cached(constants::SQL_CREATE_BLOB)?;
+
+            let handle =
+                FileSystem::<D>::insert_handle(transaction, path, entry_type, flags, chunk_size)?;
+            let is_compressed = flags & constants::COMPRESSED_FLAG != 0;
+
+            let mut buffer = vec![0u8; chunk_size];
+            let mut chunk_index = 0u32;
+            let mut bytes_written = 0usize;
+            loop {
+                match data.read(buffer.as_mut()) {
+                    Ok(size) => {
+                        let chunk = match is_compressed {
+                            true => compress(&buffer[0..size]),
+                            false => buffer[0..size].to_vec(),
+                        };
+                        create_blob_statement
+                            .execute(params![handle, chunk_index, &chunk])
+                            .map_err(|error| CreationError::from_chunk_error(error, size))?;
+                        bytes_written += size;
+                        if size != chunk_size {
+                            break;
+                        }
+                        chunk_index += 1;
+                    }
+                    Err(error) if error.kind() == ErrorKind::Interrupted => {
+                        // Just try again...
+                    }
+                    Err(error) => {
+                        return Err(error.into());
+                    }
+                }
+            }
+
+            (handle, bytes_written)
+        };
+
+        Ok((Handle(handle), size))
+    }
+
+    /// Start a batch of changes which become visible to other connections at once when committed.
+    ///
+    /// Until the batch is finished, the file system is borrowed. Dropping the batch rolls it back.
+    pub fn begin_batch(&mut self) -> Result<BatchWriter<'_>, DatabaseError> {
+        let use_trash = self.use_trash;
+        let transaction = self
+            .database
+            .borrow_mut()
+            .transaction()
+            .map_err(|error| error.try_into().expect(DatabaseError::LOGIC_ERROR_MESSAGE))?;
+        Ok(BatchWriter {
+            transaction,
+            use_trash,
+        })
+    }
+
+    /// Create a file in the virtual file system whose content is written incrementally.
+    ///
+    /// The file is only stored once the writer is finished. Dropping it beforehand aborts the creation.
+    pub fn create_writer<T: AsRef<str>>(
+        &mut self,
+        path: T,
+        chunk_size: usize,
+    ) -> Result<FileWriter<'_>, CreationError> {
+        let database = self.database.borrow_mut();
+        let chunk_size = FileSystem::<D>::chunk_size(database, chunk_size);
+        let transaction = database.transaction()?;
+        let handle = FileSystem::<D>::insert_handle(
+            &transaction,
+            path.as_ref().into(),
+            constants::FILE_ID,
+            0,
+            chunk_size,
+        )?;
+
+        Ok(FileWriter {
+            transaction,
+            handle: Handle(handle),
+            buffer: Vec::with_capacity(chunk_size),
+            chunk_size,
+            chunk_index: 0,
+        })
+    }
+
+    /// Query the largest chunk size supported by the database in bytes. Larger values fall back to the default.
+    pub fn max_chunk_size(&self) -> usize {
+        FileSystem::<D>::max_blob_size(self.database.borrow())
+    }
+
+    fn max_blob_size(database: &Database) -> usize {
+        database.limit(Limit::SQLITE_LIMIT_LENGTH) as usize
+    }
+
+    /// Choose the chunk size actually used, falling back to the default for invalid values.
+    fn chunk_size(database: &Database, chunk_size: usize) -> usize {
+        match chunk_size {
+            value if value > 0 && value <= FileSystem::<D>::max_blob_size(database) => value,
+            _ => constants::DEFAULT_BYTE_BLOB_SIZE,
+        }
+    }
+
+    /// Check whether a new entry could be created at the path, returning its normalized form.
+    ///
+    /// The normalized path must not be empty, must fit into the database and must not contain control characters.
+    /// This does not check whether an entry exists at the path already.
+    pub fn validate_path<T: AsRef<str>>(&self, path: T) -> Result<VirtualPath, PathError> {
+        FileSystem::<D>::check_path(self.database.borrow(), path.as_ref().into())
+    }
+
+    /// Check whether a new entry could be created at the path. See [validate_path](Self::validate_path) for the rules.
+    pub fn is_valid_path<T: AsRef<str>>(&self, path: T) -> bool {
+        self.validate_path(path).is_ok()
+    }
+
+    fn check_path(database: &Database, path: VirtualPath) -> Result<VirtualPath, PathError> {
+        let max_length = FileSystem::<D>::max_blob_size(database);
+        if path.as_ref().is_empty() {
+            Err(PathError::Empty)
+        } else if path.as_ref().len() > max_length {
+            Err(PathError::TooLong(max_length))
+        } else if let Some(value) = path.as_ref().chars().find(|value| value.is_control()) {
+            Err(PathError::ForbiddenCharacter(value))
+        } else {
+            Ok(path)
+        }
+    }
+
+    fn insert_handle(
+        transaction: &Database,
+        path: VirtualPath,
+        entry_type: u32,
+        flags: i64,
+        chunk_size: usize,
+    ) -> Result<i64, CreationError> {
+        let path = FileSystem::<D>::check_path(transaction, path)?;
+        let mut create_handle_statement =
+            transaction.prepare_cached(constants::SQL_CREATE_HANDLE)?;
+        match create_handle_statement.insert(params![
+            path.as_ref(),
+            entry_type,
+            chunk_size as i32,
+            flags
+        ]) {
+            Ok(handle) => Ok(handle),
+            Err(RusqliteError::SqliteFailure(error, _))
+                if error.code == ErrorCode::ConstraintViolation =>
+            {
+                Err(CreationError::FileExists)
+            }
+            Err(error) => Err(error.into()),
+        }
+    }
+
+    fn open<T: Into<VirtualPath>>(&self, path: T) -> Result<Handle, LoadingError> {
+        let mut path = path.into();
+        let mut handle_query = self
+            .database
+            .borrow()
+            .prepare_cached(constants::SQL_GET_HANDLE)?;
+
+        // Follow symbolic links until a file is found, giving up on (probably) cyclic links.
+        for _ in 0..=constants::MAX_SYMLINK_DEPTH {
+            let (handle, entry_type): (i64, u32) = handle_query
+                .query_row(params![path.as_ref()], |row| Ok((row.get(0)?, row.get(1)?)))
+                .optional()?
+                .ok_or(LoadingError::FileNotFound)?;
+
+            match entry_type {
+                constants::FILE_ID => return Ok(Handle(handle)),
+                constants::SYMLINK_ID => {
+                    let mut target_query = self
+                        .database
+                        .borrow()
+                        .prepare_cached(constants::SQL_GET_SYMLINK_TARGET)?;
+                    let target: String =
+                        target_query.query_row(params![handle], |row| row.get(0))?;
+                    path = target.into();
+                }
+                _ => return Err(LoadingError::FileNotFound),
+            }
+        }
+
+        Err(LoadingError::TooManySymlinks)
+    }
+
+    /// Resolve a path ignoring the case of ASCII characters, preferring an exact match.
+    fn open_ci<T: Into<VirtualPath>>(&self, path: T) -> Result<Handle, LoadingError> {
+        let path = path.into();
+        match self.open(path.as_ref()) {
+            Err(LoadingError::FileNotFound) => {}
+            result => return result,
+        }
+
+        let paths: Vec<String> = self
+            .database
+            .borrow()
+            .prepare_cached(constants::SQL_GET_PATH_NOCASE)?
+            .query_map(params![path.as_ref()], |row| row.get(0))?
+            .collect::<Result<_, _>>()?;
+        match paths.as_slice() {
+            [] => Err(LoadingError::FileNotFound),
+            [path] => self.open(path.as_str()),
+            _ => Err(LoadingError::AmbiguousPath),
+        }
+    }
+
+    fn chunk(&self, handle: Handle, chunk_num: u32) -> Result<Option<Vec<u8>>, RusqliteError> {
+        let mut chunk_statement = self
+            .database
+            .borrow()
+            .prepare_cached(constants::SQL_GET_CHUNK)?;
+        let chunk: Option<(Vec<u8>, i64)> = chunk_statement
+            .query_row(params![handle.0, chunk_num], |row| {
+                Ok((row.get(0)?, row.get(1)?))
+            })
+            .optional()?;
+        Ok(match chunk {
+            Some((data, flags)) if flags & constants::COMPRESSED_FLAG != 0 => {
+                Some(decompress(&data)?)
+            }
+            chunk => chunk.map(|(data, _)| data),
+        })
+    }
+
+    /// Pass the chunks of a file in order to the function, reading uncompressed ones into a single reused buffer.
+    fn for_each_chunk<E, F: FnMut(&[u8]) -> Result<(), E>>(
+        &self,
+        handle: Handle,
+        mut f: F,
+    ) -> Result<(), ProcessError<E>> {
+        let flags: i64 = self
+            .database
+            .borrow()
+            .query_row(constants::SQL_GET_FLAGS, params![handle.0], |row| {
+                row.get(0)
+            })
+            .map_err(ReadError::from)?;
+        let is_compressed = flags & constants::COMPRESSED_FLAG != 0;
+        let chunk_ids: Vec<i64> = self
+            .database
+            .borrow()
+            .prepare_cached(constants::SQL_GET_CHUNK_IDS)
+            .and_then(|mut statement| {
+                statement
+                    .query_map(params![handle.0], |row| row.get(0))?
+                    .collect()
+            })
+            .map_err(ReadError::from)?;
+
+        let mut buffer = Vec::new();
+        for chunk_id in chunk_ids {
+            let read_chunk = |buffer: &mut Vec<u8>| -> Result<(), RusqliteError> {
+                let blob = self.database.borrow().blob_open(
+                    DatabaseName::Main,
+                    constants::DATA_TABLE,
+                    "data",
+                    chunk_id,
+                    true,
+                )?;
+                buffer.resize(blob.size() as usize, 0);
+                blob.read_at_exact(buffer, 0)?;
+                Ok(())
+            };
+            read_chunk(&mut buffer).map_err(ReadError::from)?;
+
+            // Like in chunks, the empty chunk after a full one is skipped.
+            if buffer.is_empty() {
+                continue;
+            }
+            match is_compressed {
+                true => f(&decompress(&buffer).map_err(ReadError::from)?),
+                false => f(&buffer),
+            }
+            .map_err(ProcessError::Callback)?;
+        }
+        Ok(())
+    }
+
+    /// Read the complete content of a blob as it is stored.
+    fn read_blob(blob: &rusqlite::blob::Blob<'_>) -> Result<Vec<u8>, RusqliteError> {
+        let mut data = vec![0u8; blob.size() as usize];
+        blob.read_at_exact(&mut data, 0)?;
+        Ok(data)
+    }
+
+    fn read<W: Write, F: FnMut(u32, usize)>(
+        &self,
+        handle: Handle,
+        mut sink: W,
+        index: usize,
+        length: usize,
+        mut on_chunk: F,
+    ) -> Result<usize, ReadError> {
+        let index = i64::try_from(index).map_err(|_| ReadError::FileSystemLimits)?;
+
+        // Check length and exit early if not data is of interest
+        let length = i64::try_from(length).map_err(|_| ReadError::FileSystemLimits)?;
+        if length == 0 {
+            return Ok(0);
+        }
+
+        // Prepare the statements regarding the blobs
+        let mut blobs_statement = self
+            .database
+            .borrow()
+            .prepare_cached(constants::SQL_GET_BLOBS)?;
+
+        // Let SQLite calculate all the key characteristics
+        let mut chuck_size: Option<i64> = None;
+        let mut blob_iter = blobs_statement.query_map(
+            &[
+                (":handle", &handle.0),
+                (":index", &index),
+                (":size", &length),
+            ],
+            |row| {
+                let flags: i64 = row.get(3)?;
+                let chunk_num: i64 = row.get(1)?;
+                Ok(match chuck_size {
+                    Some(chunk_size) => (0usize, row.get(0)?, chunk_size, flags, chunk_num),
+                    None => {
+                        let raw_chunk_size: i64 = row.get(2)?;
+                        chuck_size = Some(raw_chunk_size);
+                        let offset: i64 = index - (chunk_num * raw_chunk_size);
+                        (
+                            offset as usize,
+                            row.get(0)?,
+                            raw_chunk_size,
+                            flags,
+                            chunk_num,
+                        )
+                    }
+                })
+            },
+        )?;
+
+        // Reads starting at a chunk boundary and spanning whole chunks copy complete blobs.
+        let first_blob = blob_iter.next().ok_or(ReadError::OutOfBounds)??;
+        let is_compressed = first_blob.3 & constants::COMPRESSED_FLAG != 0;
+        let is_aligned = first_blob.0 == 0 && length % first_blob.2 == 0;
+
+        // Initialize the buffer: Blobs larger than it are read in multiple steps.
+        let mut buffer = vec![
+            0u8;
+            if is_compressed || is_aligned {
+                0
+            } else {
+                std::cmp::min(
+                    std::cmp::min(first_blob.2, length) as usize,
+                    self.read_buffer_limit,
+                )
+            }
+        ];
+
+        let mut bytes_read = 0i64;
+        let mut blob_cache: Option<rusqlite::blob::Blob> = None;
+        for (index, blob_index) in std::iter::once(Ok(first_blob)).chain(blob_iter).enumerate() {
+            // Fetching the next blob fails i.e. if the query is interrupted.
+            let (first_index, blob_id, _, _, chunk_num) = blob_index?;
+            let mut blob = match blob_cache {
+                None => self.database.borrow().blob_open(
+                    DatabaseName::Main,
+                    constants::DATA_TABLE,
+                    "data",
+                    blob_id,
+                    true,
+                ),
+                Some(mut blob) => blob.reopen(blob_id).map(|_| blob),
+            }?;
+
+            // Compressed chunks are decompressed as a whole, as offsets do not match the stored bytes.
+            let decompressed = match is_compressed {
+                true => Some(decompress(&FileSystem::<D>::read_blob(&blob)?)?),
+                false => None,
+            };
+            // Aligned reads copy the blob as a whole without tracking offsets within it.
+            if is_aligned {
+                let num_bytes = match &decompressed {
+                    Some(data) => {
+                        sink.write_all(data)?;
+                        data.len() as i64
+                    }
+                    None => std::io::copy(&mut blob, &mut sink)? as i64,
+                };
+                on_chunk(chunk_num as u32, num_bytes as usize);
+                bytes_read += num_bytes;
+                blob_cache = Some(blob);
+                continue;
+            }
+
+            let blob_size = match &decompressed {
+                Some(data) => data.len() as i64,
+                None => blob.size() as i64,
+            };
+            let mut num_bytes = std::cmp::min(blob_size, length - bytes_read);
+            if index == 0 {
+                num_bytes = std::cmp::min(blob_size - first_index as i64, num_bytes);
+                if num_bytes <= 0 {
+                    return Err(ReadError::OutOfBounds);
+                }
+            }
+
+            match &decompressed {
+                Some(data) => {
+                    sink.write_all(&data[first_index..first_index + num_bytes as usize])?;
+                }
+                None => {
+                    let mut blob_bytes_read = 0usize;
+                    while blob_bytes_read < num_bytes as usize {
+                        // Read data into the buffer
+                        let step =
+                            std::cmp::min(buffer.len(), num_bytes as usize - blob_bytes_read);
+                        blob.read_at_exact(&mut buffer[..step], first_index + blob_bytes_read)?;
+
+                        // Copy data to writer
+                        sink.write_all(&buffer[..step])?;
+                        blob_bytes_read += step;
+                    }
+                }
+            }
+
+            on_chunk(chunk_num as u32, num_bytes as usize);
+            bytes_read += num_bytes;
+            blob_cache = Some(blob);
+        }
+
+        // Raise an out-of-bound error if the length it too large.
+        match bytes_read == length {
+            true => Ok(bytes_read as usize),
+            false => Err(ReadError::OutOfBounds),
+        }
+    }
+
+    /// Enable or disable moving deleted files into the trash rather than deleting them permanently.
+    pub fn set_trash(&mut self, use_trash: bool) -> Result<(), DatabaseError> {
+        if use_trash {
+            self.database
+                .borrow()
+                .execute(constants::SQL_CREATE_TRASH, [])
+                .map_err(|error| error.try_into().expect(DatabaseError::LOGIC_ERROR_MESSAGE))?;
+        }
+        self.use_trash = use_trash;
+        Ok(())
+    }
+
+    /// Restore the file most recently moved into the trash from the given path.
+    pub fn restore<T: AsRef<str>>(&mut self, path: T) -> Result<Handle, MatryoshkaError> {
+        let path: VirtualPath = path.as_ref().into();
+        let transaction = self
+            .database
+            .borrow_mut()
+            .transaction()
+            .map_err(LoadingError::from)?;
+
+        let (handle, entry_type): (i64, u32) = transaction
+            .query_row(constants::SQL_GET_TRASHED, params![path.as_ref()], |row| {
+                Ok((row.get(0)?, row.get(1)?))
+            })
+            .optional()
+            .map_err(LoadingError::from)?
+            .ok_or(LoadingError::FileNotFound)?;
+
+        match transaction.execute(
+            constants::SQL_RESTORE_HANDLE,
+            params![path.as_ref(), entry_type, handle],
+        ) {
+            Ok(_) => {}
+            Err(RusqliteError::SqliteFailure(error, _))
+                if error.code == ErrorCode::ConstraintViolation =>
+            {
+                return Err(CreationError::FileExists.into());
+            }
+            Err(error) => return Err(CreationError::from(error).into()),
+        }
+        transaction
+            .execute(constants::SQL_RESTORE_ENTRY, params![handle])
+            .map_err(CreationError::from)?;
+
+        transaction.commit().map_err(CreationError::from)?;
+        Ok(Handle(handle))
+    }
+
+    /// Move an entry to a new path, which must not exist yet.
+    ///
+    /// Like [rename_replace](Self::rename_replace), symbolic links are moved themselves instead of their targets.
+    pub fn rename<T: AsRef<str>, U: AsRef<str>>(
+        &mut self,
+        from: T,
+        to: U,
+    ) -> Result<Handle, MatryoshkaError> {
+        let from: VirtualPath = from.as_ref().into();
+        let to = self.validate_path(to).map_err(CreationError::InvalidPath)?;
+        let transaction = self
+            .database
+            .borrow_mut()
+            .transaction()
+            .map_err(LoadingError::from)?;
+
+        let handle: i64 = transaction
+            .query_row(constants::SQL_GET_HANDLE, params![from.as_ref()], |row| {
+                row.get(0)
+            })
+            .optional()
+            .map_err(LoadingError::from)?
+            .ok_or(LoadingError::FileNotFound)?;
+        FileSystem::<D>::check_entry_unlocked(&transaction, handle)?;
+        match transaction.execute(constants::SQL_RENAME, params![to.as_ref(), handle]) {
+            Ok(_) => {}
+            Err(RusqliteError::SqliteFailure(error, _))
+                if error.code == ErrorCode::ConstraintViolation =>
+            {
+                return Err(CreationError::FileExists.into());
+            }
+            Err(error) => return Err(CreationError::from(error).into()),
+        }
+
+        transaction.commit().map_err(CreationError::from)?;
+        Ok(Handle(handle))
+    }
+
+    /// Move an entry to a new path, replacing any existing entry there within a single transaction.
+    ///
+    /// Like "mv -f", symbolic links are moved themselves instead of their targets. If the trash is enabled, a replaced
+    /// entry is moved into it.
+    pub fn rename_replace<T: AsRef<str>, U: AsRef<str>>(
+        &mut self,
+        from: T,
+        to: U,
+    ) -> Result<Handle, MatryoshkaError> {
+        let from: VirtualPath = from.as_ref().into();
+        let to = self.validate_path(to).map_err(CreationError::InvalidPath)?;
+        let use_trash = self.use_trash;
+        let transaction = self
+            .database
+            .borrow_mut()
+            .transaction()
+            .map_err(LoadingError::from)?;
+
+        let get_handle = |path: &VirtualPath| -> Result<Option<i64>, RusqliteError> {
+            transaction
+                .query_row(constants::SQL_GET_HANDLE, params![path.as_ref()], |row| {
+                    row.get(0)
+                })
+                .optional()
+        };
+        let handle = get_handle(&from)
+            .map_err(LoadingError::from)?
+            .ok_or(LoadingError::FileNotFound)?;
+        FileSystem::<D>::check_entry_unlocked(&transaction, handle)?;
+        let existing = get_handle(&to).map_err(LoadingError::from)?;
+        if let Some(existing) = existing {
+            FileSystem::<D>::check_entry_unlocked(&transaction, existing)?;
+        }
+        let replace = || -> Result<(), RusqliteError> {
+            match existing {
+                // Paths comparing equal ignoring their case still need to be renamed to change it.
+                Some(existing) if existing == handle => {
+                    let path: String =
+                        transaction.query_row(constants::SQL_GET_PATH, params![handle], |row| {
+                            row.get(0)
+                        })?;
+                    if path == to.as_ref() {
+                        return Ok(());
+                    }
+                }
+                Some(existing) => {
+                    FileSystem::<D>::remove_entry(&transaction, existing, use_trash)?;
+                }
+                None => {}
+            }
+            transaction.execute(constants::SQL_RENAME, params![to.as_ref(), handle])?;
+            Ok(())
+        };
+        replace().map_err(CreationError::from)?;
+
+        transaction.commit().map_err(CreationError::from)?;
+        Ok(Handle(handle))
+    }
+
+    /// Delete all files matching the GLOB pattern of [find](Self::find) at once, returning their number.
+    ///
+    /// If the trash is enabled, the files are moved into it instead.
+    pub fn delete_matching<T: AsRef<str>>(&mut self, pattern: T) -> Result<usize, DatabaseError> {
+        let pattern: VirtualPath = pattern.as_ref().into();
+        let use_trash = self.use_trash;
+        let delete = |database: &mut Database| -> Result<usize, RusqliteError> {
+            let transaction = database.transaction()?;
+            let num_deleted = if use_trash {
+                let handles: Vec<i64> = transaction
+                    .prepare(constants::SQL_GET_MATCHING)?
+                    .query_map(params![pattern.as_ref(), constants::FILE_ID], |row| {
+                        row.get(0)
+                    })?
+                    .collect::<Result<_, _>>()?;
+                for handle in &handles {
+                    transaction.execute(
+                        constants::SQL_TRASH_ENTRY,
+                        params![handle, constants::TRASH_ID],
+                    )?;
+                    transaction.execute(
+                        constants::SQL_TRASH_HANDLE,
+                        params![constants::TRASH_ID, handle],
+                    )?;
+                }
+                handles.len()
+            } else {
+                transaction.execute(
+                    constants::SQL_DELETE_MATCHING,
+                    params![pattern.as_ref(), constants::FILE_ID],
+                )?
+            };
+            transaction.commit()?;
+            Ok(num_deleted)
+        };
+        delete(self.database.borrow_mut())
+            .map_err(|error| error.try_into().expect(DatabaseError::LOGIC_ERROR_MESSAGE))
+    }
+
+    /// Permanently delete all the files in the trash, returning their number.
+    pub fn empty_trash(&mut self) -> Result<usize, DatabaseError> {
+        self.database
+            .borrow()
+            .execute(constants::SQL_EMPTY_TRASH, params![constants::TRASH_ID])
+            .map_err(|error| error.try_into().expect(DatabaseError::LOGIC_ERROR_MESSAGE))
+    }
+
+    /// Overwrite existing data of a file, starting at the given index.
+    fn write(&self, handle: Handle, data: &[u8], index: usize) -> Result<(), RusqliteError> {
+        if data.is_empty() {
+            return Ok(());
+        }
+
+        let transaction = self.database.borrow().unchecked_transaction()?;
+        {
+            let mut blobs_statement = transaction.prepare_cached(constants::SQL_GET_BLOBS)?;
+            let blobs: Vec<(i64, i64, i64, i64)> = blobs_statement
+                .query_map(
+                    &[
+                        (":handle", &handle.0),
+                        (":index", &(index as i64)),
+                        (":size", &(data.len() as i64)),
+                    ],
+                    |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?)),
+                )?
+                .collect::<Result<_, _>>()?;
+
+            let mut bytes_written = 0usize;
+            for (blob_id, chunk_num, chunk_size, flags) in blobs {
+                let offset = index + bytes_written - (chunk_num * chunk_size) as usize;
+                let mut blob = transaction.blob_open(
+                    DatabaseName::Main,
+                    constants::DATA_TABLE,
+                    "data",
+                    blob_id,
+                    false,
+                )?;
+
+                // The size of compressed chunks changes, requiring them to be replaced completely.
+                if flags & constants::COMPRESSED_FLAG != 0 {
+                    let mut chunk = decompress(&FileSystem::<D>::read_blob(&blob)?)?;
+                    drop(blob);
+                    let num_bytes = std::cmp::min(chunk.len() - offset, data.len() - bytes_written);
+                    chunk[offset..offset + num_bytes]
+                        .copy_from_slice(&data[bytes_written..bytes_written + num_bytes]);
+                    transaction.execute(
+                        constants::SQL_UPDATE_CHUNK,
+                        params![compress(&chunk), blob_id],
+                    )?;
+                    bytes_written += num_bytes;
+                    continue;
+                }
+
+                let num_bytes =
+                    std::cmp::min(blob.size() as usize - offset, data.len() - bytes_written);
+                blob.write_at(&data[bytes_written..bytes_written + num_bytes], offset)?;
+                bytes_written += num_bytes;
+            }
+        }
+        transaction.execute(constants::SQL_TOUCH, params![handle.0])?;
+        transaction.commit()
+    }
+
+    /// Append data to a file by rewriting its last chunk and adding new ones of its stored chunk size.
+    fn append(&self, handle: Handle, data: &[u8]) -> Result<(), LoadingError> {
+        if data.is_empty() {
+            return Ok(());
+        }
+
+        let transaction = self.database.borrow().unchecked_transaction()?;
+        let (chunk_size, flags): (i64, i64) = transaction
+            .query_row(
+                constants::SQL_GET_LAYOUT,
+                params![handle.0, constants::FILE_ID],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .optional()?
+            .ok_or(LoadingError::FileNotFound)?;
+        let is_compressed = flags & constants::COMPRESSED_FLAG != 0;
+
+        let last_chunk: Option<(i64, i64, Vec<u8>)> = transaction
+            .query_row(constants::SQL_GET_LAST_CHUNK, params![handle.0], |row| {
+                Ok((row.get(0)?, row.get(1)?, row.get(2)?))
+            })
+            .optional()?;
+        let (mut chunk_id, mut chunk_num, mut pending) = match last_chunk {
+            Some((chunk_id, chunk_num, chunk)) if is_compressed => {
+                (Some(chunk_id), chunk_num, decompress(&chunk)?)
+            }
+            Some((chunk_id, chunk_num, chunk)) => (Some(chunk_id), chunk_num, chunk),
+            None => (None, 0, Vec::new()),
+        };
+        pending.extend_from_slice(data);
+
+        {
+            // Like for newly created files, a full last chunk is followed by an empty one.
+            let mut chunks: Vec<&[u8]> = pending.chunks(chunk_size as usize).collect();
+            if pending.len() % chunk_size as usize == 0 {
+                chunks.push(&[]);
+            }
+
+            let mut create_blob_statement =
+                transaction.prepare_cached(constants::SQL_CREATE_BLOB)?;
+            for chunk in chunks {
+                let chunk = match is_compressed {
+                    true => compress(chunk),
+                    false => chunk.to_vec(),
+                };
+                match chunk_id.take() {
+                    Some(chunk_id) => {
+                        transaction
+                            .execute(constants::SQL_UPDATE_CHUNK, params![chunk, chunk_id])?;
+                    }
+                    None => {
+                        create_blob_statement.execute(params![handle.0, chunk_num, chunk])?;
+                    }
+                }
+                chunk_num += 1;
+            }
+        }
+        transaction.execute(constants::SQL_TOUCH, params![handle.0])?;
+        transaction.commit()?;
+        Ok(())
+    }
+
+    /// Split the content of a file anew into chunks of its chunk size, where only the last one may be shorter.
+    fn compact(&self, handle: Handle) -> Result<(), RusqliteError> {
+        let transaction = self.database.borrow().unchecked_transaction()?;
+        let (chunk_size, flags): (i64, i64) = transaction.query_row(
+            constants::SQL_GET_LAYOUT,
+            params![handle.0, constants::FILE_ID],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )?;
+        let chunk_size = chunk_size as usize;
+        let is_compressed = flags & constants::COMPRESSED_FLAG != 0;
+
+        {
+            let chunk_ids: Vec<i64> = transaction
+                .prepare(constants::SQL_GET_CHUNK_IDS)?
+                .query_map(params![handle.0], |row| row.get(0))?
+                .collect::<Result<_, _>>()?;
+            let mut get_chunk_statement = transaction.prepare(constants::SQL_GET_CHUNK_DATA)?;
+            let mut delete_chunk_statement = transaction.prepare(constants::SQL_DELETE_CHUNK)?;
+            let mut create_blob_statement =
+                transaction.prepare_cached(constants::SQL_CREATE_BLOB)?;
+
+            // Only the pending bytes not forming a whole chunk yet are kept in memory.
+            let mut buffer: Vec<u8> = Vec::with_capacity(chunk_size);
+            let mut chunk_index = 0i64;
+            let mut insert_chunk = |data: &[u8]| -> Result<(), RusqliteError> {
+                let chunk = match is_compressed {
+                    true => compress(data),
+                    false => data.to_vec(),
+                };
+                create_blob_statement.execute(params![handle.0, -1 - chunk_index, &chunk])?;
+                chunk_index += 1;
+                Ok(())
+            };
+            for chunk_id in chunk_ids {
+                let data: Vec<u8> =
+                    get_chunk_statement.query_row(params![chunk_id], |row| row.get(0))?;
+                delete_chunk_statement.execute(params![chunk_id])?;
+                match is_compressed {
+                    true => buffer.extend_from_slice(&decompress(&data)?),
+                    false => buffer.extend_from_slice(&data),
+                }
+
+                let num_chunks = buffer.len() / chunk_size;
+                for chunk in buffer.chunks_exact(chunk_size) {
+                    insert_chunk(chunk)?;
+                }
+                buffer.drain(..num_chunks * chunk_size);
+            }
+
+            // Like on creation, the last chunk is always stored, even if it is empty.
+            insert_chunk(&buffer)?;
+        }
+
+        transaction.execute(constants::SQL_RENUMBER_CHUNKS, params![handle.0])?;
+        transaction.commit()
+    }
+
+    /// Delete the entry with the given handle, returning whether it existed. The trash is used if enabled.
+    ///
+    /// Files opened exclusively are not deleted.
+    pub fn delete_handle(&self, handle: Handle) -> Result<bool, LoadingError> {
+        Ok(self.delete(handle)? > 0)
+    }
+
+    fn delete(&self, handle: Handle) -> Result<usize, LoadingError> {
+        let transaction = self.database.borrow().unchecked_transaction()?;
+        FileSystem::<D>::check_entry_unlocked(&transaction, handle.0)?;
+        let num_deleted = FileSystem::<D>::remove_entry(&transaction, handle.0, self.use_trash)?;
+        transaction.commit()?;
+        Ok(num_deleted)
+    }
+
+    /// Release the lock of a file opened exclusively, returning whether it was locked.
+    ///
+    /// This recovers files whose lock outlived its guard, i.e. as the process holding it terminated. Releasing the
+    /// lock of an [ExclusiveFile] still in use allows others to modify the file concurrently.
+    pub fn force_unlock<T: AsRef<str>>(&self, path: T) -> Result<bool, LoadingError> {
+        let handle = self.open(path.as_ref())?;
+        let num_unlocked = self
+            .database
+            .borrow()
+            .execute(constants::SQL_UNLOCK, params![handle.0])?;
+        Ok(num_unlocked > 0)
+    }
+
+    /// Ensure that the file still exists and is not opened exclusively.
+    fn check_unlocked(&self, handle: Handle) -> Result<(), LoadingError> {
+        let flags: Option<i64> = self
+            .database
+            .borrow()
+            .query_row(
+                constants::SQL_GET_LAYOUT,
+                params![handle.0, constants::FILE_ID],
+                |row| row.get(1),
+            )
+            .optional()?;
+        match flags {
+            None => Err(LoadingError::FileNotFound),
+            Some(flags) if flags & constants::LOCKED_FLAG != 0 => Err(LoadingError::FileLocked),
+            Some(_) => Ok(()),
+        }
+    }
+
+    /// Ensure within an existing transaction that the entry, if it exists, is not opened exclusively.
+    fn check_entry_unlocked(transaction: &Database, handle: i64) -> Result<(), LoadingError> {
+        let flags: Option<i64> = transaction
+            .query_row(constants::SQL_GET_FLAGS, params![handle], |row| row.get(0))
+            .optional()?;
+        match flags {
+            Some(flags) if flags & constants::LOCKED_FLAG != 0 => Err(LoadingError::FileLocked),
+            _ => Ok(()),
+        }
+    }
+
+    /// Remove an entry within an existing transaction, moving it into the trash if requested.
+    fn remove_entry(
+        transaction: &Database,
+        handle: i64,
+        use_trash: bool,
+    ) -> Result<usize, RusqliteError> {
+        if !use_trash {
+            return transaction.execute(constants::SQL_DELETE, params![handle]);
+        }
+
+        // The entry is kept to avoid deleting its data.
+        let num_trashed = transaction.execute(
+            constants::SQL_TRASH_ENTRY,
+            params![handle, constants::TRASH_ID],
+        )?;
+        if num_trashed > 0 {
+            transaction.execute(
+                constants::SQL_TRASH_HANDLE,
+                params![constants::TRASH_ID, handle],
+            )?;
+        }
+        Ok(num_trashed)
+    }
+
+    /// Check whether the handle refers to an existing file, without determining its size like opening it does.
+    ///
+    /// Symbolic links and files in the trash do not count as existing files.
+    pub fn handle_exists(&self, handle: Handle) -> Result<bool, DatabaseError> {
+        self.database
+            .borrow()
+            .query_row(
+                constants::SQL_EXISTS,
+                params![handle.0, constants::FILE_ID],
+                |row| row.get(0),
+            )
+            .map_err(|error| error.try_into().expect(DatabaseError::LOGIC_ERROR_MESSAGE))
+    }
+
+    fn size(&self, handle: Handle) -> Result<Option<usize>, DatabaseError> {
+        Ok(self
+            .sizes(&[handle])?
+            .first()
+            .map(|(_, size)| *size as usize))
+    }
+}
+
+/// A lazy iterator over the paths matching a GLOB pattern, created by [find_iter](FileSystem::find_iter).
+#[derive(Debug)]
+pub struct FindIter<'a> {
+    statement: Statement<'a>,
+    pattern: VirtualPath,
+    // Instead of keeping a cursor open, the iteration continues after the path returned last.
+    last_path: Option<String>,
+}
+
+impl<'a> Iterator for FindIter<'a> {
+    type Item = Result<String, DatabaseError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let last_path = self.last_path.take()?;
+        match self
+            .statement
+            .query_row(
+                params![self.pattern.as_ref(), constants::FILE_ID, last_path],
+                |row| row.get::<_, String>(0),
+            )
+            .optional()
+        {
+            Ok(Some(path)) => {
+                self.last_path = Some(path.clone());
+                Some(Ok(path))
+            }
+            Ok(None) => None,
+            Err(error) => Some(Err(error
+                .try_into()
+                .expect(DatabaseError::LOGIC_ERROR_MESSAGE))),
+        }
+    }
+}
+
+/// A batch of changes to the virtual file system, which are committed atomically.
+pub struct BatchWriter<'a> {
+    transaction: Transaction<'a>,
+    use_trash: bool,
+}
+
+impl<'a> BatchWriter<'a> {
+    /// Create a file as part of the batch. If it fails, the batch remains usable.
+    pub fn create<T: AsRef<str>, R: Read>(
+        &mut self,
+        path: T,
+        data: R,
+        chunk_size: usize,
+    ) -> Result<Handle, CreationError> {
+        // A savepoint allows reverting a failed file without affecting the remaining batch.
+        let savepoint = self.transaction.savepoint()?;
+        let (handle, _) = FileSystem::<Database>::write_entry(
+            &savepoint,
+            path.as_ref().into(),
+            constants::FILE_ID,
+            0,
+            data,
+            chunk_size,
+        )?;
+        savepoint.commit()?;
+        Ok(handle)
+    }
+
+    /// Delete the entry at the path as part of the batch, returning whether it existed. Symbolic links are not followed.
+    ///
+    /// Files opened exclusively are not deleted.
+    pub fn delete<T: AsRef<str>>(&mut self, path: T) -> Result<bool, LoadingError> {
+        let path: VirtualPath = path.as_ref().into();
+        let transaction = &self.transaction;
+        let handle: Option<i64> = transaction
+            .query_row(constants::SQL_GET_HANDLE, params![path.as_ref()], |row| {
+                row.get(0)
+            })
+            .optional()?;
+        match handle {
+            Some(handle) => {
+                FileSystem::<Database>::check_entry_unlocked(transaction, handle)?;
+                Ok(FileSystem::<Database>::remove_entry(transaction, handle, self.use_trash)? > 0)
+            }
+            None => Ok(false),
+        }
+    }
+
+    /// Commit all the changes of the batch at once.
+    pub fn commit(self) -> Result<(), DatabaseError> {
+        self.transaction
+            .commit()
+            .map_err(|error| error.try_into().expect(DatabaseError::LOGIC_ERROR_MESSAGE))
+    }
+
+    /// Discard all the changes of the batch.
+    pub fn rollback(self) -> Result<(), DatabaseError> {
+        self.transaction
+            .rollback()
+            .map_err(|error| error.try_into().expect(DatabaseError::LOGIC_ERROR_MESSAGE))
+    }
+}
+
+impl<'a> Debug for BatchWriter<'a> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        f.debug_struct("BatchWriter")
+            .field("use_trash", &self.use_trash)
+            .finish()
+    }
+}
+
+/// A sink creating a file in the virtual file system chunk by chunk.
+pub struct FileWriter<'a> {
+    transaction: Transaction<'a>,
+    handle: Handle,
+    buffer: Vec<u8>,
+    chunk_size: usize,
+    chunk_index: u32,
+}
+
+impl<'a> FileWriter<'a> {
+    /// Store the remaining data and commit the file to the virtual file system.
+    pub fn finish(mut self) -> Result<Handle, CreationError> {
+        // The last chunk is always stored, even if it is empty.
+        self.write_chunk()?;
+        self.transaction.commit()?;
+        Ok(self.handle)
+    }
+
+    fn write_chunk(&mut self) -> Result<(), CreationError> {
+        self.transaction
+            .prepare_cached(constants::SQL_CREATE_BLOB)?
+            .execute(params![self.handle.0, self.chunk_index, &self.buffer[..]])
+            .map_err(|error| CreationError::from_chunk_error(error, self.buffer.len()))?;
+        self.buffer.clear();
+        self.chunk_index += 1;
+        Ok(())
+    }
+}
+
+impl<'a> Write for FileWriter<'a> {
+    fn write(&mut self, buf: &[u8]) -> IoResult<usize> {
+        let num_bytes = std::cmp::min(buf.len(), self.chunk_size - self.buffer.len());
+        self.buffer.extend_from_slice(&buf[..num_bytes]);
+        if self.buffer.len() == self.chunk_size {
+            self.write_chunk()
+                .map_err(|error| IoError::new(ErrorKind::Other, error.error_message()))?;
+        }
+        Ok(num_bytes)
+    }
+
+    fn flush(&mut self) -> IoResult<()> {
+        // Data is only persisted when the writer is finished.
+        Ok(())
+    }
+}
+
+// The buffer might be huge, so it is not part of the debug output.
+impl<'a> Debug for FileWriter<'a> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        f.debug_struct("FileWriter")
+            .field("handle", &self.handle)
+            .field("chunk_size", &self.chunk_size)
+            .field("chunk_index", &self.chunk_index)
+            .finish()
+    }
+}
+
+/// A source reporting the number of bytes read so far compared to the expected total.
+struct ProgressReader<R, F> {
+    data: R,
+    bytes_read: u64,
+    total_len: u64,
+    on_progress: F,
+}
+
+impl<R: Read, F: FnMut(u64, u64)> Read for ProgressReader<R, F> {
+    fn read(&mut self, buf: &mut [u8]) -> IoResult<usize> {
+        let num_bytes = self.data.read(buf)?;
+        if num_bytes > 0 {
+            self.bytes_read += num_bytes as u64;
+            (self.on_progress)(
+                self.bytes_read,
+                std::cmp::max(self.total_len, self.bytes_read),
+            );
+        }
+        Ok(num_bytes)
+    }
+}
+
+/// A sink decoding UTF-8 text, even if characters are split between writes.
+struct Utf8Sink {
+    text: String,
+    pending: Vec<u8>,
+    is_invalid: bool,
+}
+
+impl Write for Utf8Sink {
+    fn write(&mut self, buf: &[u8]) -> IoResult<usize> {
+        self.pending.extend_from_slice(buf);
+        let valid_bytes = match std::str::from_utf8(&self.pending) {
+            Ok(text) => text.len(),
+            // The last character is incomplete and its remaining bytes will follow.
+            Err(error) if error.error_len().is_none() => error.valid_up_to(),
+            Err(_) => {
+                self.is_invalid = true;
+                return Err(IoError::new(ErrorKind::InvalidData, "Invalid UTF-8"));
+            }
+        };
+
+        self.text.push_str(
+            std::str::from_utf8(&self.pending[..valid_bytes]).expect("Validated UTF-8 is invalid"),
+        );
+        self.pending.drain(..valid_bytes);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> IoResult<()> {
+        Ok(())
+    }
+}
+
+/// A file stored in the virtual file system.
+#[derive(Debug)]
+pub struct File<'a, D> {
+    file_system: &'a FileSystem<D>,
+    handle: Handle,
+    size: usize,
+    current_index: usize,
+}
+
+impl<'a, D> File<'a, D>
+where
+    D: BorrowMut<Database>,
+{
+    /// Create a file in the virtual file system.
+    pub fn create<T: AsRef<str>, R: Read>(
+        file_system: &'a mut FileSystem<D>,
+        path: T,
+        data: R,
+        chunk_size: usize,
+    ) -> Result<File<'a, D>, CreationError> {
+        let (handle, size) = file_system.create(path.as_ref(), data, chunk_size)?;
+        Ok(File {
+            file_system,
+            handle,
+            size,
+            current_index: 0,
+        })
+    }
+
+    /// Create a file from data of the expected total length, reporting the bytes stored so far and the total.
+    ///
+    /// The data is stored as it is, even if its length differs from the expected one. If it is longer, the reported
+    /// total grows with it. Once the data is exhausted, the actual length is reported as both values, so the final
+    /// report always indicates completion.
+    pub fn create_sized<T: AsRef<str>, R: Read, F: FnMut(u64, u64)>(
+        file_system: &'a mut FileSystem<D>,
+        path: T,
+        data: R,
+        total_len: u64,
+        chunk_size: usize,
+        mut on_progress: F,
+    ) -> Result<File<'a, D>, CreationError> {
+        let mut reader = ProgressReader {
+            data,
+            bytes_read: 0,
+            total_len,
+            on_progress: &mut on_progress,
+        };
+        let (handle, size) = file_system.create(path.as_ref(), &mut reader, chunk_size)?;
+        on_progress(size as u64, size as u64);
+        Ok(File {
+            file_system,
+            handle,
+            size,
+            current_index: 0,
+        })
+    }
+
+    /// Create a file of the given size filled with zeros, like [create_empty](FileSystem::create_empty).
+    pub fn create_empty<T: AsRef<str>>(
+        file_system: &'a mut FileSystem<D>,
+        path: T,
+        size: usize,
+        chunk_size: usize,
+    ) -> Result<File<'a, D>, CreationError> {
+        file_system.create_empty(path, size, chunk_size)
+    }
+
+    /// Create a file in the virtual file system whose chunks are compressed individually.
+    ///
+    /// Reading the file decompresses it transparently. As whole chunks are decompressed, even for small reads, smaller
+    /// chunks favour random access.
+    pub fn create_compressed<T: AsRef<str>, R: Read>(
+        file_system: &'a mut FileSystem<D>,
+        path: T,
+        data: R,
+        chunk_size: usize,
+    ) -> Result<File<'a, D>, CreationError> {
+        let (handle, size) = file_system.create_compressed(path.as_ref(), data, chunk_size)?;
+        Ok(File {
+            file_system,
+            handle,
+            size,
+            current_index: 0,
+        })
+    }
+
+    /// Load a file from the virtual file system. Symbolic links are followed.
+    pub fn load<T: AsRef<str>>(
+        file_system: &'a FileSystem<D>,
+        path: T,
+    ) -> Result<File<'a, D>, LoadingError> {
+        let handle = file_system.open(path.as_ref())?;
+        Ok(File {
+            file_system,
+            handle,
+            size: file_system
+                .size(handle)
+                .map_err(LoadingError::DatabaseError)?
+                .expect("Missing file size for existing file"),
+            current_index: 0,
+        })
+    }
+
+    /// Load a file from the virtual file system, ignoring the case of ASCII characters in its path.
+    ///
+    /// A file matching the path exactly is preferred. Otherwise, exactly one file must match ignoring the case.
+    pub fn load_ci<T: AsRef<str>>(
+        file_system: &'a FileSystem<D>,
+        path: T,
+    ) -> Result<File<'a, D>, LoadingError> {
+        let handle = file_system.open_ci(path.as_ref())?;
+        Ok(File {
+            file_system,
+            handle,
+            size: file_system
+                .size(handle)
+                .map_err(LoadingError::DatabaseError)?
+                .expect("Missing file size for existing file"),
+            current_index: 0,
+        })
+    }
+
+    /// Load a file for exclusive in-place modification until the returned guard is dropped.
+    ///
+    /// The lock is advisory: It is stored in the database and respected by the modifying operations of this library,
+    /// i.e. [FileSystem::copy_range] or [FileSystem::rename], but not enforced by SQLite. If the process terminates
+    /// without dropping the guard, the file stays locked until [FileSystem::force_unlock] is called.
+    pub fn open_exclusive<T: AsRef<str>>(
+        file_system: &'a FileSystem<D>,
+        path: T,
+    ) -> Result<ExclusiveFile<'a, D>, LoadingError> {
+        let file = File::load(file_system, path)?;
+        let num_locked = file_system
+            .database
+            .borrow()
+            .execute(constants::SQL_LOCK, params![file.handle.0])?;
+        match num_locked {
+            0 => Err(LoadingError::FileLocked),
+            _ => Ok(ExclusiveFile { file }),
+        }
+    }
+
+    /// Open a file whose size is already known, i.e. as it was just created.
+    ///
+    /// The existence of the file is checked, while its size is trusted.
+    pub fn from_parts(
+        file_system: &'a FileSystem<D>,
+        handle: Handle,
+        size: usize,
+    ) -> Result<File<'a, D>, LoadingError> {
+        match file_system.handle_exists(handle) {
+            Ok(true) => Ok(File::from_parts_unchecked(file_system, handle, size)),
+            Ok(false) => Err(LoadingError::FileNotFound),
+            Err(error) => Err(LoadingError::DatabaseError(error)),
+        }
+    }
+
+    /// Open a file whose handle and size are already known without querying the database.
+    ///
+    /// Both the handle and the size are trusted. If they are wrong, reading the file fails or returns partial content.
+    pub fn from_parts_unchecked(
+        file_system: &'a FileSystem<D>,
+        handle: Handle,
+        size: usize,
+    ) -> File<'a, D> {
+        File {
+            file_system,
+            handle,
+            size,
+            current_index: 0,
+        }
+    }
+
+    /// Read the content of a file from the virtual file system.
+    ///
+    /// This function does not(!) modify the internal position. In practise, using the Read trait might be more advantageous.
+    pub fn random_read<W: Write>(
+        &self,
+        sink: W,
+        index: usize,
+        length: usize,
+    ) -> Result<usize, ReadError> {
+        self.file_system
+            .read(self.handle, sink, index, length, |_, _| {})
+    }
+
+    /// Read like [random_read](Self::random_read), but give up once the timeout elapsed, i.e. on a busy database.
+    ///
+    /// The running query is interrupted from a separate thread, so reads spending their time in the sink are only
+    /// stopped once they query the next chunk. While reading, a longer busy timeout of the connection is reduced to the
+    /// timeout.
+    pub fn random_read_timeout<W: Write>(
+        &self,
+        sink: W,
+        index: usize,
+        length: usize,
+        timeout: Duration,
+    ) -> Result<usize, ReadError> {
+        // Waiting for a busy database does not check for interrupts. Therefore, it must not exceed the timeout.
+        let database = self.file_system.database.borrow();
+        let busy_timeout: u32 = database
+            .pragma_query_value(None, "busy_timeout", |row| row.get(0))
+            .map_err(ReadError::from)?;
+        let timeout_millis = u32::try_from(timeout.as_millis()).unwrap_or(u32::MAX);
+        let is_busy_timeout_reduced = busy_timeout > timeout_millis;
+        if is_busy_timeout_reduced {
+            database.busy_timeout(timeout).map_err(ReadError::from)?;
+        }
+
+        let (finished_sender, finished_receiver) = mpsc::channel::<()>();
+        let interrupt_handle = self.file_system.interrupt_handle();
+        let timer = std::thread::spawn(move || {
+            let is_timed_out = matches!(
+                finished_receiver.recv_timeout(timeout),
+                Err(RecvTimeoutError::Timeout)
+            );
+            if is_timed_out {
+                interrupt_handle.interrupt();
+            }
+            is_timed_out
+        });
+
+        let start = Instant::now();
+        let result = self.random_read(sink, index, length);
+        // The timer stops as soon as the sender is dropped.
+        drop(finished_sender);
+        let is_timed_out =
+            timer.join().expect("Timer of read panicked") || start.elapsed() >= timeout;
+        if is_busy_timeout_reduced {
+            database
+                .busy_timeout(Duration::from_millis(u64::from(busy_timeout)))
+                .map_err(ReadError::from)?;
+        }
+
+        match result {
+            Err(ReadError::Interrupted) if is_timed_out => Err(ReadError::TimedOut),
+            Err(ReadError::DatabaseError(error)) if is_timed_out && error.is_transient() => {
+                Err(ReadError::TimedOut)
+            }
+            result => result,
+        }
+    }
+
+    /// Read like [random_read](Self::random_read), while reporting the number of bytes taken from each chunk.
+    ///
+    /// The callback is invoked with the number of the chunk and the bytes read from it in the order of reading.
+    pub fn read_with_chunk_info<W: Write, F: FnMut(u32, usize)>(
+        &self,
+        sink: W,
+        index: usize,
+        length: usize,
+        on_chunk: F,
+    ) -> Result<usize, ReadError> {
+        self.file_system
+            .read(self.handle, sink, index, length, on_chunk)
+    }
+
+    /// Read the last bytes of the file. If the file is shorter than the requested length, it is read completely.
+    ///
+    /// This function does not(!) modify the internal position.
+    pub fn read_tail<W: Write>(&self, sink: W, length: usize) -> Result<usize, ReadError> {
+        let length = std::cmp::min(length, self.size);
+        self.random_read(sink, self.size - length, length)
+    }
+
+    /// Read a single chunk of the file as it is stored.
+    pub fn read_chunk(&self, chunk_num: u32) -> Result<Vec<u8>, ReadError> {
+        self.file_system
+            .chunk(self.handle, chunk_num)?
+            .ok_or(ReadError::OutOfBounds)
+    }
+
+    /// Iterate over the content of the file as it is stored, one chunk at a time.
+    ///
+    /// Empty chunks, i.e. after a file filling its last chunk completely, are skipped.
+    pub fn chunks(&self) -> impl Iterator<Item = Result<Vec<u8>, ReadError>> + '_ {
+        let mut chunk_num = 0u32;
+        let mut is_finished = false;
+        std::iter::from_fn(move || {
+            if is_finished {
+                return None;
+            }
+            let chunk = self.file_system.chunk(self.handle, chunk_num);
+            chunk_num += 1;
+            match chunk {
+                Ok(Some(data)) if !data.is_empty() => Some(Ok(data)),
+                Ok(_) => {
+                    is_finished = true;
+                    None
+                }
+                Err(error) => {
+                    is_finished = true;
+                    Some(Err(error.into()))
+                }
+            }
+        })
+    }
+
+    /// Process the content of the file chunk by chunk, stopping at the first error.
+    ///
+    /// Unlike [chunks](Self::chunks), a single buffer is reused for all chunks of an uncompressed file. Therefore, the
+    /// slice passed to the function is only valid during the call.
+    pub fn for_each_chunk<E, F: FnMut(&[u8]) -> Result<(), E>>(
+        &self,
+        f: F,
+    ) -> Result<(), ProcessError<E>> {
+        self.file_system.for_each_chunk(self.handle, f)
+    }
+
+    /// Read the whole content of the file into a shared buffer, whose clones are cheap and use the same memory.
+    ///
+    /// This function does not(!) modify the internal position.
+    #[cfg(feature = "bytes")]
+    pub fn read_bytes(&self) -> Result<bytes::Bytes, ReadError> {
+        let mut buffer = Vec::with_capacity(self.size);
+        self.random_read(&mut buffer, 0, self.size)?;
+        Ok(buffer.into())
+    }
+
+    /// Feed the whole content of the file into a hasher, i.e. for computing a checksum.
+    ///
+    /// The file is streamed chunk by chunk instead of being loaded into memory at once.
+    pub fn hash_with<H: Hasher>(&self, hasher: &mut H) -> Result<(), ReadError> {
+        for chunk in self.chunks() {
+            hasher.write(&chunk?);
+        }
+        Ok(())
+    }
+
+    /// Read the whole content of the file as UTF-8 text.
+    ///
+    /// This function does not(!) modify the internal position.
+    pub fn read_to_string(&self) -> Result<String, ReadError> {
+        let mut sink = Utf8Sink {
+            text: String::with_capacity(self.size),
+            pending: Vec::new(),
+            is_invalid: false,
+        };
+
+        match self.random_read(&mut sink, 0, self.size) {
+            Ok(_) if sink.pending.is_empty() => Ok(sink.text),
+            Ok(_) => Err(ReadError::InvalidUtf8),
+            Err(_) if sink.is_invalid => Err(ReadError::InvalidUtf8),
+            Err(error) => Err(error),
+        }
+    }
+
+    /// Read from the current position into the buffer without advancing it.
+    ///
+    /// This allows looking ahead before consuming the data using the Read trait.
+    pub fn peek(&self, mut buf: &mut [u8]) -> Result<usize, ReadError> {
+        let length = std::cmp::min(buf.len(), self.size - self.current_index);
+        self.file_system
+            .read(self.handle, &mut buf, self.current_index, length, |_, _| {})
+    }
+
+    /// Query the position the Read trait continues at.
+    pub fn position(&self) -> usize {
+        self.current_index
+    }
+
+    /// Set the position the Read trait continues at, which may be at most the length of the file.
+    pub fn set_position(&mut self, position: usize) -> Result<(), ReadError> {
+        if position > self.size {
+            return Err(ReadError::OutOfBounds);
+        }
+        self.current_index = position;
+        Ok(())
+    }
+
+    /// Move the position the Read trait continues at back to the start of the file.
+    pub fn rewind(&mut self) {
+        self.current_index = 0;
+    }
+
+    /// Query the length of the file.
+    pub fn len(&self) -> usize {
+        self.size
+    }
+
+    /// Checks whether the file is empty.
+    pub fn is_empty(&self) -> bool {
+        self.size == 0
+    }
+
+    /// Query the number of bytes stored in the database for the file.
+    ///
+    /// Unlike [len](Self::len), this is the size after compression. For uncompressed files, both are equal.
+    pub fn stored_size(&self) -> Result<u64, DatabaseError> {
+        self.file_system
+            .database
+            .borrow()
+            .query_row(constants::SQL_STORED_SIZE, params![self.handle.0], |row| {
+                row.get::<_, i64>(0)
+            })
+            .map(|size| size as u64)
+            .map_err(|error| error.try_into().expect(DatabaseError::LOGIC_ERROR_MESSAGE))
+    }
+
+    /// Query the chunks storing the file in the database, ordered by their position.
+    pub fn layout(&self) -> Result<Vec<ChunkLayout>, DatabaseError> {
+        let layout = || -> Result<Vec<ChunkLayout>, RusqliteError> {
+            self.file_system
+                .database
+                .borrow()
+                .prepare(constants::SQL_LAYOUT)?
+                .query_map(params![self.handle.0], |row| {
+                    Ok(ChunkLayout::new(
+                        row.get(0)?,
+                        row.get(1)?,
+                        row.get::<_, i64>(2)? as u64,
+                    ))
+                })?
+                .collect()
+        };
+        layout().map_err(|error| error.try_into().expect(DatabaseError::LOGIC_ERROR_MESSAGE))
+    }
+
+    /// Query the raw underlying handle.
+    pub fn handle(&self) -> Handle {
+        self.handle
+    }
+
+    /// Get a reference to the file which does not borrow the file system.
+    pub fn file_ref(&self) -> FileRef {
+        FileRef {
+            handle: self.handle,
+            size: self.size,
+        }
+    }
+
+    /// Store the file anew in chunks of its chunk size, where only the last one may be shorter.
+    ///
+    /// This removes undersized chunks in the middle of the file which slow down reading. The chunk size itself is kept.
+    /// Files opened exclusively elsewhere are not modified. Use [ExclusiveFile::compact] for compacting them.
+    pub fn compact(&mut self) -> Result<(), MatryoshkaError> {
+        self.file_system.check_unlocked(self.handle)?;
+        self.compact_unchecked()
+    }
+
+    fn compact_unchecked(&mut self) -> Result<(), MatryoshkaError> {
+        self.file_system
+            .compact(self.handle)
+            .map_err(|error| CreationError::from(error).into())
+    }
+
+    /// Append data to the end of the file, using the chunk size it was created with.
+    ///
+    /// Files opened exclusively elsewhere are not modified. Use [ExclusiveFile::append] for appending to them.
+    pub fn append(&mut self, data: &[u8]) -> Result<(), MatryoshkaError> {
+        self.file_system.check_unlocked(self.handle)?;
+        self.append_unchecked(data)
+    }
+
+    fn append_unchecked(&mut self, data: &[u8]) -> Result<(), MatryoshkaError> {
+        self.file_system
+            .append(self.handle, data)
+            .map_err(|error| match error {
+                LoadingError::DatabaseError(error) => MatryoshkaError::Database(error),
+                error => error.into(),
+            })?;
+        self.size += data.len();
+        Ok(())
+    }
+
+    /// Delete the file from the virtual file system. Files opened exclusively are not deleted.
+    pub fn delete(self) -> bool {
+        self.file_system.delete(self.handle) == Ok(1)
+    }
+}
+
+// A derived implementation would needlessly require the database to be cloneable.
+impl<'a, D> Clone for File<'a, D> {
+    fn clone(&self) -> Self {
+        File {
+            file_system: self.file_system,
+            handle: self.handle,
+            size: self.size,
+            current_index: self.current_index,
+        }
+    }
+}
+
+impl<'a, D: BorrowMut<Database>> Read for File<'a, D> {
+    fn read(&mut self, buf: &mut [u8]) -> IoResult<usize> {
+        match self.peek(buf) {
+            Ok(written_bytes) => {
+                self.current_index += written_bytes;
+                Ok(written_bytes)
+            }
+            Err(error) => Err(IoError::new(ErrorKind::Other, error.error_message())),
+        }
+    }
+
+    fn read_to_end(&mut self, buf: &mut Vec<u8>) -> IoResult<usize> {
+        // The chunks are appended one by one, avoiding a temporary buffer for the whole file.
+        let length = self.size - self.current_index;
+        match self.random_read(buf, self.current_index, length) {
+            Ok(written_bytes) => {
+                self.current_index += written_bytes;
+                Ok(written_bytes)
+            }
+            Err(error) => Err(IoError::new(ErrorKind::Other, error.error_message())),
+        }
+    }
+}
+
+/// A file opened exclusively, which is unlocked once it is dropped.
+#[derive(Debug)]
+pub struct ExclusiveFile<'a, D: BorrowMut<Database>> {
+    file: File<'a, D>,
+}
+
+impl<'a, D: BorrowMut<Database>> ExclusiveFile<'a, D> {
+    /// Overwrite existing data of the file in place, starting at the given index.
+    ///
+    /// The range must be within the bounds of the file, as it is never resized.
+    pub fn write_at(&self, index: usize, data: &[u8]) -> Result<(), MatryoshkaError> {
+        if !matches!(index.checked_add(data.len()), Some(end) if end <= self.file.len()) {
+            return Err(ReadError::OutOfBounds.into());
+        }
+        self.file
+            .file_system
+            .write(self.file.handle, data, index)
+            .map_err(|error| {
+                MatryoshkaError::Database(
+                    error.try_into().expect(DatabaseError::LOGIC_ERROR_MESSAGE),
+                )
+            })
+    }
+
+    /// Append data to the end of the file. Unlike [File::append], the lock held by this file is ignored.
+    pub fn append(&mut self, data: &[u8]) -> Result<(), MatryoshkaError> {
+        self.file.append_unchecked(data)
+    }
+
+    /// Store the file anew in chunks of its chunk size. Unlike [File::compact], the lock held by this file is ignored.
+    pub fn compact(&mut self) -> Result<(), MatryoshkaError> {
+        self.file.compact_unchecked()
+    }
+}
+
+impl<'a, D: BorrowMut<Database>> Deref for ExclusiveFile<'a, D> {
+    type Target = File<'a, D>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.file
+    }
+}
+
+impl<'a, D: BorrowMut<Database>> DerefMut for ExclusiveFile<'a, D> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.file
+    }
+}
+
+impl<'a, D: BorrowMut<Database>> Drop for ExclusiveFile<'a, D> {
+    fn drop(&mut self) {
+        // Errors cannot be reported here. At worst, the file stays locked.
+        let _ = self
+            .file
+            .file_system
+            .database
+            .borrow()
+            .execute(constants::SQL_UNLOCK, params![self.file.handle.0]);
+    }
+}
+
+/// A reference to a file which, unlike a File, does not borrow the file system.
+///
+/// It allows keeping track of a file while the file system is modified, i.e. by creating other files.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FileRef {
+    handle: Handle,
+    size: usize,
+}
+
+impl FileRef {
+    /// Open the referenced file in the file system it was created in.
+    ///
+    /// Like File::from_parts_unchecked, neither the existence nor the size of the file is checked.
+    pub fn open<'a, D: BorrowMut<Database>>(&self, file_system: &'a FileSystem<D>) -> File<'a, D> {
+        File::from_parts_unchecked(file_system, self.handle, self.size)
+    }
+
+    /// Query the raw underlying handle.
+    pub fn handle(&self) -> Handle {
+        self.handle
+    }
+
+    /// Query the length of the file.
+    pub fn len(&self) -> usize {
+        self.size
+    }
+
+    /// Checks whether the file is empty.
+    pub fn is_empty(&self) -> bool {
+        self.size == 0
+    }
+}
+
+impl<'a, D: BorrowMut<Database>> TryFrom<(&'a FileSystem<D>, Handle)> for File<'a, D> {
+    type Error = LoadingError;
+
+    fn try_from(value: (&'a FileSystem<D>, Handle)) -> Result<Self, Self::Error> {
+        let (file_system, handle) = value;
+        match file_system.size(handle) {
+            Ok(Some(size)) => Ok(File {
+                file_system,
+                handle,
+                size,
+                current_index: 0,
+            }),
+            Ok(None) => Err(LoadingError::FileNotFound),
+            Err(error) => Err(LoadingError::DatabaseError(error)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::borrow::{Borrow, BorrowMut};
+    use std::cell::RefCell;
+    use std::collections::hash_map::DefaultHasher;
+    use std::convert::TryInto;
+    use std::hash::Hasher;
+
+    use test_case::test_case;
+
+    use super::super::errors::{
+        CreationError, LoadingError, MatryoshkaError, PathError, ProcessError, ReadError,
+        TransferError,
+    };
+    use rusqlite::named_params;
+    use sha2::{Digest, Sha256};
+
+    use super::{
+        CheckpointMode, Database, Duration, File, FileSystem, FileSystemError, Handle,
+        InterruptHandle, Limit, LoadOptions, PathCollation, PathKind, SystemTime,
+    };
+    use std::io::{Read, Write};
+
+    #[test]
+    fn test_loading() {
+        let mut connection = Database::open_in_memory().expect("Open in-memory database failed");
+        {
+            assert_eq!(
+                FileSystem::load(&mut connection, false).unwrap_err(),
+                FileSystemError::NoFileSystem
+            );
+        }
+        {
+            FileSystem::load(&mut connection, true).expect("Creating filesystem failed");
+        }
+        {
+            FileSystem::load(&mut connection, false).expect("Loading created filesystem failed");
+        }
+    }
+
+    #[test]
+    fn test_loading_version() {
+        let mut connection = Database::open_in_memory().expect("Open in-memory database failed");
+        {
+            assert_eq!(
+                FileSystem::load_version(&mut connection, 0, false).unwrap_err(),
+                FileSystemError::NoFileSystem
+            );
+            FileSystem::load_version(&mut connection, 0, true).expect("Creating filesystem failed");
+        }
+
+        // Simulate a more recent version stored side by side
+        connection
+            .execute("CREATE TABLE Matryoshka_Meta_1 (example TEXT)", [])
+            .expect("Creating newer meta table failed");
+        {
+            assert_eq!(
+                FileSystem::load(&mut connection, false).unwrap_err(),
+                FileSystemError::UnsupportedVersion(1)
+            );
+            assert_eq!(
+                FileSystem::load_version(&mut connection, 1, false).unwrap_err(),
+                FileSystemError::UnsupportedVersion(1)
+            );
+            FileSystem::load_version(&mut connection, 0, false)
+                .expect("Loading older filesystem failed");
+        }
+    }
+
+    /// A database recording how it is borrowed.
+    struct TrackedDatabase {
+        database: Database,
+        borrows: RefCell<Vec<&'static str>>,
+    }
+
+    impl Borrow<Database> for TrackedDatabase {
+        fn borrow(&self) -> &Database {
+            self.borrows.borrow_mut().push("borrow");
+            &self.database
+        }
+    }
+
+    impl BorrowMut<Database> for TrackedDatabase {
+        fn borrow_mut(&mut self) -> &mut Database {
+            self.borrows.borrow_mut().push("borrow_mut");
+            &mut self.database
+        }
+    }
+
+    #[test]
+    fn test_borrowing() {
+        let mut file_system = FileSystem::load(
+            TrackedDatabase {
+                database: Database::open_in_memory().expect("Open in-memory database failed"),
+                borrows: RefCell::new(Vec::new()),
+            },
+            true,
+        )
+        .expect("Creating filesystem failed");
+        let borrows = |file_system: &FileSystem<TrackedDatabase>| {
+            file_system.database.borrows.replace(Vec::new())
+        };
+
+        borrows(&file_system);
+        file_system
+            .create("file", &[1u8, 2, 3][..], 2)
+            .expect("File creation failed");
+        assert_eq!(borrows(&file_system), vec!["borrow_mut"]);
+
+        file_system
+            .create_symlink("link", "file")
+            .expect("Symlink creation failed");
+        assert_eq!(borrows(&file_system), vec!["borrow_mut"]);
+
+        let mut writer = file_system
+            .create_writer("written", 2)
+            .expect("Writer creation failed");
+        writer.write_all(&[1u8, 2, 3]).expect("Writing failed");
+        writer.finish().expect("Finishing writer failed");
+        assert_eq!(borrows(&file_system), vec!["borrow_mut"]);
+    }
+
+    #[test]
+    fn test_connection() {
+        let mut file_system = FileSystem::load(
+            Database::open_in_memory().expect("Open in-memory database failed"),
+            true,
+        )
+        .expect("Creating filesystem failed");
+
+        file_system
+            .connection_mut()
+            .pragma_update(None, "user_version", &42)
+            .expect("Updating pragma failed");
+        let user_version: i32 = file_system
+            .connection()
+            .pragma_query_value(None, "user_version", |row| row.get(0))
+            .expect("Querying pragma failed");
+        assert_eq!(user_version, 42);
+    }
+
+    #[test_case(None; "default slack")]
+    #[test_case(Some(0); "no slack")]
+    fn test_statement_cache(slack: Option<usize>) {
+        let mut file_system = FileSystem::load(
+            Database::open_in_memory().expect("Open in-memory database failed"),
+            true,
+        )
+        .expect("Creating filesystem failed");
+        if let Some(slack) = slack {
+            file_system.set_statement_cache_slack(slack);
+        }
+
+        let data: Vec<u8> = (0..10).collect();
+        for index in 0..20 {
+            let path = format!("folder/file_{}", index);
+            File::create(&mut file_system, &path, &data[..], 3).expect("File creation failed");
+
+            // Interleave the operations with a user statement
+            let num_files: i64 = file_system
+                .connection()
+                .prepare_cached("SELECT COUNT(*) FROM Matryoshka_Meta_0")
+                .expect("Preparing user statement failed")
+                .query_row([], |row| row.get(0))
+                .expect("Querying user statement failed");
+            assert_eq!(num_files, index + 1);
+
+            assert_eq!(
+                file_system.find("folder/*").expect("Finding failed").len() as i64,
+                index + 1
+            );
+            let mut buffer = Vec::new();
+            File::load(&file_system, &path)
+                .expect("Loading file failed")
+                .read_to_end(&mut buffer)
+                .expect("Reading failed");
+            assert_eq!(buffer, data);
+        }
+    }
+
+    #[test]
+    fn test_into_inner() {
+        let mut file_system = FileSystem::load(
+            Database::open_in_memory().expect("Open in-memory database failed"),
+            true,
+        )
+        .expect("Creating filesystem failed");
+        File::create(&mut file_system, "file", &[1u8, 2, 3][..], 2).expect("File creation failed");
+
+        let connection = file_system.into_inner();
+        let num_files: i64 = connection
+            .query_row(
+                &format!(
+                    "SELECT COUNT(*) FROM {}",
+                    super::constants::MATRYOSHKA_TABLE
+                ),
+                [],
+                |row| row.get(0),
+            )
+            .expect("Querying database failed");
+        assert_eq!(num_files, 1);
+    }
+
+    #[test]
+    fn test_find_any() {
+        let mut file_system = FileSystem::load(
+            Database::open_in_memory().expect("Open in-memory database failed"),
+            true,
+        )
+        .expect("Creating filesystem failed");
+        for path in ["b.png", "a.jpg", "c.png", "d.txt"].iter() {
+            File::create(&mut file_system, path, &[1u8][..], 1).expect("File creation failed");
+        }
+
+        assert_eq!(
+            file_system
+                .find_any(&["*.png", "*.jpg", "c.*"])
+                .expect("Finding failed"),
+            vec!["a.jpg", "b.png", "c.png"]
+        );
+        assert!(file_system
+            .find_any(&["*.gif"])
+            .expect("Finding failed")
+            .is_empty());
+        assert!(file_system
+            .find_any::<&str>(&[])
+            .expect("Finding failed")
+            .is_empty());
+    }
+
+    #[test]
+    fn test_size_matching() {
+        let mut file_system = FileSystem::load(
+            Database::open_in_memory().expect("Open in-memory database failed"),
+            true,
+        )
+        .expect("Creating filesystem failed");
+        let data: Vec<u8> = (0..100).collect();
+        for (path, size) in [("a.log", 10), ("b.log", 25), ("c.txt", 7), ("d.txt", 100)].iter() {
+            File::create(&mut file_system, path, &data[..*size], 3).expect("File creation failed");
+        }
+        file_system
+            .create_symlink("e.log", "d.txt")
+            .expect("Symlink creation failed");
+
+        assert_eq!(
+            file_system
+                .size_matching("*.log")
+                .expect("Querying size failed"),
+            35
+        );
+        assert_eq!(
+            file_system
+                .size_matching("*.txt")
+                .expect("Querying size failed"),
+            107
+        );
+        assert_eq!(
+            file_system
+                .size_matching("*.gif")
+                .expect("Querying size failed"),
+            0
+        );
+    }
+
+    #[test]
+    fn test_sizes_of_compressed() {
+        let mut file_system = FileSystem::load(
+            Database::open_in_memory().expect("Open in-memory database failed"),
+            true,
+        )
+        .expect("Creating filesystem failed");
+        File::create_compressed(&mut file_system, "compressed", &[7u8; 1000][..], 300)
+            .expect("File creation failed");
+        File::create(&mut file_system, "plain", &[7u8; 500][..], 300)
+            .expect("File creation failed");
+
+        // The original size is reported instead of the stored one
+        assert_eq!(
+            file_system.list_with_sizes("*"),
+            Ok(vec![
+                (String::from("compressed"), 1000),
+                (String::from("plain"), 500)
+            ])
+        );
+        assert_eq!(file_system.size_matching("*"), Ok(1500));
+        assert_eq!(
+            file_system.find_by_size(600, None),
+            Ok(vec![String::from("compressed")])
+        );
+        assert_eq!(
+            file_system.largest_file(),
+            Ok(Some((String::from("compressed"), 1000)))
+        );
+    }
+
+    #[test]
+    fn test_list_with_sizes() {
+        let mut file_system = FileSystem::load(
+            Database::open_in_memory().expect("Open in-memory database failed"),
+            true,
+        )
+        .expect("Creating filesystem failed");
+        let data: Vec<u8> = (0..10).collect();
+        for (path, size) in [
+            ("folder/b", 10),
+            ("folder/a", 4),
+            ("folder/empty", 0),
+            ("c", 1),
+        ]
+        .iter()
+        {
+            File::create(&mut file_system, path, &data[..*size], 3).expect("File creation failed");
+        }
+        file_system
+            .create_symlink("folder/link", "c")
+            .expect("Symlink creation failed");
+
+        assert_eq!(
+            file_system
+                .list_with_sizes("folder/*")
+                .expect("Listing failed"),
+            vec![
+                (String::from("folder/a"), 4),
+                (String::from("folder/b"), 10),
+                (String::from("folder/empty"), 0)
+            ]
+        );
+        assert!(file_system
+            .list_with_sizes("missing/*")
+            .expect("Listing failed")
+            .is_empty());
+    }
+
+    #[test]
+    fn test_glob() {
+        let mut file_system = FileSystem::load(
+            Database::open_in_memory().expect("Open in-memory database failed"),
+            true,
+        )
+        .expect("Creating filesystem failed");
+        for path in ["folder/file", "folder/sub/file", "other/file"].iter() {
+            File::create(&mut file_system, path, &[1u8][..], 1).expect("File creation failed");
+        }
+
+        // SQLite GLOB crosses separators
+        assert_eq!(
+            file_system.find("folder/*").expect("Finding failed"),
+            vec!["folder/file", "folder/sub/file"]
+        );
+
+        assert_eq!(
+            file_system.glob("folder/*").expect("Globbing failed"),
+            vec!["folder/file"]
+        );
+        assert_eq!(
+            file_system.glob("folder/**").expect("Globbing failed"),
+            vec!["folder/file", "folder/sub/file"]
+        );
+        assert_eq!(
+            file_system.glob("*/file").expect("Globbing failed"),
+            vec!["folder/file", "other/file"]
+        );
+        assert!(file_system.glob("*").expect("Globbing failed").is_empty());
+    }
+
+    #[test]
+    fn test_page_size() {
+        let mut connection = Database::open_in_memory().expect("Open in-memory database failed");
+        {
+            let file_system = FileSystem::load_with_page_size(&mut connection, true, Some(16384))
+                .expect("Creating filesystem failed");
+            assert_eq!(
+                file_system.page_size().expect("Querying page size failed"),
+                16384
+            );
+        }
+        let page_size: u32 = connection
+            .pragma_query_value(None, "page_size", |row| row.get(0))
+            .expect("Querying page size failed");
+        assert_eq!(page_size, 16384);
+
+        // Existing file systems are not affected
+        let file_system = FileSystem::load_with_page_size(&mut connection, true, Some(4096))
+            .expect("Loading filesystem failed");
+        assert_eq!(
+            file_system.page_size().expect("Querying page size failed"),
+            16384
+        );
+    }
+
+    #[test_case(true; "incremental")]
+    #[test_case(false; "disabled")]
+    fn test_incremental_vacuum(incremental_vacuum: bool) {
+        let mut file_system = match incremental_vacuum {
+            true => FileSystem::load_with_incremental_vacuum(
+                Database::open_in_memory().expect("Open in-memory database failed"),
+                true,
+            ),
+            false => FileSystem::load(
+                Database::open_in_memory().expect("Open in-memory database failed"),
+                true,
+            ),
+        }
+        .expect("Creating filesystem failed");
+        for index in 0..4 {
+            File::create(
+                &mut file_system,
+                format!("file{}", index),
+                &[42u8; 16384][..],
+                4096,
+            )
+            .expect("File creation failed");
+        }
+        for index in 0..4 {
+            file_system
+                .delete_handle(
+                    File::load(&file_system, format!("file{}", index))
+                        .expect("Loading file failed")
+                        .handle(),
+                )
+                .expect("Deleting file failed");
+        }
+
+        let free_pages = |file_system: &FileSystem<Database>| {
+            file_system
+                .storage_stats()
+                .expect("Querying stats failed")
+                .free_pages()
+        };
+        let initial_free_pages = free_pages(&file_system);
+        assert!(initial_free_pages > 1);
+
+        file_system
+            .incremental_vacuum(Some(1))
+            .expect("Vacuuming failed");
+        match incremental_vacuum {
+            true => assert_eq!(free_pages(&file_system), initial_free_pages - 1),
+            false => assert_eq!(free_pages(&file_system), initial_free_pages),
+        }
+        file_system
+            .incremental_vacuum(None)
+            .expect("Vacuuming failed");
+        match incremental_vacuum {
+            true => assert_eq!(free_pages(&file_system), 0),
+            false => assert_eq!(free_pages(&file_system), initial_free_pages),
+        }
+    }
+
+    #[test]
+    fn test_load_options() {
+        let mut connection = Database::open_in_memory().expect("Open in-memory database failed");
+        assert_eq!(
+            FileSystem::load_with(&mut connection, &LoadOptions::default()).unwrap_err(),
+            FileSystemError::NoFileSystem
+        );
+
+        // All options apply at once when creating the file system
+        {
+            let file_system = FileSystem::load_with(
+                &mut connection,
+                LoadOptions::new()
+                    .create(true)
+                    .page_size(8192)
+                    .incremental_vacuum(true)
+                    .collation(PathCollation::NoCase),
+            )
+            .expect("Creating filesystem failed");
+            assert_eq!(
+                file_system.page_size().expect("Querying page size failed"),
+                8192
+            );
+            assert_eq!(file_system.path_collation(), PathCollation::NoCase);
+        }
+        let auto_vacuum: u32 = connection
+            .pragma_query_value(None, "auto_vacuum", |row| row.get(0))
+            .expect("Querying vacuum mode failed");
+        assert_eq!(auto_vacuum, 2);
+
+        let file_system = FileSystem::load_with(&mut connection, &LoadOptions::new())
+            .expect("Loading filesystem failed");
+        assert_eq!(file_system.path_collation(), PathCollation::NoCase);
+
+        // Database files are opened with the default VFS unless specified
+        let tmp_dir = tempfile::TempDir::new().expect("Unable to create temporary directory");
+        let path = tmp_dir.path().join("database.sqlite");
+        assert!(FileSystem::open_with(&path, &LoadOptions::new()).is_err());
+        assert!(!path.exists());
+        assert!(matches!(
+            FileSystem::open_with(
+                tmp_dir.path().join("bad\0name"),
+                LoadOptions::new().create(true)
+            ),
+            Err(FileSystemError::DatabaseError(_))
+        ));
+        assert!(matches!(
+            FileSystem::open_with(&path, LoadOptions::new().create(true).vfs("bad\0vfs")),
+            Err(FileSystemError::DatabaseError(_))
+        ));
+        assert!(!path.exists());
+        let file_system = FileSystem::open_with(
+            &path,
+            LoadOptions::new()
+                .create(true)
+                .collation(PathCollation::NoCase),
+        )
+        .expect("Creating filesystem failed");
+        assert_eq!(file_system.path_collation(), PathCollation::NoCase);
+    }
+
+    #[test]
+    fn test_in_memory() {
+        let file_system = FileSystem::load(
+            Database::open_in_memory().expect("Open in-memory database failed"),
+            true,
+        )
+        .expect("Creating filesystem failed");
+        assert!(file_system.is_in_memory());
+
+        let tmp_dir = tempfile::TempDir::new().expect("Unable to create temporary directory");
+        let file_system = FileSystem::load(
+            Database::open(tmp_dir.path().join("database.sqlite"))
+                .expect("Open database file failed"),
+            true,
+        )
+        .expect("Creating filesystem failed");
+        assert!(!file_system.is_in_memory());
+    }
+
+    #[test]
+    fn test_load_with_vfs() {
+        let default_vfs = unsafe {
+            std::ffi::CStr::from_ptr((*rusqlite::ffi::sqlite3_vfs_find(std::ptr::null())).zName)
+        }
+        .to_str()
+        .expect("Invalid VFS name")
+        .to_string();
+        let tmp_dir = tempfile::TempDir::new().expect("Unable to create temporary directory");
+        let path = tmp_dir.path().join("database.sqlite");
+
+        // Without creating the file system, the database file is not created either
+        assert!(FileSystem::load_with_vfs(&path, &default_vfs, false).is_err());
+        assert!(!path.exists());
+        assert!(FileSystem::load_with_vfs(&path, "missing_vfs", true).is_err());
+        assert!(FileSystem::load_with_vfs(&path, "bad\0vfs", true).is_err());
+        assert!(
+            FileSystem::load_with_vfs(tmp_dir.path().join("bad\0name"), &default_vfs, true)
+                .is_err()
+        );
+        assert!(!path.exists());
+
+        {
+            let mut file_system = FileSystem::load_with_vfs(&path, &default_vfs, true)
+                .expect("Creating filesystem failed");
+            File::create(&mut file_system, "first", &b"42"[..], 1).expect("File creation failed");
+        }
+        {
+            let mut file_system = FileSystem::load(
+                Database::open(&path).expect("Open database file failed"),
+                false,
+            )
+            .expect("Loading filesystem failed");
+            assert_eq!(
+                file_system.find("*").expect("Finding failed"),
+                vec!["first"]
+            );
+            File::create(&mut file_system, "second", &b"PI"[..], 1).expect("File creation failed");
+        }
+
+        let file_system = FileSystem::load_with_vfs(&path, &default_vfs, false)
+            .expect("Loading filesystem failed");
+        assert_eq!(
+            file_system.find("*").expect("Finding failed"),
+            vec!["first", "second"]
+        );
+        assert_eq!(
+            File::load(&file_system, "second")
+                .expect("Loading file failed")
+                .read_to_string(),
+            Ok(String::from("PI"))
+        );
+    }
+
+    #[test]
+    fn test_chunk_stats() {
+        let mut file_system = FileSystem::load(
+            Database::open_in_memory().expect("Open in-memory database failed"),
+            true,
+        )
+        .expect("Creating filesystem failed");
+        let stats = file_system.chunk_stats().expect("Querying stats failed");
+        assert_eq!(stats.count(), 0);
+        assert_eq!(stats.max_length(), 0);
+        assert_eq!(stats.average_length(), None);
+
+        // Chunks of 4, 4 and 2 bytes
+        File::create(&mut file_system, "first", &[1u8; 10][..], 4).expect("File creation failed");
+        // Chunks of 3 and 3 bytes, followed by an empty one
+        File::create(&mut file_system, "second", &[2u8; 6][..], 3).expect("File creation failed");
+
+        let stats = file_system.chunk_stats().expect("Querying stats failed");
+        assert_eq!(stats.count(), 6);
+        assert_eq!(stats.total_length(), 16);
+        assert_eq!(stats.max_length(), 4);
+        assert_eq!(stats.average_length(), Some(16.0 / 6.0));
+    }
+
+    #[test]
+    fn test_handle_exists() {
+        let mut file_system = FileSystem::load(
+            Database::open_in_memory().expect("Open in-memory database failed"),
+            true,
+        )
+        .expect("Creating filesystem failed");
+        let file = File::create(&mut file_system, "file", &b"42"[..], 1)
+            .expect("File creation failed")
+            .handle();
+        let empty = File::create(&mut file_system, "empty", &[][..], 1)
+            .expect("File creation failed")
+            .handle();
+        let symlink = file_system
+            .create_symlink("link", "file")
+            .expect("Symlink creation failed");
+
+        assert_eq!(file_system.handle_exists(file), Ok(true));
+        assert_eq!(file_system.handle_exists(empty), Ok(true));
+        assert_eq!(file_system.handle_exists(symlink), Ok(false));
+        assert_eq!(file_system.handle_exists(Handle(4242)), Ok(false));
+
+        assert!(File::load(&file_system, "file")
+            .expect("Loading file failed")
+            .delete());
+        assert_eq!(file_system.handle_exists(file), Ok(false));
+    }
+
+    #[test]
+    fn test_check_capacity() {
+        let file_system = FileSystem::load(
+            Database::open_in_memory().expect("Open in-memory database failed"),
+            true,
+        )
+        .expect("Creating filesystem failed");
+        assert!(file_system
+            .check_capacity(1)
+            .expect("Checking capacity failed"));
+        assert!(file_system
+            .check_capacity(u64::MAX)
+            .expect("Checking capacity failed"));
+
+        let tmp_dir = tempfile::TempDir::new().expect("Unable to create temporary directory");
+        let file_system = FileSystem::load(
+            Database::open(tmp_dir.path().join("database.sqlite"))
+                .expect("Open database file failed"),
+            true,
+        )
+        .expect("Creating filesystem failed");
+        assert!(file_system
+            .check_capacity(1)
+            .expect("Checking capacity failed"));
+        assert!(!file_system
+            .check_capacity(u64::MAX)
+            .expect("Checking capacity failed"));
+    }
+
+    #[test_case(0, 0, 0, 0, false; "File size: 0, Chunk size: 0, First index: 0, Length: 0")]
+    #[test_case(1, 0, 0, 1, false; "File size: 1, Chunk size: 0, First index: 0, Length: 1")]
+    #[test_case(3, 0, 0, 3, false; "File size: 3, Chunk size: 0, First index: 0, Length: 3")]
+    #[test_case(0, 1, 0, 0, false; "File size: 0, Chunk size: 1, First index: 0, Length: 0")]
+    #[test_case(1, 1, 0, 1, false; "File size: 1, Chunk size: 1, First index: 0, Length: 1")]
+    #[test_case(3, 1, 0, 3, false; "File size: 3, Chunk size: 1, First index: 0, Length: 3")]
+    #[test_case(0, 3, 0, 0, false; "File size: 0, Chunk size: 3, First index: 0, Length: 0")]
+    #[test_case(1, 3, 0, 1, false; "File size: 1, Chunk size: 3, First index: 0, Length: 1")]
+    #[test_case(3, 3, 0, 3, false; "File size: 3, Chunk size: 3, First index: 0, Length: 3")]
+    #[test_case(0, 4, 0, 0, false; "File size: 0, Chunk size: 4, First index: 0, Length: 0")]
+    #[test_case(1, 4, 0, 1, false; "File size: 1, Chunk size: 4, First index: 0, Length: 1")]
+    #[test_case(3, 4, 0, 3, false; "File size: 3, Chunk size: 4, First index: 0, Length: 3")]
+    // Test random reads
+    #[test_case(3, 0, 1, 2, false; "File size: 3, Chunk size: 0, First index: 1, Length: 2")]
+    #[test_case(3, 1, 1, 2, false; "File size: 3, Chunk size: 1, First index: 1, Length: 2")]
+    #[test_case(3, 3, 1, 2, false; "File size: 3, Chunk size: 3, First index: 1, Length: 2")]
+    #[test_case(3, 4, 1, 2, false; "File size: 3, Chunk size: 4, First index: 1, Length: 2")]
+    #[test_case(3, 0, 2, 1, false; "File size: 3, Chunk size: 0, First index: 2, Length: 1")]
+    #[test_case(3, 1, 2, 1, false; "File size: 3, Chunk size: 1, First index: 2, Length: 1")]
+    #[test_case(3, 3, 2, 1, false; "File size: 3, Chunk size: 3, First index: 2, Length: 1")]
+    #[test_case(3, 4, 2, 1, false; "File size: 3, Chunk size: 4, First index: 2, Length: 1")]
+    #[test_case(6, 4, 2, 1, false; "File size: 4, Chunk size: 4, First index: 2, Length: 2")]
+    // Test out-of-bounds
+    #[test_case(0, 0, 0, 1, true; "File size: 0, Chunk size: 0, First index: 0, Length: 1 --> OUT OF BOUNDS!")]
+    #[test_case(1, 0, 1, 1, true; "File size: 1, Chunk size: 0, First index: 1, Length: 1 --> OUT OF BOUNDS!")]
+    #[test_case(1, 0, 1, 2, true; "File size: 1, Chunk size: 0, First index: 1, Length: 2 --> OUT OF BOUNDS!")]
+    #[test_case(3, 0, 1, 3, true; "File size: 3, Chunk size: 0, First index: 1, Length: 3 --> OUT OF BOUNDS!")]
+    #[test_case(3, 0, 2, 2, true; "File size: 3, Chunk size: 0, First index: 2, Length: 2 --> OUT OF BOUNDS!")]
+    #[test_case(0, 1, 0, 1, true; "File size: 0, Chunk size: 1, First index: 0, Length: 1 --> OUT OF BOUNDS!")]
+    #[test_case(1, 1, 1, 1, true; "File size: 1, Chunk size: 1, First index: 1, Length: 1 --> OUT OF BOUNDS!")]
+    #[test_case(1, 1, 1, 2, true; "File size: 1, Chunk size: 1, First index: 1, Length: 2 --> OUT OF BOUNDS!")]
+    #[test_case(3, 1, 1, 3, true; "File size: 3, Chunk size: 1, First index: 1, Length: 3 --> OUT OF BOUNDS!")]
+    #[test_case(3, 1, 2, 2, true; "File size: 3, Chunk size: 1, First index: 2, Length: 2 --> OUT OF BOUNDS!")]
+    #[test_case(0, 3, 0, 1, true; "File size: 0, Chunk size: 3, First index: 0, Length: 1 --> OUT OF BOUNDS!")]
+    #[test_case(1, 3, 1, 1, true; "File size: 1, Chunk size: 3, First index: 1, Length: 1 --> OUT OF BOUNDS!")]
+    #[test_case(1, 3, 1, 2, true; "File size: 1, Chunk size: 3, First index: 1, Length: 2 --> OUT OF BOUNDS!")]
+    #[test_case(3, 3, 1, 3, true; "File size: 3, Chunk size: 3, First index: 1, Length: 3 --> OUT OF BOUNDS!")]
+    #[test_case(3, 3, 2, 2, true; "File size: 3, Chunk size: 3, First index: 2, Length: 2 --> OUT OF BOUNDS!")]
+    #[test_case(0, 4, 0, 1, true; "File size: 0, Chunk size: 4, First index: 0, Length: 1 --> OUT OF BOUNDS!")]
+    #[test_case(1, 4, 1, 1, true; "File size: 1, Chunk size: 4, First index: 1, Length: 1 --> OUT OF BOUNDS!")]
+    #[test_case(1, 4, 1, 2, true; "File size: 1, Chunk size: 4, First index: 1, Length: 2 --> OUT OF BOUNDS!")]
+    #[test_case(3, 4, 1, 3, true; "File size: 3, Chunk size: 4, First index: 1, Length: 3 --> OUT OF BOUNDS!")]
+    #[test_case(3, 4, 2, 2, true; "File size: 3, Chunk size: 4, First index: 2, Length: 2 --> OUT OF BOUNDS!")]
+    // Special case: It is always save to read data of length 0
+    #[test_case(0, 0, 1, 0, false; "File size: 0, Chunk size: 0, First index: 1, Length: 0")]
+    #[test_case(0, 1, 1, 0, false; "File size: 0, Chunk size: 1, First index: 1, Length: 0")]
+    #[test_case(0, 3, 1, 0, false; "File size: 0, Chunk size: 3, First index: 1, Length: 0")]
+    #[test_case(0, 4, 1, 0, false; "File size: 0, Chunk size: 4, First index: 1, Length: 0")]
+    fn test_file_handling(
+        file_size: u8,
+        chunk_size: usize,
+        index: usize,
+        length: usize,
+        is_out_of_bounds: bool,
+    ) {
+        let data: Vec<_> = (0..file_size).into_iter().collect();
+        let path = "file";
+        let mut connection = Database::open_in_memory().expect("Open in-memory database failed");
+        let mut file_system =
+            FileSystem::load(&mut connection, true).expect("Creating filesystem failed");
+
+        // Create file
+        {
+            let file = File::create(&mut file_system, path, &data[..], chunk_size)
+                .expect("Creating file failed");
+            assert_eq!(file.len(), data.len());
+        }
+
+        // Check that the file could not be overwritten
+        assert_eq!(
+            File::create(&mut file_system, path, &data[..], chunk_size)
+                .expect_err("Able to write file a second time"),
+            CreationError::FileExists
+        );
+
+        // Load and read file
+        {
+            let file = File::load(&mut file_system, path).expect("Loading file failed");
+            assert_eq!(file.len(), data.len());
+
+            let mut read_data = Vec::new();
+            if is_out_of_bounds {
+                assert_eq!(
+                    file.random_read(&mut read_data, index, length)
+                        .expect_err("Reading file content was successful despite out of bounds"),
+                    ReadError::OutOfBounds
+                );
+            } else {
+                assert_eq!(
+                    file.random_read(&mut read_data, index, length)
+                        .expect("Reading file content failed"),
+                    length
+                );
+                assert_eq!(read_data.len(), length);
+                if length > 0 {
+                    assert_eq!(&read_data, &data[index..(index + length)]);
+                }
+            }
+        }
+    }
+
+    #[test_case(12, 4, 0, 12; "Whole file")]
+    #[test_case(12, 4, 4, 8; "Trailing chunks")]
+    #[test_case(12, 4, 4, 4; "Single inner chunk")]
+    #[test_case(10, 5, 5, 5; "Last chunk")]
+    fn test_aligned_read(file_size: u8, chunk_size: usize, index: usize, length: usize) {
+        let data: Vec<_> = (0..file_size).collect();
+        let mut file_system = FileSystem::load(
+            Database::open_in_memory().expect("Open in-memory database failed"),
+            true,
+        )
+        .expect("Creating filesystem failed");
+        let file = File::create(&mut file_system, "file", &data[..], chunk_size)
+            .expect("File creation failed");
+
+        let mut aligned_data = Vec::new();
+        assert_eq!(
+            file.random_read(&mut aligned_data, index, length)
+                .expect("Aligned read failed"),
+            length
+        );
+
+        // Assemble the same range using unaligned reads only
+        let mut unaligned_data = Vec::new();
+        file.random_read(&mut unaligned_data, index, 1)
+            .expect("Unaligned read failed");
+        file.random_read(&mut unaligned_data, index + 1, length - 1)
+            .expect("Unaligned read failed");
+
+        assert_eq!(&aligned_data, &unaligned_data);
+        assert_eq!(&aligned_data, &data[index..(index + length)]);
+
+        // Compressed chunks are copied as a whole as well
+        let file = File::create_compressed(&mut file_system, "compressed", &data[..], chunk_size)
+            .expect("File creation failed");
+        let mut compressed_data = Vec::new();
+        file.random_read(&mut compressed_data, index, length)
+            .expect("Aligned read failed");
+        assert_eq!(&compressed_data, &unaligned_data);
+    }
+
+    #[test]
+    fn test_aligned_read_out_of_bounds() {
+        let data = [1u8, 2, 3, 4, 5, 6];
+        let mut file_system = FileSystem::load(
+            Database::open_in_memory().expect("Open in-memory database failed"),
+            true,
+        )
+        .expect("Creating filesystem failed");
+        let file =
+            File::create(&mut file_system, "file", &data[..], 4).expect("File creation failed");
+
+        // The last chunk is shorter than the aligned length
+        assert_eq!(
+            file.random_read(Vec::new(), 4, 4)
+                .expect_err("Reading beyond the file succeeded"),
+            ReadError::OutOfBounds
+        );
+        assert_eq!(
+            file.random_read(Vec::new(), 8, 4)
+                .expect_err("Reading beyond the file succeeded"),
+            ReadError::OutOfBounds
+        );
+    }
+
+    #[test]
+    fn test_read_trait() {
+        let mut file_system = FileSystem::load(
+            Database::open_in_memory().expect("Open in-memory database failed"),
+            true,
+        )
+        .expect("Creating filesystem failed");
+
+        let data = [1u8, 2, 3, 4, 5];
+
+        let mut file =
+            File::create(&mut file_system, "file", &data[..], 3).expect("File creation failed");
+        let mut buffer = vec![0u8; 3];
+        assert_eq!(file.read(&mut buffer[..]).expect("Successful read"), 3);
+        assert_eq!(&buffer, &[1u8, 2, 3]);
+
+        assert_eq!(file.read_to_end(&mut buffer).expect("Successful read"), 2);
+        assert_eq!(&buffer, &data);
+
+        // Test that it is safe to read at EOF
+        assert_eq!(file.read(&mut buffer[..]).expect("Successful read"), 0);
+        assert_eq!(file.read_to_end(&mut buffer).expect("Successful read"), 0);
+    }
+
+    #[test_case(0, 3; "Empty file")]
+    #[test_case(2, 3; "Single chunk")]
+    #[test_case(3, 3; "Single full chunk")]
+    #[test_case(7, 3; "Multiple chunks")]
+    fn test_created_size(file_size: u8, chunk_size: usize) {
+        let mut file_system = FileSystem::load(
+            Database::open_in_memory().expect("Open in-memory database failed"),
+            true,
+        )
+        .expect("Creating filesystem failed");
+
+        let data: Vec<_> = (0..file_size).collect();
+        let handle = {
+            let file = File::create(&mut file_system, "file", &data[..], chunk_size)
+                .expect("File creation failed");
+            assert_eq!(file.len(), data.len());
+            file.handle()
+        };
+
+        // The size tracked during creation matches the stored one
+        assert_eq!(
+            file_system.size(handle).expect("Querying size failed"),
+            Some(data.len())
+        );
+    }
+
+    #[test_case(5, 2, 3; "partial last chunk")]
+    #[test_case(4, 2, 2; "full last chunk")]
+    #[test_case(0, 2, 0; "empty file")]
+    fn test_chunks(size: u8, chunk_size: usize, num_chunks: usize) {
+        let mut file_system = FileSystem::load(
+            Database::open_in_memory().expect("Open in-memory database failed"),
+            true,
+        )
+        .expect("Creating filesystem failed");
+
+        let data: Vec<u8> = (0..size).collect();
+        let file = File::create(&mut file_system, "file", &data[..], chunk_size)
+            .expect("File creation failed");
+
+        let chunks: Vec<Vec<u8>> = file
+            .chunks()
+            .collect::<Result<_, _>>()
+            .expect("Reading chunks failed");
+        assert_eq!(chunks.len(), num_chunks);
+        assert!(chunks.iter().all(|chunk| chunk.len() <= chunk_size));
+        assert_eq!(chunks.concat(), data);
+    }
+
+    #[test_case(0, 4; "empty")]
+    #[test_case(8, 4; "full last chunk")]
+    #[test_case(1000, 64; "partial last chunk")]
+    fn test_compressed(size: usize, chunk_size: usize) {
+        let mut file_system = FileSystem::load(
+            Database::open_in_memory().expect("Open in-memory database failed"),
+            true,
+        )
+        .expect("Creating filesystem failed");
+
+        let data: Vec<u8> = (0..size).map(|value| (value % 7) as u8).collect();
+        File::create(&mut file_system, "plain", &data[..], chunk_size)
+            .expect("File creation failed");
+        File::create_compressed(&mut file_system, "compressed", &data[..], chunk_size)
+            .expect("File creation failed");
+
+        for path in ["plain", "compressed"].iter() {
+            let file = File::load(&file_system, path).expect("Loading file failed");
+            assert_eq!(file.len(), size);
+
+            // Read ranges starting and ending inside and at the boundaries of chunks
+            for start in (0..size).step_by(3) {
+                for length in [1, chunk_size - 1, chunk_size, size - start].iter() {
+                    let length = std::cmp::min(*length, size - start);
+                    let mut buffer = Vec::new();
+                    assert_eq!(
+                        file.random_read(&mut buffer, start, length)
+                            .expect("Reading failed"),
+                        length
+                    );
+                    assert_eq!(&buffer[..], &data[start..start + length]);
+                }
+            }
+            assert_eq!(
+                file.random_read(Vec::new(), size, 1)
+                    .expect_err("Reading beyond the file succeeded"),
+                ReadError::OutOfBounds
+            );
+
+            let chunks: Vec<Vec<u8>> = file
+                .chunks()
+                .collect::<Result<_, _>>()
+                .expect("Reading chunks failed");
+            assert_eq!(chunks.concat(), data);
+        }
+
+        // Compressible data needs less space
+        if chunk_size >= 64 {
+            let stored_size = |path: &str| {
+                File::load(&file_system, path)
+                    .expect("Loading file failed")
+                    .stored_size()
+                    .expect("Querying size failed")
+            };
+            assert!(stored_size("compressed") < stored_size("plain"));
+        }
+    }
+
+    #[test]
+    fn test_copy_range_compressed() {
+        let mut file_system = FileSystem::load(
+            Database::open_in_memory().expect("Open in-memory database failed"),
+            true,
+        )
+        .expect("Creating filesystem failed");
+        let source: Vec<u8> = (0..10).collect();
+        File::create(&mut file_system, "source", &source[..], 3).expect("File creation failed");
+        File::create_compressed(&mut file_system, "destination", &[0u8; 8][..], 3)
+            .expect("File creation failed");
+
+        file_system
+            .copy_range("source", 2, 5, "destination", 2)
+            .expect("Copying range failed");
+        let mut buffer = Vec::new();
+        File::load(&file_system, "destination")
+            .expect("Loading file failed")
+            .read_to_end(&mut buffer)
+            .expect("Reading failed");
+        assert_eq!(buffer, vec![0, 0, 2, 3, 4, 5, 6, 0]);
+    }
+
+    #[test]
+    fn test_read_chunk() {
+        let mut file_system = FileSystem::load(
+            Database::open_in_memory().expect("Open in-memory database failed"),
+            true,
+        )
+        .expect("Creating filesystem failed");
+
+        let data: Vec<u8> = (0..10).collect();
+        let file =
+            File::create(&mut file_system, "file", &data[..], 3).expect("File creation failed");
+
+        for (chunk_num, expected) in data.chunks(3).enumerate() {
+            assert_eq!(
+                file.read_chunk(chunk_num as u32)
+                    .expect("Reading chunk failed"),
+                expected
+            );
+        }
+        assert_eq!(
+            file.read_chunk(4).expect_err("Read missing chunk"),
+            ReadError::OutOfBounds
+        );
+    }
+
+    #[test]
+    fn test_read_to_string() {
+        let mut file_system = FileSystem::load(
+            Database::open_in_memory().expect("Open in-memory database failed"),
+            true,
+        )
+        .expect("Creating filesystem failed");
+
+        // Both the 2-byte and the 3-byte character straddle a chunk boundary.
+        let text = "aä€bc";
+        File::create(&mut file_system, "text", text.as_bytes(), 2).expect("File creation failed");
+        let file = File::load(&file_system, "text").expect("Loading file failed");
+        assert_eq!(file.read_to_string().expect("Reading text failed"), text);
+
+        File::create(&mut file_system, "empty", &[][..], 2).expect("File creation failed");
+        let file = File::load(&file_system, "empty").expect("Loading file failed");
+        assert_eq!(file.read_to_string().expect("Reading text failed"), "");
+
+        // Invalid bytes and truncated characters are detected
+        for (path, data) in [
+            ("invalid", &[b'a', 0xFF, b'b'][..]),
+            ("truncated", &[b'a', 0xE2, 0x82][..]),
+        ]
+        .iter()
+        {
+            File::create(&mut file_system, path, *data, 2).expect("File creation failed");
+            let file = File::load(&file_system, path).expect("Loading file failed");
+            assert_eq!(
+                file.read_to_string()
+                    .expect_err("Reading invalid text succeeded"),
+                ReadError::InvalidUtf8
+            );
+        }
+    }
+
+    #[test_case(1, &[5u8]; "last byte")]
+    #[test_case(2, &[4u8, 5]; "last chunk")]
+    #[test_case(10, &[1u8, 2, 3, 4, 5]; "clamped")]
+    #[test_case(0, &[]; "nothing")]
+    fn test_read_tail(length: usize, expected: &[u8]) {
+        let mut file_system = FileSystem::load(
+            Database::open_in_memory().expect("Open in-memory database failed"),
+            true,
+        )
+        .expect("Creating filesystem failed");
+
+        let data = [1u8, 2, 3, 4, 5];
+        let file =
+            File::create(&mut file_system, "file", &data[..], 2).expect("File creation failed");
+
+        let mut buffer = Vec::new();
+        assert_eq!(
+            file.read_tail(&mut buffer, length)
+                .expect("Reading tail failed"),
+            expected.len()
+        );
+        assert_eq!(&buffer, expected);
+    }
+
+    #[test]
+    #[cfg(feature = "serialize")]
+    fn test_bytes() {
+        let mut file_system = FileSystem::load(
+            Database::open_in_memory().expect("Open in-memory database failed"),
+            true,
+        )
+        .expect("Creating filesystem failed");
+        File::create(&mut file_system, "folder/file", &[1u8, 2, 3][..], 2)
+            .expect("File creation failed");
+        File::create(&mut file_system, "empty", &[][..], 2).expect("File creation failed");
+
+        let data = file_system.to_bytes().expect("Serialization failed");
+        let file_system =
+            FileSystem::from_bytes(data, false).expect("Loading serialized filesystem failed");
+        for (path, expected) in [("folder/file", &[1u8, 2, 3][..]), ("empty", &[][..])].iter() {
+            let mut buffer = Vec::new();
+            File::load(&file_system, path)
+                .expect("Loading file failed")
+                .read_to_end(&mut buffer)
+                .expect("Reading failed");
+            assert_eq!(&buffer[..], *expected);
+        }
+
+        // Without data, an empty database is used
+        assert!(matches!(
+            FileSystem::from_bytes(Vec::new(), false),
+            Err(FileSystemError::NoFileSystem)
+        ));
+        FileSystem::from_bytes(Vec::new(), true).expect("Creating filesystem failed");
+        assert!(FileSystem::from_bytes(vec![42; 1024], true).is_err());
+    }
+
+    #[test]
+    #[cfg(feature = "serialize")]
+    fn test_serialize() {
+        let tmp_dir = tempfile::TempDir::new().expect("Unable to create temporary directory");
+        let mut file_system = FileSystem::load(
+            Database::open(tmp_dir.path().join("database.sqlite"))
+                .expect("Opening database failed"),
+            true,
+        )
+        .expect("Creating filesystem failed");
+        File::create(&mut file_system, "file", &[1u8, 2, 3][..], 2).expect("File creation failed");
+
+        let data = file_system.serialize().expect("Serialization failed");
+        let mut database = Database::open_in_memory().expect("Open in-memory database failed");
+        super::deserialize(&mut database, &data).expect("Deserialization failed");
+        let file_system = FileSystem::load(database, false).expect("Loading filesystem failed");
+        let mut buffer = Vec::new();
+        File::load(&file_system, "file")
+            .expect("Loading file failed")
+            .read_to_end(&mut buffer)
+            .expect("Reading failed");
+        assert_eq!(buffer, vec![1, 2, 3]);
+    }
+
+    #[test]
+    fn test_file_ref() {
+        let mut file_system = FileSystem::load(
+            Database::open_in_memory().expect("Open in-memory database failed"),
+            true,
+        )
+        .expect("Creating filesystem failed");
+
+        let file_a = File::create(&mut file_system, "a", &[1u8, 2, 3][..], 2)
+            .expect("File creation failed")
+            .file_ref();
+        File::create(&mut file_system, "b", &[4u8, 5][..], 2).expect("File creation failed");
+
+        let mut buffer = Vec::new();
+        let mut file = file_a.open(&file_system);
+        assert_eq!(file.handle(), file_a.handle());
+        assert_eq!(file.len(), file_a.len());
+        file.read_to_end(&mut buffer).expect("Reading failed");
+        assert_eq!(buffer, vec![1, 2, 3]);
+    }
+
+    #[test]
+    fn test_from_parts() {
+        let mut file_system = FileSystem::load(
+            Database::open_in_memory().expect("Open in-memory database failed"),
+            true,
+        )
+        .expect("Creating filesystem failed");
+
+        let data = [1u8, 2, 3, 4, 5];
+        let (handle, size) = {
+            let file =
+                File::create(&mut file_system, "file", &data[..], 2).expect("File creation failed");
+            (file.handle(), file.len())
+        };
+        let link = file_system
+            .create_symlink("link", "file")
+            .expect("Symlink creation failed");
+
+        let mut buffer = Vec::new();
+        File::from_parts(&file_system, handle, size)
+            .expect("Opening file failed")
+            .read_to_end(&mut buffer)
+            .expect("Reading failed");
+        assert_eq!(&buffer, &data);
+
+        let mut buffer = Vec::new();
+        File::from_parts_unchecked(&file_system, handle, size)
+            .read_to_end(&mut buffer)
+            .expect("Reading failed");
+        assert_eq!(&buffer, &data);
+
+        // Only existing files are accepted
+        for handle in [link, Handle(handle.0 + 42)].iter() {
+            assert_eq!(
+                File::from_parts(&file_system, *handle, size).expect_err("Opened invalid handle"),
+                LoadingError::FileNotFound
+            );
+        }
+    }
+
+    /// A sink interrupting the running query on the first write.
+    struct InterruptingSink(Option<InterruptHandle>);
+
+    impl Write for InterruptingSink {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            if let Some(handle) = self.0.take() {
+                handle.interrupt();
+            }
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    /// A sink taking the given time for each write.
+    struct SlowSink(Duration);
+
+    impl Write for SlowSink {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            std::thread::sleep(self.0);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_interrupt() {
+        let mut file_system = FileSystem::load(
+            Database::open_in_memory().expect("Open in-memory database failed"),
+            true,
+        )
+        .expect("Creating filesystem failed");
+        let data: Vec<u8> = (0..10).collect();
+        File::create(&mut file_system, "file", &data[..], 2).expect("File creation failed");
+        let file = File::load(&file_system, "file").expect("Loading file failed");
+
+        let sink = InterruptingSink(Some(file_system.interrupt_handle()));
+        assert_eq!(
+            file.random_read(sink, 0, data.len())
+                .expect_err("Interrupted read succeeded"),
+            ReadError::Interrupted
+        );
+
+        // Subsequent reads are not affected
+        let mut buffer = Vec::new();
+        file.random_read(&mut buffer, 0, data.len())
+            .expect("Reading failed");
+        assert_eq!(buffer, data);
+    }
+
+    #[test]
+    fn test_random_read_timeout() {
+        let mut file_system = FileSystem::load(
+            Database::open_in_memory().expect("Open in-memory database failed"),
+            true,
+        )
+        .expect("Creating filesystem failed");
+        let data: Vec<u8> = (0..10).collect();
+        File::create(&mut file_system, "file", &data[..], 2).expect("File creation failed");
+        let file = File::load(&file_system, "file").expect("Loading file failed");
+
+        let mut buffer = Vec::new();
+        assert_eq!(
+            file.random_read_timeout(&mut buffer, 0, data.len(), Duration::from_secs(60)),
+            Ok(data.len())
+        );
+        assert_eq!(buffer, data);
+
+        // A sink slower than the timeout
+        let sink = SlowSink(Duration::from_millis(50));
+        assert_eq!(
+            file.random_read_timeout(sink, 0, data.len(), Duration::from_millis(10)),
+            Err(ReadError::TimedOut)
+        );
+    }
+
+    #[test]
+    fn test_random_read_timeout_busy() {
+        let tmp_dir = tempfile::TempDir::new().expect("Unable to create temporary directory");
+        let database_path = tmp_dir.path().join("database.sqlite");
+        let mut file_system = FileSystem::load(
+            Database::open(&database_path).expect("Opening database failed"),
+            true,
+        )
+        .expect("Creating filesystem failed");
+        File::create(&mut file_system, "file", &[1u8, 2, 3][..], 2).expect("File creation failed");
+        let file = File::load(&file_system, "file").expect("Loading file failed");
+
+        // Another connection holding an exclusive lock lets reads wait for the busy timeout.
+        let other = Database::open(&database_path).expect("Opening database failed");
+        other
+            .execute_batch("BEGIN EXCLUSIVE")
+            .expect("Locking database failed");
+        assert_eq!(
+            file.random_read_timeout(Vec::new(), 0, 3, Duration::from_millis(10)),
+            Err(ReadError::TimedOut)
+        );
+    }
+
+    #[test]
+    fn test_read_to_end() {
+        let mut file_system = FileSystem::load(
+            Database::open_in_memory().expect("Open in-memory database failed"),
+            true,
+        )
+        .expect("Creating filesystem failed");
+
+        let data: Vec<u8> = (0..100_000u32).map(|value| value as u8).collect();
+        let mut file =
+            File::create(&mut file_system, "file", &data[..], 4096).expect("File creation failed");
+
+        // The content is appended behind existing data
+        let mut buffer = vec![42u8];
+        assert_eq!(
+            file.read_to_end(&mut buffer).expect("Reading failed"),
+            data.len()
+        );
+        assert_eq!(buffer[0], 42);
+        assert_eq!(&buffer[1..], &data[..]);
+        assert_eq!(file.read_to_end(&mut buffer).expect("Reading failed"), 0);
+
+        // Reading continues from the current position
+        let mut file = File::load(&file_system, "file").expect("Loading file failed");
+        let mut buffer = vec![0u8; 5000];
+        file.read_exact(&mut buffer).expect("Reading failed");
+        assert_eq!(
+            file.read_to_end(&mut buffer).expect("Reading failed"),
+            95_000
+        );
+        assert_eq!(buffer, data);
+    }
+
+    #[test]
+    fn test_peek() {
+        let mut file_system = FileSystem::load(
+            Database::open_in_memory().expect("Open in-memory database failed"),
+            true,
+        )
+        .expect("Creating filesystem failed");
+
+        let data = [1u8, 2, 3, 4, 5];
+        let mut file =
+            File::create(&mut file_system, "file", &data[..], 2).expect("File creation failed");
+
+        let mut peek_buffer = vec![0u8; 3];
+        assert_eq!(file.peek(&mut peek_buffer[..]).expect("Successful peek"), 3);
+        assert_eq!(&peek_buffer, &[1u8, 2, 3]);
+
+        // Peeking does not consume the data
+        let mut read_buffer = vec![0u8; 3];
+        assert_eq!(file.read(&mut read_buffer[..]).expect("Successful read"), 3);
+        assert_eq!(&read_buffer, &peek_buffer);
+
+        // Peeking continues from the current position and stops at EOF
+        assert_eq!(file.peek(&mut peek_buffer[..]).expect("Successful peek"), 2);
+        assert_eq!(&peek_buffer[..2], &[4u8, 5]);
+        assert_eq!(
+            file.read_to_end(&mut read_buffer).expect("Successful read"),
+            2
+        );
+        assert_eq!(&read_buffer, &data);
+        assert_eq!(file.peek(&mut peek_buffer[..]).expect("Successful peek"), 0);
+    }
+
+    #[test]
+    fn test_clone() {
+        let mut file_system = FileSystem::load(
+            Database::open_in_memory().expect("Open in-memory database failed"),
+            true,
+        )
+        .expect("Creating filesystem failed");
+
+        let data = [1u8, 2, 3, 4, 5];
+        File::create(&mut file_system, "file", &data[..], 2).expect("File creation failed");
+
+        let mut file = File::load(&file_system, "file").expect("Loading file failed");
+        let mut buffer = vec![0u8; 2];
+        assert_eq!(file.read(&mut buffer[..]).expect("Successful read"), 2);
+
+        // The clone starts at the same position ...
+        let mut cloned_file = file.clone();
+        assert_eq!(cloned_file.handle(), file.handle());
+
+        // ... but advances independently.
+        assert_eq!(file.read(&mut buffer[..]).expect("Successful read"), 2);
+        assert_eq!(&buffer, &[3u8, 4]);
+        assert_eq!(file.read(&mut buffer[..]).expect("Successful read"), 1);
+
+        assert_eq!(
+            cloned_file.read(&mut buffer[..]).expect("Successful read"),
+            2
+        );
+        assert_eq!(&buffer, &[3u8, 4]);
+    }
+
+    #[test]
+    fn test_handle() {
+        let mut file_system = FileSystem::load(
+            Database::open_in_memory().expect("Open in-memory database failed"),
+            true,
+        )
+        .expect("Creating filesystem failed");
+        let data = [1u8, 2, 3];
+
+        let handle = {
+            let file =
+                File::create(&mut file_system, "file", &data[..], 3).expect("File creation failed");
+            assert_eq!(file.len(), data.len());
+            file.handle
+        };
+
+        // Create an invalid handle and check it is not equal to the "real" one
+        let invalid_handle: Handle = 42.into();
+        assert_ne!(handle, invalid_handle);
+
+        // Re-open file from handle
+        {
+            let file: File<_> = (&file_system, handle)
+                .try_into()
+                .expect("Reconstructing file from handle failed");
+            assert_eq!(file.len(), data.len());
+        }
+
+        // Check that invalid handle is correctly identified
+        let invalid_file: Result<File<_>, _> = (&file_system, invalid_handle).try_into();
+        assert_eq!(
+            invalid_file.expect_err("Successful reconstruction of invalid handle"),
+            LoadingError::FileNotFound
+        );
+    }
+
+    #[test]
+    fn test_empty_file() {
+        let mut file_system = FileSystem::load(
+            Database::open_in_memory().expect("Open in-memory database failed"),
+            true,
+        )
+        .expect("Unable to create file system");
+        let data = Vec::new();
+
+        let handle = {
+            let file =
+                File::create(&mut file_system, "abc", &data[..], 3).expect("Unable to create file");
+            assert_eq!(file.len(), 0);
+            assert_eq!(file.is_empty(), true);
+            file.handle()
+        };
+
+        let reopened_file: File<_> = (&file_system, handle)
+            .try_into()
+            .expect("Unable to re-open empty file");
+        assert_eq!(reopened_file.len(), 0);
+    }
+
+    #[test]
+    fn test_delete() {
+        let mut file_system = FileSystem::load(
+            Database::open_in_memory().expect("Open in-memory database failed"),
+            true,
+        )
+        .expect("Creating filesystem failed");
+        let data = [1u8, 2, 3];
+        let path = "abc";
+
+        // Create file
+        File::create(&mut file_system, path, &data[..], 3).expect("File creation failed");
+
+        // Check that the file exists
+        File::create(&mut file_system, path, &data[..], 3)
+            .expect_err("File created despite existent");
+
+        // Delete the file
+        let file = File::load(&mut file_system, path).expect("Existing file not found");
+        assert!(file.delete());
+
+        // Check the file does not longer exists
+        assert_eq!(
+            File::load(&mut file_system, path).expect_err("Delete file still found"),
+            LoadingError::FileNotFound
+        );
+
+        // Check a new file can be created
+        File::create(&mut file_system, path, &data[..], 3).expect("File (re-)creation failed");
+    }
+
+    #[test]
+    fn test_find() {
+        let mut file_system = FileSystem::load(
+            Database::open_in_memory().expect("Open in-memory database failed"),
+            true,
+        )
+        .expect("Creating filesystem failed");
+
+        let paths = [
+            "folder/example_file_1.txt",
+            "folder/example_file_2.txt",
+            "folder/nested_folder1/file1.txt",
+            "folder/nested_folder1/file2.txt",
+            "folder/nested_folder2/file1.txt",
+        ];
+        let data = [1u8, 2, 3];
+        for path in paths.iter() {
+            File::create(&mut file_system, path, &data[..], 42).expect("Creating file failed");
+        }
+
+        // Check non-existing paths
+        assert_eq!(file_system.find("folder").expect("Finding failed").len(), 0);
+
+        // Check existing paths - makes no real sense, but...
+        assert_eq!(file_system.find(paths[0]).expect("Finding failed").len(), 1);
+
+        // Check single char wildcard
+        assert_eq!(
+            file_system
+                .find("folder/example_file_?.txt")
+                .expect("Finding failed")
+                .len(),
+            2
+        );
+
+        // Check multiple char wildcard
+        assert_eq!(
+            file_system
+                .find("folder/example_*.txt")
+                .expect("Finding failed")
+                .len(),
+            2
+        );
+
+        // Check multiple char wildcard in folders
+        assert_eq!(
+            file_system
+                .find("folder/*/*")
+                .expect("Finding failed")
+                .len(),
+            3
+        );
+
+        // Check general wildcard
+        assert_eq!(file_system.find("*").expect("Finding failed").len(), 5);
+    }
+
+    #[test]
+    fn test_roots() {
+        let mut file_system = FileSystem::load(
+            Database::open_in_memory().expect("Open in-memory database failed"),
+            true,
+        )
+        .expect("Creating filesystem failed");
+
+        // An empty file system has no roots
+        assert_eq!(
+            file_system.roots().expect("Querying roots failed"),
+            Vec::<String>::new()
+        );
+
+        let paths = [
+            "folder/example_file_1.txt",
+            "folder/nested_folder/file.txt",
+            "root_file.txt",
+            "other_folder/file.txt",
+        ];
+        let data = [1u8, 2, 3];
+        for path in paths.iter() {
+            File::create(&mut file_system, path, &data[..], 42).expect("Creating file failed");
+        }
+
+        assert_eq!(
+            file_system.roots().expect("Querying roots failed"),
+            vec!["folder", "other_folder", "root_file.txt"]
+        );
+    }
+
+    #[test]
+    fn test_symlink() {
+        let mut file_system = FileSystem::load(
+            Database::open_in_memory().expect("Open in-memory database failed"),
+            true,
+        )
+        .expect("Creating filesystem failed");
+        let data = [1u8, 2, 3];
+
+        let handle = File::create(&mut file_system, "folder/file", &data[..], 2)
+            .expect("File creation failed")
+            .handle();
+        file_system
+            .create_symlink("link", "/folder/./file")
+            .expect("Symlink creation failed");
+        file_system
+            .create_symlink("folder/link_to_link", "link")
+            .expect("Symlink creation failed");
+
+        // Links are resolved, even if chained
+        for path in ["link", "folder/link_to_link"].iter() {
+            let mut file = File::load(&file_system, path).expect("Resolving symlink failed");
+            assert_eq!(file.handle(), handle);
+
+            let mut buffer = Vec::new();
+            assert_eq!(file.read_to_end(&mut buffer).expect("Reading failed"), 3);
+            assert_eq!(&buffer, &data);
+        }
+
+        // Links are no files
+        assert_eq!(file_system.find("*").expect("Finding failed").len(), 1);
+
+        // Paths of links are occupied
+        assert_eq!(
+            file_system
+                .create_symlink("link", "folder")
+                .expect_err("Symlink overwritten"),
+            CreationError::FileExists
+        );
+
+        // Dangling links are not found
+        file_system
+            .create_symlink("dangling", "missing")
+            .expect("Symlink creation failed");
+        assert_eq!(
+            File::load(&file_system, "dangling").expect_err("Dangling symlink resolved"),
+            LoadingError::FileNotFound
+        );
+    }
+
+    #[test]
+    fn test_symlink_cycle() {
+        let mut file_system = FileSystem::load(
+            Database::open_in_memory().expect("Open in-memory database failed"),
+            true,
+        )
+        .expect("Creating filesystem failed");
+
+        file_system
+            .create_symlink("a", "b")
+            .expect("Symlink creation failed");
+        file_system
+            .create_symlink("b", "a")
+            .expect("Symlink creation failed");
+        file_system
+            .create_symlink("self", "self")
+            .expect("Symlink creation failed");
+
+        for path in ["a", "b", "self"].iter() {
+            assert_eq!(
+                File::load(&file_system, path).expect_err("Cyclic symlink resolved"),
+                LoadingError::TooManySymlinks
+            );
+        }
+    }
+
+    #[test_case("uploads", "uploads/sub/file", true; "below")]
+    #[test_case("uploads", "/uploads/./file", true; "normalized")]
+    #[test_case("uploads", "uploads/../secret", false; "traversal")]
+    #[test_case("uploads", "uploads/sub/../../secret", false; "nested traversal")]
+    #[test_case("uploads", "uploads", false; "base itself")]
+    #[test_case("uploads", "uploads_other/file", false; "sibling")]
+    fn test_create_scoped(base: &str, path: &str, is_valid: bool) {
+        let mut file_system = FileSystem::load(
+            Database::open_in_memory().expect("Open in-memory database failed"),
+            true,
+        )
+        .expect("Creating filesystem failed");
+
+        match file_system.create_scoped(base, path, &[1u8, 2, 3][..], 2) {
+            Ok(handle) => {
+                assert!(is_valid);
+                assert_eq!(
+                    File::load(&file_system, path)
+                        .expect("Loading file failed")
+                        .handle(),
+                    handle
+                );
+            }
+            Err(error) => {
+                assert!(!is_valid);
+                assert_eq!(error, CreationError::OutsideScope);
+                assert!(file_system.find("*").expect("Finding failed").is_empty());
+            }
+        }
+    }
+
+    #[test_case(1; "single byte")]
+    #[test_case(3; "smaller than chunk")]
+    #[test_case(1048576; "default")]
+    fn test_read_buffer_limit(limit: usize) {
+        let mut file_system = FileSystem::load(
+            Database::open_in_memory().expect("Open in-memory database failed"),
+            true,
+        )
+        .expect("Creating filesystem failed");
+        file_system.set_read_buffer_limit(limit);
+        let data: Vec<u8> = (0..100).collect();
+        File::create(&mut file_system, "small", &data[..], 8).expect("File creation failed");
+        File::create(&mut file_system, "huge", &data[..], 33554432).expect("File creation failed");
+
+        for path in ["small", "huge"].iter() {
+            let file = File::load(&file_system, path).expect("Loading file failed");
+            for (start, length) in [(0, 100), (0, 16), (5, 2), (7, 30), (99, 1)].iter() {
+                let mut buffer = Vec::new();
+                assert_eq!(
+                    file.random_read(&mut buffer, *start, *length)
+                        .expect("Reading failed"),
+                    *length
+                );
+                assert_eq!(&buffer[..], &data[*start..*start + *length]);
+            }
+        }
+    }
+
+    #[test_case(0, None, vec!["empty", "large", "small"]; "all")]
+    #[test_case(1, None, vec!["large", "small"]; "non-empty")]
+    #[test_case(10, None, vec!["large"]; "min threshold")]
+    #[test_case(0, Some(9), vec!["empty", "small"]; "upper bound")]
+    #[test_case(3, Some(3), vec!["small"]; "exact")]
+    #[test_case(11, None, vec![]; "none")]
+    fn test_find_by_size(min: u64, max: Option<u64>, expected: Vec<&str>) {
+        let mut file_system = FileSystem::load(
+            Database::open_in_memory().expect("Open in-memory database failed"),
+            true,
+        )
+        .expect("Creating filesystem failed");
+        File::create(&mut file_system, "empty", &[][..], 4).expect("File creation failed");
+        File::create(&mut file_system, "small", &[1u8; 3][..], 4).expect("File creation failed");
+        File::create(&mut file_system, "large", &[1u8; 10][..], 4).expect("File creation failed");
+        file_system
+            .create_symlink("link", "large")
+            .expect("Symlink creation failed");
+
+        assert_eq!(
+            file_system
+                .find_by_size(min, max)
+                .expect("Finding files failed"),
+            expected
+        );
+    }
+
+    #[test]
+    fn test_open_exclusive() {
+        let mut file_system = FileSystem::load(
+            Database::open_in_memory().expect("Open in-memory database failed"),
+            true,
+        )
+        .expect("Creating filesystem failed");
+        File::create(&mut file_system, "file", &[0u8; 6][..], 4).expect("File creation failed");
+        File::create_compressed(&mut file_system, "source", &[1u8, 2][..], 4)
+            .expect("File creation failed");
+
+        {
+            let mut file = File::open_exclusive(&file_system, "file").expect("Opening file failed");
+            assert_eq!(
+                File::open_exclusive(&file_system, "file").expect_err("File was locked twice"),
+                LoadingError::FileLocked
+            );
+            file.write_at(3, &[1, 2, 3]).expect("Writing failed");
+            assert_eq!(
+                file.write_at(4, &[1, 2, 3]),
+                Err(ReadError::OutOfBounds.into())
+            );
+
+            let mut buffer = Vec::new();
+            file.read_to_end(&mut buffer).expect("Reading failed");
+            assert_eq!(buffer, vec![0, 0, 0, 1, 2, 3]);
+        }
+
+        // Other writers respect the lock, even if it outlives its guard
+        let file = File::open_exclusive(&file_system, "file").expect("Opening file failed");
+        std::mem::forget(file);
+        assert_eq!(
+            file_system.copy_range("source", 0, 2, "file", 0),
+            Err(LoadingError::FileLocked.into())
+        );
+
+        // Locked files are still readable
+        let mut buffer = Vec::new();
+        File::load(&file_system, "file")
+            .expect("Loading file failed")
+            .read_to_end(&mut buffer)
+            .expect("Reading failed");
+        assert_eq!(buffer, vec![0, 0, 0, 1, 2, 3]);
+    }
+
+    #[test]
+    fn test_lock_enforcement() {
+        let mut file_system = FileSystem::load(
+            Database::open_in_memory().expect("Open in-memory database failed"),
+            true,
+        )
+        .expect("Creating filesystem failed");
+        let mut destination = FileSystem::load(
+            Database::open_in_memory().expect("Open in-memory database failed"),
+            true,
+        )
+        .expect("Creating filesystem failed");
+        File::create(&mut file_system, "locked", &[1u8, 2, 3][..], 2)
+            .expect("File creation failed");
+        File::create(&mut file_system, "other", &[4u8][..], 2).expect("File creation failed");
+
+        // The lock is kept without its guard, i.e. after a crash
+        let handle = {
+            let mut file =
+                File::open_exclusive(&file_system, "locked").expect("Opening file failed");
+            file.append(&[4, 5]).expect("Appending to own lock failed");
+            file.compact().expect("Compacting own lock failed");
+            let handle = file.handle();
+            std::mem::forget(file);
+            handle
+        };
+
+        let locked = || File::load(&file_system, "locked").expect("Loading file failed");
+        assert_eq!(
+            file_system.delete_handle(handle),
+            Err(LoadingError::FileLocked)
+        );
+        assert!(!locked().delete());
+        assert_eq!(locked().compact(), Err(LoadingError::FileLocked.into()));
+        assert_eq!(
+            file_system.rename("locked", "moved"),
+            Err(LoadingError::FileLocked.into())
+        );
+        assert_eq!(
+            file_system.rename_replace("locked", "other"),
+            Err(LoadingError::FileLocked.into())
+        );
+        assert_eq!(
+            file_system.rename_replace("other", "locked"),
+            Err(LoadingError::FileLocked.into())
+        );
+        assert_eq!(
+            file_system.transfer_to(&mut destination, "locked"),
+            Err(LoadingError::FileLocked.into())
+        );
+        assert_eq!(destination.find("*"), Ok(Vec::new()));
+        {
+            let mut batch = file_system.begin_batch().expect("Starting batch failed");
+            assert_eq!(batch.delete("locked"), Err(LoadingError::FileLocked));
+            batch.commit().expect("Committing batch failed");
+        }
+        assert_eq!(
+            file_system.find("*").expect("Finding files failed").len(),
+            2
+        );
+        let mut buffer = Vec::new();
+        File::load(&file_system, "locked")
+            .expect("Loading file failed")
+            .read_to_end(&mut buffer)
+            .expect("Reading failed");
+        assert_eq!(buffer, vec![1, 2, 3, 4, 5]);
+
+        // Stale locks are released explicitly
+        assert_eq!(file_system.force_unlock("locked"), Ok(true));
+        assert_eq!(file_system.force_unlock("locked"), Ok(false));
+        assert_eq!(
+            file_system.force_unlock("missing"),
+            Err(LoadingError::FileNotFound)
+        );
+        assert_eq!(file_system.rename("locked", "moved"), Ok(handle));
+        assert!(File::open_exclusive(&file_system, "moved").is_ok());
+        assert_eq!(file_system.delete_handle(handle), Ok(true));
+    }
+
+    #[test]
+    fn test_entries_by_insertion() {
+        let mut file_system = FileSystem::load(
+            Database::open_in_memory().expect("Open in-memory database failed"),
+            true,
+        )
+        .expect("Creating filesystem failed");
+        for path in ["c", "a", "b/d"].iter() {
+            File::create(&mut file_system, path, &[1u8][..], 4).expect("File creation failed");
+        }
+        file_system
+            .create_symlink("link", "a")
+            .expect("Symlink creation failed");
+        file_system
+            .rename_replace("c", "e")
+            .expect("Moving file failed");
+
+        assert_eq!(
+            file_system
+                .entries_by_insertion()
+                .expect("Listing files failed"),
+            vec!["e", "a", "b/d"]
+        );
+    }
+
+    #[test]
+    fn test_stored_size() {
+        let mut file_system = FileSystem::load(
+            Database::open_in_memory().expect("Open in-memory database failed"),
+            true,
+        )
+        .expect("Creating filesystem failed");
+        let data = [42u8; 1000];
+
+        let file =
+            File::create(&mut file_system, "plain", &data[..], 64).expect("File creation failed");
+        assert_eq!(file.stored_size(), Ok(file.len() as u64));
+
+        let file = File::create_compressed(&mut file_system, "compressed", &data[..], 64)
+            .expect("File creation failed");
+        assert_eq!(file.len(), data.len());
+        assert!(file.stored_size().expect("Querying size failed") < file.len() as u64);
+    }
+
+    #[test_case(0, 4; "empty")]
+    #[test_case(8, 4; "full chunks")]
+    #[test_case(10, 4; "short last chunk")]
+    fn test_create_empty(size: usize, chunk_size: usize) {
+        let mut file_system = FileSystem::load(
+            Database::open_in_memory().expect("Open in-memory database failed"),
+            true,
+        )
+        .expect("Creating filesystem failed");
+        let file = file_system
+            .create_empty("file", size, chunk_size)
+            .expect("File creation failed");
+        assert_eq!(file.len(), size);
+
+        let mut expected = vec![0u8; size];
+        if size > 0 {
+            let file = File::open_exclusive(&file_system, "file").expect("Opening file failed");
+            let data = [1u8, 2, 3];
+            let index = (size - data.len()) / 2;
+            file.write_at(index, &data).expect("Writing failed");
+            expected[index..index + data.len()].copy_from_slice(&data);
+        }
+
+        let mut buffer = Vec::new();
+        File::load(&file_system, "file")
+            .expect("Loading file failed")
+            .read_to_end(&mut buffer)
+            .expect("Reading failed");
+        assert_eq!(buffer, expected);
+    }
+
+    #[test]
+    fn test_largest_file() {
+        let mut file_system = FileSystem::load(
+            Database::open_in_memory().expect("Open in-memory database failed"),
+            true,
+        )
+        .expect("Creating filesystem failed");
+        assert_eq!(file_system.largest_file(), Ok(None));
+
+        File::create(&mut file_system, "empty", &[][..], 4).expect("File creation failed");
+        assert_eq!(
+            file_system.largest_file(),
+            Ok(Some((String::from("empty"), 0)))
+        );
+
+        File::create(&mut file_system, "small", &[1u8; 3][..], 4).expect("File creation failed");
+        File::create(&mut file_system, "large", &[1u8; 10][..], 4).expect("File creation failed");
+        File::create(&mut file_system, "medium", &[1u8; 5][..], 4).expect("File creation failed");
+        assert_eq!(
+            file_system.largest_file(),
+            Ok(Some((String::from("large"), 10)))
+        );
+    }
+
+    #[test_case(0, 4)]
+    #[test_case(8, 4)]
+    #[test_case(10, 4)]
+    fn test_layout(size: usize, chunk_size: usize) {
+        let mut file_system = FileSystem::load(
+            Database::open_in_memory().expect("Open in-memory database failed"),
+            true,
+        )
+        .expect("Creating filesystem failed");
+        let file = File::create_empty(&mut file_system, "file", size, chunk_size)
+            .expect("File creation failed");
+
+        let layout = file.layout().expect("Querying layout failed");
+        assert_eq!(layout.len(), size / chunk_size + 1);
+        for (index, chunk) in layout.iter().enumerate() {
+            assert_eq!(chunk.chunk_num(), index as u32);
+        }
+        assert_eq!(
+            layout.iter().map(|chunk| chunk.length()).sum::<u64>(),
+            size as u64
+        );
+        assert!(layout
+            .iter()
+            .all(|chunk| chunk.length() <= chunk_size as u64));
+    }
+
+    #[test_case(true; "commit")]
+    #[test_case(false; "rollback")]
+    fn test_batch(commit: bool) {
+        let tmp_dir = tempfile::TempDir::new().expect("Unable to create temporary directory");
+        let database_path = tmp_dir.path().join("database.sqlite");
+        let mut file_system = FileSystem::load(
+            Database::open(&database_path).expect("Opening database failed"),
+            true,
+        )
+        .expect("Creating filesystem failed");
+        File::create(&mut file_system, "old", &[1u8][..], 4).expect("File creation failed");
+        let reader = FileSystem::load(
+            Database::open(&database_path).expect("Opening database failed"),
+            false,
+        )
+        .expect("Loading filesystem failed");
+        let list = |file_system: &FileSystem<Database>| {
+            let mut paths = file_system.find("*").expect("Finding files failed");
+            paths.sort();
+            paths
+        };
+
+        let mut batch = file_system.begin_batch().expect("Starting batch failed");
+        batch
+            .create("a", &[1u8, 2, 3][..], 2)
+            .expect("File creation failed");
+        batch
+            .create("b/c", &[4u8][..], 2)
+            .expect("File creation failed");
+        assert_eq!(
+            batch.create("a", &[5u8][..], 2),
+            Err(CreationError::FileExists)
+        );
+        assert_eq!(batch.delete("old"), Ok(true));
+        assert_eq!(batch.delete("missing"), Ok(false));
+
+        // Changes are invisible to other connections until the batch is committed
+        assert_eq!(list(&reader), vec!["old"]);
+        match commit {
+            true => batch.commit().expect("Committing failed"),
+            false => batch.rollback().expect("Rolling back failed"),
+        }
+        match commit {
+            true => assert_eq!(list(&reader), vec!["a", "b/c"]),
+            false => assert_eq!(list(&reader), vec!["old"]),
+        }
+        assert_eq!(list(&reader), list(&file_system));
+    }
+
+    #[test]
+    fn test_merkle_root() {
+        let files: [(&str, &[u8]); 3] = [("a", &[1, 2, 3]), ("b/c", &[4; 10]), ("d", &[])];
+        let create = |files: &mut dyn Iterator<Item = &(&str, &[u8])>| {
+            let mut file_system = FileSystem::load(
+                Database::open_in_memory().expect("Open in-memory database failed"),
+                true,
+            )
+            .expect("Creating filesystem failed");
+            for (path, data) in files {
+                File::create(&mut file_system, path, *data, 4).expect("File creation failed");
+            }
+            file_system
+        };
+
+        let file_system = create(&mut files.iter());
+        let reversed = create(&mut files.iter().rev());
+        assert_eq!(
+            file_system.merkle_root().expect("Hashing failed"),
+            reversed.merkle_root().expect("Hashing failed")
+        );
+
+        let hashes = file_system.file_hashes().expect("Hashing failed");
+        assert_eq!(hashes.keys().collect::<Vec<_>>(), vec!["a", "b/c", "d"]);
+        assert_eq!(hashes["d"], <[u8; 32]>::from(Sha256::digest(&[])));
+
+        let different = create(&mut files[..2].iter());
+        assert_ne!(
+            file_system.merkle_root().expect("Hashing failed"),
+            different.merkle_root().expect("Hashing failed")
+        );
+    }
+
+    #[test_case(5, false; "uncompressed")]
+    #[test_case(5, true; "compressed")]
+    #[test_case(1, false; "chunk size 1")]
+    fn test_append(chunk_size: usize, compressed: bool) {
+        let mut file_system = FileSystem::load(
+            Database::open_in_memory().expect("Open in-memory database failed"),
+            true,
+        )
+        .expect("Creating filesystem failed");
+        let initial = [1u8, 2, 3];
+        match compressed {
+            true => File::create_compressed(&mut file_system, "file", &initial[..], chunk_size),
+            false => File::create(&mut file_system, "file", &initial[..], chunk_size),
+        }
+        .expect("File creation failed");
+
+        let mut expected = initial.to_vec();
+        let mut file = File::load(&file_system, "file").expect("Loading file failed");
+        for data in [&[][..], &[4u8, 5][..], &[6u8; 12][..]].iter() {
+            file.append(data).expect("Appending failed");
+            expected.extend_from_slice(data);
+            assert_eq!(file.len(), expected.len());
+        }
+
+        let file = File::load(&file_system, "file").expect("Loading file failed");
+        assert_eq!(file.len(), expected.len());
+        let mut content = Vec::new();
+        file.random_read(&mut content, 0, file.len())
+            .expect("Reading failed");
+        assert_eq!(content, expected);
+
+        // All but the last chunk use the original chunk size
+        let lengths: Vec<usize> = file
+            .chunks()
+            .map(|chunk| chunk.expect("Reading chunk failed").len())
+            .collect();
+        let (last, full) = lengths.split_last().expect("File without chunks");
+        assert!(full.iter().all(|length| *length == chunk_size));
+        assert!(*last <= chunk_size);
+
+        // Like for newly created files, the stored chunks end with a partial one
+        if !compressed {
+            let layout = file.layout().expect("Reading layout failed");
+            assert_eq!(
+                layout.last().map(|chunk| chunk.length()),
+                Some((expected.len() % chunk_size) as u64)
+            );
+        }
+    }
+
+    #[test]
+    fn test_append_missing_or_locked() {
+        let mut file_system = FileSystem::load(
+            Database::open_in_memory().expect("Open in-memory database failed"),
+            true,
+        )
+        .expect("Creating filesystem failed");
+        File::create(&mut file_system, "file", &[1u8, 2][..], 4).expect("File creation failed");
+
+        // The lock of an exclusive file only keeps others from appending
+        {
+            let mut file = File::open_exclusive(&file_system, "file").expect("Opening file failed");
+            let mut other = File::load(&file_system, "file").expect("Loading file failed");
+            assert_eq!(other.append(&[0]), Err(LoadingError::FileLocked.into()));
+            file.append(&[3, 4, 5]).expect("Appending failed");
+            assert_eq!(file.len(), 5);
+        }
+        let mut file = File::load(&file_system, "file").expect("Loading file failed");
+        assert_eq!(
+            file.read_to_string(),
+            Ok(String::from("\u{1}\u{2}\u{3}\u{4}\u{5}"))
+        );
+
+        // Deleted files are reported as missing
+        assert!(File::load(&file_system, "file")
+            .expect("Loading file failed")
+            .delete());
+        assert_eq!(file.append(&[6]), Err(LoadingError::FileNotFound.into()));
+    }
+
+    #[test]
+    fn test_unexpected_types() {
+        let mut file_system = FileSystem::load(
+            Database::open_in_memory().expect("Open in-memory database failed"),
+            true,
+        )
+        .expect("Creating filesystem failed");
+        let file = File::create(&mut file_system, "file", &[1u8, 2, 3][..], 2)
+            .expect("File creation failed")
+            .file_ref();
+        let corrupt = |file_system: &FileSystem<Database>, sql: &str| {
+            file_system
+                .connection()
+                .execute(sql, [])
+                .expect("Corrupting database failed");
+        };
+
+        // Reading
+        corrupt(&file_system, "UPDATE Matryoshka_Meta_0 SET flags = 'text'");
+        assert!(matches!(
+            file.open(&file_system).random_read(&mut Vec::new(), 0, 3),
+            Err(ReadError::DatabaseError(_))
+        ));
+
+        // Size
+        assert!(matches!(
+            File::load(&file_system, "file"),
+            Err(LoadingError::DatabaseError(_))
+        ));
+
+        // Opening
+        corrupt(
+            &file_system,
+            "UPDATE Matryoshka_Meta_0 SET flags = 0, type = 'text'",
+        );
+        assert!(matches!(
+            File::load(&file_system, "file"),
+            Err(LoadingError::DatabaseError(_))
+        ));
+
+        // Finding
+        corrupt(
+            &file_system,
+            "UPDATE Matryoshka_Meta_0 SET type = 1, path = CAST(path AS BLOB)",
+        );
+        assert!(file_system.find("*").is_err());
+        assert!(file_system.find_any(&["*", "file"]).is_err());
+        assert!(file_system.roots().is_err());
+        assert!(file_system.modified_since(SystemTime::UNIX_EPOCH).is_err());
+    }
+
+    #[test]
+    fn test_load_ci() {
+        let mut file_system = FileSystem::load(
+            Database::open_in_memory().expect("Open in-memory database failed"),
+            true,
+        )
+        .expect("Creating filesystem failed");
+        let readme = File::create(&mut file_system, "Readme.txt", &[1u8][..], 1)
+            .expect("File creation failed")
+            .handle();
+        let upper = File::create(&mut file_system, "folder/FILE", &[2u8][..], 1)
+            .expect("File creation failed")
+            .handle();
+        let lower = File::create(&mut file_system, "folder/file", &[3u8][..], 1)
+            .expect("File creation failed")
+            .handle();
+        file_system
+            .create_symlink("Link", "Readme.txt")
+            .expect("Creating symlink failed");
+        let load = |path: &str| File::load_ci(&file_system, path).map(|file| file.handle());
+
+        assert_eq!(load("readme.TXT"), Ok(readme));
+        assert_eq!(load("link"), Ok(readme));
+        assert_eq!(load("missing"), Err(LoadingError::FileNotFound));
+
+        // Exact matches are preferred, other ones are ambiguous
+        assert_eq!(load("folder/FILE"), Ok(upper));
+        assert_eq!(load("folder/file"), Ok(lower));
+        assert_eq!(load("folder/File"), Err(LoadingError::AmbiguousPath));
+    }
+
+    #[test]
+    fn test_path_collation() {
+        let tmp_dir = tempfile::TempDir::new().expect("Unable to create temporary directory");
+        let database_path = tmp_dir.path().join("database.sqlite");
+        let mut file_system = FileSystem::load_with_collation(
+            Database::open(&database_path).expect("Opening database failed"),
+            true,
+            PathCollation::NoCase,
+        )
+        .expect("Creating filesystem failed");
+        assert_eq!(file_system.path_collation(), PathCollation::NoCase);
+
+        let handle = File::create(&mut file_system, "File.txt", &[1u8][..], 1)
+            .expect("File creation failed")
+            .handle();
+        assert_eq!(
+            File::create(&mut file_system, "file.txt", &[2u8][..], 1).map(|file| file.handle()),
+            Err(CreationError::FileExists)
+        );
+        assert_eq!(
+            File::load(&file_system, "FILE.TXT").map(|file| file.handle()),
+            Ok(handle)
+        );
+
+        // Renaming to a path only differing in case changes it
+        assert_eq!(
+            file_system.rename_replace("file.txt", "FILE.txt"),
+            Ok(handle)
+        );
+        assert_eq!(
+            file_system.find("*").expect("Finding files failed"),
+            vec![String::from("FILE.txt")]
+        );
+        assert_eq!(
+            file_system.rename_replace("FILE.txt", "FILE.txt"),
+            Ok(handle)
+        );
+
+        // The collation is kept when loading the file system again
+        drop(file_system);
+        let file_system = FileSystem::load(
+            Database::open(&database_path).expect("Opening database failed"),
+            false,
+        )
+        .expect("Loading filesystem failed");
+        assert_eq!(file_system.path_collation(), PathCollation::NoCase);
+        assert_eq!(
+            File::load(&file_system, "file.TXT").map(|file| file.handle()),
+            Ok(handle)
+        );
+
+        let file_system = FileSystem::load(
+            Database::open_in_memory().expect("Open in-memory database failed"),
+            true,
+        )
+        .expect("Creating filesystem failed");
+        assert_eq!(file_system.path_collation(), PathCollation::Binary);
+    }
+
+    #[test_case(10, 10; "correct total")]
+    #[test_case(10, 4; "shorter data")]
+    #[test_case(10, 16; "longer data")]
+    fn test_create_sized(total_len: u64, actual_len: usize) {
+        let mut file_system = FileSystem::load(
+            Database::open_in_memory().expect("Open in-memory database failed"),
+            true,
+        )
+        .expect("Creating filesystem failed");
+        let data: Vec<u8> = (0..actual_len as u8).collect();
+
+        let mut reports = Vec::new();
+        let file = File::create_sized(
+            &mut file_system,
+            "file",
+            &data[..],
+            total_len,
+            3,
+            |done, total| reports.push((done, total)),
+        )
+        .expect("File creation failed");
+        assert_eq!(file.len(), actual_len);
+        let mut content = Vec::new();
+        file.random_read(&mut content, 0, file.len())
+            .expect("Reading failed");
+        assert_eq!(content, data);
+
+        // Progress only increases, never exceeds the total, and ends with completion
+        assert!(reports.windows(2).all(|pair| pair[0].0 <= pair[1].0));
+        assert!(reports.iter().all(|(done, total)| done <= total));
+        assert_eq!(
+            reports.last(),
+            Some(&(actual_len as u64, actual_len as u64))
+        );
+        if actual_len as u64 <= total_len {
+            assert!(reports[..reports.len() - 1]
+                .iter()
+                .all(|(_, total)| *total == total_len));
+        }
+    }
+
+    #[test]
+    fn test_register_scalar_function() {
+        let mut file_system = FileSystem::load(
+            Database::open_in_memory().expect("Open in-memory database failed"),
+            true,
+        )
+        .expect("Creating filesystem failed");
+        for path in ["a/image.png", "b/text.txt", "c/photo.png"].iter() {
+            File::create(&mut file_system, path, &[42u8][..], 1).expect("File creation failed");
+        }
+
+        file_system
+            .register_scalar_function("file_ext", 1, |context| {
+                let path: String = context.get(0)?;
+                Ok(path
+                    .rsplit_once('.')
+                    .map(|(_, extension)| extension.to_owned()))
+            })
+            .expect("Registering function failed");
+
+        let paths: Vec<String> = file_system
+            .connection()
+            .prepare(
+                "SELECT path FROM Matryoshka_Meta_0 WHERE file_ext(path) = 'png' ORDER BY path",
+            )
+            .expect("Preparing query failed")
+            .query_map([], |row| row.get(0))
+            .expect("Querying failed")
+            .collect::<Result<_, _>>()
+            .expect("Reading rows failed");
+        assert_eq!(paths, vec!["a/image.png", "c/photo.png"]);
+
+        // Invalid names are reported instead of panicking
+        assert!(file_system
+            .register_scalar_function("bad\0name", 1, |_| Ok(0))
+            .is_err());
+    }
+
+    #[test]
+    fn test_estimate_compression() {
+        let mut file_system = FileSystem::load(
+            Database::open_in_memory().expect("Open in-memory database failed"),
+            true,
+        )
+        .expect("Creating filesystem failed");
+        assert_eq!(file_system.estimate_compression(10), Ok(1.0));
+
+        File::create(&mut file_system, "a", &[42u8; 4096][..], 1024).expect("File creation failed");
+        File::create(&mut file_system, "b", &[7u8; 4096][..], 1024).expect("File creation failed");
+        File::create_compressed(&mut file_system, "c", &[1u8; 4096][..], 1024)
+            .expect("File creation failed");
+        let stored_size = file_system
+            .storage_stats()
+            .expect("Querying statistics failed");
+
+        let ratio = file_system
+            .estimate_compression(10)
+            .expect("Estimating compression failed");
+        assert!(ratio > 0.0 && ratio < 0.1, "Unexpected ratio {}", ratio);
+        assert_eq!(file_system.estimate_compression(0), Ok(1.0));
+
+        // The database is not modified
+        assert_eq!(
+            file_system
+                .storage_stats()
+                .expect("Querying statistics failed"),
+            stored_size
+        );
+    }
+
+    #[test_case(false; "uncompressed")]
+    #[test_case(true; "compressed")]
+    fn test_for_each_chunk(compressed: bool) {
+        let mut file_system = FileSystem::load(
+            Database::open_in_memory().expect("Open in-memory database failed"),
+            true,
+        )
+        .expect("Creating filesystem failed");
+        let data: Vec<u8> = (0..20).collect();
+        let file = match compressed {
+            true => File::create_compressed(&mut file_system, "file", &data[..], 5),
+            false => File::create(&mut file_system, "file", &data[..], 5),
+        }
+        .expect("File creation failed");
+
+        let mut content = Vec::new();
+        assert_eq!(
+            file.for_each_chunk(|chunk| -> Result<(), ()> {
+                content.extend_from_slice(chunk);
+                Ok(())
+            }),
+            Ok(())
+        );
+        assert_eq!(content.len(), file.len());
+        assert_eq!(content, data);
+
+        // Errors stop the processing
+        let mut num_calls = 0;
+        assert_eq!(
+            file.for_each_chunk(|_| {
+                num_calls += 1;
+                match num_calls {
+                    2 => Err("Stop"),
+                    _ => Ok(()),
+                }
+            }),
+            Err(ProcessError::Callback("Stop"))
+        );
+        assert_eq!(num_calls, 2);
+    }
+
+    #[test_case("file", Ok(()); "valid")]
+    #[test_case("/folder/./file/", Ok(()); "normalized")]
+    #[test_case("", Err(PathError::Empty); "empty")]
+    #[test_case("folder/..", Err(PathError::Empty); "empty after normalization")]
+    #[test_case("fi\0le", Err(PathError::ForbiddenCharacter('\0')); "nul")]
+    #[test_case("fi\nle", Err(PathError::ForbiddenCharacter('\n')); "newline")]
+    fn test_validate_path(path: &str, expected: Result<(), PathError>) {
+        let mut file_system = FileSystem::load(
+            Database::open_in_memory().expect("Open in-memory database failed"),
+            true,
+        )
+        .expect("Creating filesystem failed");
+
+        assert_eq!(
+            file_system.validate_path(path).map(|_| ()),
+            expected,
+            "Validation differs"
+        );
+        assert_eq!(file_system.is_valid_path(path), expected.is_ok());
+        assert_eq!(
+            File::create(&mut file_system, path, &b"42"[..], 1).map(|_| ()),
+            expected.map_err(CreationError::InvalidPath),
+            "Creation differs"
+        );
+    }
+
+    #[test]
+    fn test_validate_path_length() {
+        let mut file_system = FileSystem::load(
+            Database::open_in_memory().expect("Open in-memory database failed"),
+            true,
+        )
+        .expect("Creating filesystem failed");
+        file_system
+            .connection()
+            .set_limit(Limit::SQLITE_LIMIT_LENGTH, 64);
+
+        assert_eq!(
+            file_system
+                .validate_path(format!("folder/{}", "a".repeat(57)))
+                .map(|path| path.as_ref().to_string()),
+            Ok(format!("folder/{}", "a".repeat(57)))
+        );
+        assert_eq!(
+            file_system.validate_path("a".repeat(65)),
+            Err(PathError::TooLong(64))
+        );
+        assert!(matches!(
+            File::create(&mut file_system, "a".repeat(65), &b"42"[..], 1),
+            Err(CreationError::InvalidPath(PathError::TooLong(64)))
+        ));
+    }
+
+    #[test]
+    fn test_load_keeping_statement_cache() {
+        // Count the statements prepared on the connection, which includes those cached.
+        fn num_statements(database: &Database) -> usize {
+            let mut count = 0;
+            unsafe {
+                let handle = database.handle();
+                let mut statement = rusqlite::ffi::sqlite3_next_stmt(handle, std::ptr::null_mut());
+                while !statement.is_null() {
+                    count += 1;
+                    statement = rusqlite::ffi::sqlite3_next_stmt(handle, statement);
+                }
+            }
+            count
+        }
+
+        let database = Database::open_in_memory().expect("Open in-memory database failed");
+        database.set_prepared_statement_cache_capacity(128);
+        for index in 0..64 {
+            database
+                .prepare_cached(&format!("SELECT {}", index))
+                .expect("Preparing statement failed");
+        }
+
+        let file_system = FileSystem::load_keeping_statement_cache(database, true)
+            .expect("Creating filesystem failed");
+        assert_eq!(
+            num_statements(file_system.connection()),
+            64 + super::constants::PRECOMPILED_COMMANDS.len()
+        );
+    }
+
+    #[test]
+    fn test_rewind() {
+        let mut file_system = FileSystem::load(
+            Database::open_in_memory().expect("Open in-memory database failed"),
+            true,
+        )
+        .expect("Creating filesystem failed");
+        let data: Vec<u8> = (0..20).collect();
+        let mut file =
+            File::create(&mut file_system, "file", &data[..], 3).expect("File creation failed");
+
+        let mut first = Vec::new();
+        file.read_to_end(&mut first).expect("Reading failed");
+        assert_eq!(file.position(), data.len());
+        file.rewind();
+        assert_eq!(file.position(), 0);
+        let mut second = Vec::new();
+        file.read_to_end(&mut second).expect("Reading failed");
+        assert_eq!(first, second);
+        assert_eq!(second, data);
+
+        assert_eq!(file.set_position(7), Ok(()));
+        let mut buffer = [0u8; 4];
+        file.read_exact(&mut buffer).expect("Reading failed");
+        assert_eq!(buffer, [7, 8, 9, 10]);
+        assert_eq!(file.set_position(data.len()), Ok(()));
+        assert_eq!(file.read(&mut buffer).expect("Reading failed"), 0);
+        assert_eq!(
+            file.set_position(data.len() + 1),
+            Err(ReadError::OutOfBounds)
+        );
+        assert_eq!(file.position(), data.len());
+    }
+
+    #[test]
+    fn test_tree() {
+        let mut file_system = FileSystem::load(
+            Database::open_in_memory().expect("Open in-memory database failed"),
+            true,
+        )
+        .expect("Creating filesystem failed");
+        assert!(file_system.tree().expect("Building tree failed").is_empty());
+
+        for path in &[
+            "b",
+            "a",
+            "folder/y",
+            "folder/sub/x",
+            "folder/sub/z",
+            "other/deep/w",
+        ] {
+            File::create(&mut file_system, path, &b"42"[..], 1).expect("File creation failed");
+        }
+        let tree = file_system.tree().expect("Building tree failed");
+        assert_eq!(
+            tree.keys().map(String::as_str).collect::<Vec<_>>(),
+            vec!["", "folder", "folder/sub", "other/deep"]
+        );
+        assert_eq!(tree[""], vec!["a", "b"]);
+        assert_eq!(tree["folder"], vec!["y"]);
+        assert_eq!(tree["folder/sub"], vec!["x", "z"]);
+        assert_eq!(tree["other/deep"], vec!["w"]);
+    }
+
+    #[test]
+    fn test_find_iter() {
+        let mut file_system = FileSystem::load(
+            Database::open_in_memory().expect("Open in-memory database failed"),
+            true,
+        )
+        .expect("Creating filesystem failed");
+        for path in &["folder/c", "folder/a", "other", "folder/b"] {
+            File::create(&mut file_system, path, path.as_bytes(), 3).expect("File creation failed");
+        }
+
+        // The file system is read while iterating
+        let mut paths = Vec::new();
+        for path in file_system.find_iter("folder/*").expect("Finding failed") {
+            let path = path.expect("Iteration failed");
+            let mut file = File::load(&file_system, &path).expect("Loading file failed");
+            let mut content = Vec::new();
+            file.read_to_end(&mut content).expect("Reading failed");
+            assert_eq!(content, path.as_bytes());
+            paths.push(path);
+        }
+        assert_eq!(paths, vec!["folder/a", "folder/b", "folder/c"]);
+        assert_eq!(
+            file_system
+                .find_iter("missing/*")
+                .expect("Finding failed")
+                .count(),
+            0
+        );
+    }
+
+    #[test_case("folder/file", PathKind::File; "file")]
+    #[test_case("/folder/file/", PathKind::File; "normalized file")]
+    #[test_case("folder", PathKind::Directory; "directory")]
+    #[test_case("folder/sub", PathKind::Directory; "nested directory")]
+    #[test_case("", PathKind::Directory; "root")]
+    #[test_case("fold", PathKind::Missing; "partial name")]
+    #[test_case("folder/fi", PathKind::Missing; "partial file name")]
+    #[test_case("missing", PathKind::Missing; "missing")]
+    fn test_path_kind(path: &str, expected: PathKind) {
+        let mut file_system = FileSystem::load(
+            Database::open_in_memory().expect("Open in-memory database failed"),
+            true,
+        )
+        .expect("Creating filesystem failed");
+        for path in &["folder/file", "folder/sub/file", "folder.txt"] {
+            File::create(&mut file_system, path, &b"42"[..], 1).expect("File creation failed");
+        }
+
+        assert_eq!(file_system.path_kind(path), Ok(expected));
+    }
+
+    #[test]
+    fn test_separator() {
+        let mut file_system = FileSystem::load(
+            Database::open_in_memory().expect("Open in-memory database failed"),
+            true,
+        )
+        .expect("Creating filesystem failed");
+        for path in &["a:b:c", "a:b:d", "a:e", "f", "g/h:i"] {
+            File::create(&mut file_system, path, &b"42"[..], 1).expect("File creation failed");
+        }
+
+        // By default, only '/' separates directories
+        assert_eq!(file_system.separator(), '/');
+        assert_eq!(
+            file_system.roots().expect("Querying roots failed"),
+            vec!["a:b:c", "a:b:d", "a:e", "f", "g"]
+        );
+        assert_eq!(
+            file_system.list_dir("g").expect("Listing failed"),
+            vec!["h:i"]
+        );
+
+        file_system.set_separator(':');
+        assert_eq!(
+            file_system.roots().expect("Querying roots failed"),
+            vec!["a", "f", "g/h"]
+        );
+        assert_eq!(
+            file_system.list_dir("").expect("Listing failed"),
+            vec!["a", "f", "g/h"]
+        );
+        assert_eq!(
+            file_system.list_dir("a").expect("Listing failed"),
+            vec!["b", "e"]
+        );
+        assert_eq!(
+            file_system.list_dir("a:b:").expect("Listing failed"),
+            vec!["c", "d"]
+        );
+        assert!(file_system
+            .list_dir("a:b:c")
+            .expect("Listing failed")
+            .is_empty());
+        assert_eq!(file_system.path_kind("a:b"), Ok(PathKind::Directory));
+        assert_eq!(file_system.path_kind("g"), Ok(PathKind::Missing));
+        assert_eq!(
+            file_system.plan_delete_dir("a:b"),
+            Ok(vec![String::from("a:b:c"), String::from("a:b:d")])
+        );
+        assert_eq!(
+            file_system.plan_delete_dir("a"),
+            Ok(vec![
+                String::from("a:b:c"),
+                String::from("a:b:d"),
+                String::from("a:e")
+            ])
+        );
+
+        let tree = file_system.tree().expect("Building tree failed");
+        assert_eq!(tree["a:b"], vec!["c", "d"]);
+        assert_eq!(tree[""], vec!["f"]);
+        assert_eq!(tree["g/h"], vec!["i"]);
+
+        assert_eq!(file_system.parent("a:b:c").expect("Parent missing"), "a:b");
+        assert_eq!(file_system.parent("f").expect("Parent missing"), "");
+        assert_eq!(file_system.file_name("/a:b:c"), Some(String::from("c")));
+        assert_eq!(file_system.file_name(""), None);
+    }
+
+    #[test]
+    fn test_sizes() {
+        let mut file_system = FileSystem::load(
+            Database::open_in_memory().expect("Open in-memory database failed"),
+            true,
+        )
+        .expect("Creating filesystem failed");
+        let data: Vec<u8> = (0..20).collect();
+        let first = File::create(&mut file_system, "first", &data[..7], 3)
+            .expect("File creation failed")
+            .handle();
+        let compressed = File::create_compressed(&mut file_system, "compressed", &data[..], 4)
+            .expect("File creation failed")
+            .handle();
+        let empty = File::create(&mut file_system, "empty", &[][..], 3)
+            .expect("File creation failed")
+            .handle();
+
+        assert_eq!(file_system.sizes(&[]), Ok(Vec::new()));
+        assert_eq!(
+            file_system.sizes(&[compressed, Handle(4242), first, empty, Handle(-1), first]),
+            Ok(vec![(compressed, 20), (first, 7), (empty, 0), (first, 7)])
+        );
+
+        // Large sets are split into multiple queries
+        file_system
+            .connection()
+            .set_limit(Limit::SQLITE_LIMIT_VARIABLE_NUMBER, 2);
+        assert_eq!(
+            file_system.sizes(&[first, Handle(4242), compressed, empty]),
+            Ok(vec![(first, 7), (compressed, 20), (empty, 0)])
+        );
+    }
+
+    #[test]
+    fn test_sizes_of_non_files() {
+        let mut file_system = FileSystem::load(
+            Database::open_in_memory().expect("Open in-memory database failed"),
+            true,
+        )
+        .expect("Creating filesystem failed");
+        file_system.set_trash(true).expect("Enabling trash failed");
+        let file = File::create(&mut file_system, "file", &[1u8, 2][..], 3)
+            .expect("File creation failed")
+            .handle();
+        let trashed = File::create(&mut file_system, "trashed", &[1u8, 2, 3][..], 3)
+            .expect("File creation failed")
+            .handle();
+        let link = file_system
+            .create_symlink("link", "file")
+            .expect("Symlink creation failed");
+        assert!(File::load(&file_system, "trashed")
+            .expect("Loading file failed")
+            .delete());
+
+        // Only files have a size
+        assert_eq!(
+            file_system.sizes(&[trashed, file, link]),
+            Ok(vec![(file, 2)])
+        );
+        assert_eq!(file_system.sizes(&[link]), Ok(Vec::new()));
+        assert_eq!(file_system.sizes(&[trashed]), Ok(Vec::new()));
+    }
+
+    #[test]
+    fn test_entries() {
+        let mut file_system = FileSystem::load(
+            Database::open_in_memory().expect("Open in-memory database failed"),
+            true,
+        )
+        .expect("Creating filesystem failed");
+        File::create(&mut file_system, "b", &[1u8, 2][..], 4).expect("File creation failed");
+        File::create(&mut file_system, "a/c", &[3u8][..], 4).expect("File creation failed");
+        file_system
+            .create_symlink("link", "b")
+            .expect("Symlink creation failed");
+
+        let entries: Vec<(Handle, String)> = file_system
+            .entries()
+            .expect("Listing entries failed")
+            .collect();
+        let mut paths = file_system.find("*").expect("Finding files failed");
+        paths.sort();
+        assert_eq!(
+            entries
+                .iter()
+                .map(|(_, path)| path.as_str())
+                .collect::<Vec<_>>(),
+            paths
+        );
+        for (handle, path) in entries {
+            let file: File<Database> = (&file_system, handle)
+                .try_into()
+                .expect("Loading file by handle failed");
+            let expected = File::load(&file_system, &path).expect("Loading file failed");
+            assert_eq!(file.handle(), expected.handle());
+            assert_eq!(file.read_to_string(), expected.read_to_string());
+        }
+    }
+
+    #[test]
+    fn test_find_duplicates() {
+        let mut file_system = FileSystem::load(
+            Database::open_in_memory().expect("Open in-memory database failed"),
+            true,
+        )
+        .expect("Creating filesystem failed");
+        assert_eq!(
+            file_system
+                .find_duplicates()
+                .expect("Finding duplicates failed"),
+            Vec::<Vec<String>>::new()
+        );
+
+        let data: Vec<u8> = (0..10).collect();
+        File::create(&mut file_system, "b", &data[..], 3).expect("File creation failed");
+        File::create(&mut file_system, "a", &data[..], 4).expect("File creation failed");
+        File::create_compressed(&mut file_system, "c", &data[..], 4).expect("File creation failed");
+        File::create(&mut file_system, "same_size", &[0u8; 10][..], 4)
+            .expect("File creation failed");
+        File::create(&mut file_system, "unique", &[42u8][..], 4).expect("File creation failed");
+        file_system
+            .create_symlink("link", "a")
+            .expect("Symlink creation failed");
+
+        assert_eq!(
+            file_system
+                .find_duplicates()
+                .expect("Finding duplicates failed"),
+            vec![vec![
+                String::from("a"),
+                String::from("b"),
+                String::from("c")
+            ]]
+        );
+    }
+
+    #[test]
+    fn test_max_chunk_size() {
+        let mut file_system = FileSystem::load(
+            Database::open_in_memory().expect("Open in-memory database failed"),
+            true,
+        )
+        .expect("Creating filesystem failed");
+        assert_eq!(
+            file_system.max_chunk_size(),
+            file_system.connection().limit(Limit::SQLITE_LIMIT_LENGTH) as usize
+        );
+
+        file_system
+            .connection_mut()
+            .set_limit(Limit::SQLITE_LIMIT_LENGTH, 1024);
+        assert_eq!(file_system.max_chunk_size(), 1024);
+    }
+
+    #[test]
+    fn test_delete_handle() {
+        let mut file_system = FileSystem::load(
+            Database::open_in_memory().expect("Open in-memory database failed"),
+            true,
+        )
+        .expect("Creating filesystem failed");
+        let handle = File::create(&mut file_system, "file", &[1u8, 2, 3][..], 2)
+            .expect("File creation failed")
+            .handle();
+
+        assert_eq!(file_system.delete_handle(handle), Ok(true));
+        assert_eq!(
+            File::load(&file_system, "file").expect_err("Deleted file was loaded"),
+            LoadingError::FileNotFound
+        );
+        assert_eq!(file_system.delete_handle(handle), Ok(false));
+    }
+
+    #[test_case(false; "delete")]
+    #[test_case(true; "trash")]
+    fn test_rename_replace(use_trash: bool) {
+        let mut file_system = FileSystem::load(
+            Database::open_in_memory().expect("Open in-memory database failed"),
+            true,
+        )
+        .expect("Creating filesystem failed");
+        file_system
+            .set_trash(use_trash)
+            .expect("Configuring trash failed");
+        let read = |file_system: &FileSystem<Database>, path: &str| {
+            let mut buffer = Vec::new();
+            File::load(file_system, path)
+                .expect("Loading file failed")
+                .read_to_end(&mut buffer)
+                .expect("Reading failed");
+            buffer
+        };
+
+        let handle = File::create(&mut file_system, "source", &[1u8, 2, 3][..], 2)
+            .expect("File creation failed")
+            .handle();
+        File::create(&mut file_system, "destination", &[4u8][..], 2).expect("File creation failed");
+
+        // Replace an existing destination
+        assert_eq!(
+            file_system.rename_replace("source", "destination"),
+            Ok(handle)
+        );
+        assert_eq!(
+            File::load(&file_system, "source").expect_err("Moved file was loaded"),
+            LoadingError::FileNotFound
+        );
+        assert_eq!(read(&file_system, "destination"), vec![1, 2, 3]);
+        assert_eq!(
+            file_system.find("*").expect("Finding files failed"),
+            vec![String::from("destination")]
+        );
+
+        // Move to a new path or to itself
+        assert_eq!(file_system.rename_replace("destination", "new"), Ok(handle));
+        assert_eq!(file_system.rename_replace("new", "new"), Ok(handle));
+        assert_eq!(read(&file_system, "new"), vec![1, 2, 3]);
+
+        assert_eq!(
+            file_system.rename_replace("missing", "new"),
+            Err(LoadingError::FileNotFound.into())
+        );
+        assert_eq!(read(&file_system, "new"), vec![1, 2, 3]);
+
+        // The destination is validated like for a rename
+        assert_eq!(
+            file_system.rename_replace("new", ""),
+            Err(CreationError::InvalidPath(PathError::Empty).into())
+        );
+        assert_eq!(
+            file_system.rename_replace("new", "a\0b"),
+            Err(CreationError::InvalidPath(PathError::ForbiddenCharacter('\0')).into())
+        );
+        assert_eq!(read(&file_system, "new"), vec![1, 2, 3]);
+    }
+
+    #[test]
+    fn test_rename() {
+        let mut file_system = FileSystem::load(
+            Database::open_in_memory().expect("Open in-memory database failed"),
+            true,
+        )
+        .expect("Creating filesystem failed");
+        let handle = File::create(&mut file_system, "source", &[1u8, 2, 3][..], 2)
+            .expect("File creation failed")
+            .handle();
+        File::create(&mut file_system, "destination", &[4u8][..], 2).expect("File creation failed");
+
+        // Existing destinations are kept
+        assert_eq!(
+            file_system.rename("source", "destination"),
+            Err(CreationError::FileExists.into())
+        );
+        assert_eq!(
+            file_system.rename("missing", "new"),
+            Err(LoadingError::FileNotFound.into())
+        );
+
+        assert_eq!(file_system.rename("source", "folder/new"), Ok(handle));
+        assert_eq!(
+            File::load(&file_system, "source").expect_err("Moved file was loaded"),
+            LoadingError::FileNotFound
+        );
+        assert_eq!(
+            File::load(&file_system, "folder/new")
+                .expect("Loading file failed")
+                .handle(),
+            handle
+        );
+        assert_eq!(file_system.rename("folder/new", "folder/new"), Ok(handle));
+    }
+
+    #[test_case(0, 10, vec![(0, 4), (1, 4), (2, 2)]; "whole file")]
+    #[test_case(2, 4, vec![(0, 2), (1, 2)]; "across boundary")]
+    #[test_case(4, 4, vec![(1, 4)]; "single chunk")]
+    #[test_case(9, 1, vec![(2, 1)]; "last byte")]
+    fn test_read_with_chunk_info(index: usize, length: usize, expected: Vec<(u32, usize)>) {
+        let mut file_system = FileSystem::load(
+            Database::open_in_memory().expect("Open in-memory database failed"),
+            true,
+        )
+        .expect("Creating filesystem failed");
+        let data: Vec<u8> = (0..10).collect();
+        let file =
+            File::create(&mut file_system, "file", &data[..], 4).expect("File creation failed");
+
+        let mut chunks = Vec::new();
+        let mut buffer = Vec::new();
+        assert_eq!(
+            file.read_with_chunk_info(&mut buffer, index, length, |chunk_num, num_bytes| {
+                chunks.push((chunk_num, num_bytes))
+            }),
+            Ok(length)
+        );
+        assert_eq!(&buffer[..], &data[index..index + length]);
+        assert_eq!(chunks, expected);
+    }
+
+    #[test_case(CheckpointMode::Passive)]
+    #[test_case(CheckpointMode::Full)]
+    #[test_case(CheckpointMode::Restart)]
+    #[test_case(CheckpointMode::Truncate)]
+    fn test_checkpoint(mode: CheckpointMode) {
+        let tmp_dir = tempfile::TempDir::new().expect("Unable to create temporary directory");
+        let database_path = tmp_dir.path().join("database.sqlite");
+        let database = Database::open(&database_path).expect("Opening database failed");
+        let journal_mode: String = database
+            .pragma_update_and_check(None, "journal_mode", &"WAL", |row| row.get(0))
+            .expect("Enabling WAL failed");
+        assert_eq!(journal_mode, "wal");
+
+        let mut file_system = FileSystem::load(database, true).expect("Creating filesystem failed");
+        File::create(&mut file_system, "file", &[42u8; 4096][..], 512)
+            .expect("File creation failed");
+        let wal_path = tmp_dir.path().join("database.sqlite-wal");
+        assert!(std::fs::metadata(&wal_path).expect("Missing WAL").len() > 0);
+
+        assert_eq!(file_system.checkpoint(mode), Ok(()));
+        if mode == CheckpointMode::Truncate {
+            assert_eq!(std::fs::metadata(&wal_path).expect("Missing WAL").len(), 0);
+        }
+    }
+
+    #[test_case(true; "wal")]
+    #[test_case(false; "rollback journal")]
+    fn test_sync(use_wal: bool) {
+        let tmp_dir = tempfile::TempDir::new().expect("Unable to create temporary directory");
+        let database_path = tmp_dir.path().join("database.sqlite");
+        let database = Database::open(&database_path).expect("Opening database failed");
+        if use_wal {
+            database
+                .pragma_update(None, "journal_mode", &"WAL")
+                .expect("Enabling WAL failed");
+        }
+        database
+            .pragma_update(None, "synchronous", &"NORMAL")
+            .expect("Configuring synchronization failed");
+
+        let mut file_system = FileSystem::load(database, true).expect("Creating filesystem failed");
+        File::create(&mut file_system, "file", &[42u8; 8192][..], 512)
+            .expect("File creation failed");
+        assert_eq!(file_system.sync(), Ok(()));
+
+        // The content is part of the database file itself
+        assert!(
+            std::fs::metadata(&database_path)
+                .expect("Missing database")
+                .len()
+                > 8192
+        );
+
+        let in_memory = FileSystem::load(
+            Database::open_in_memory().expect("Open in-memory database failed"),
+            true,
+        )
+        .expect("Creating filesystem failed");
+        assert_eq!(in_memory.sync(), Ok(()));
+    }
+
+    #[test]
+    fn test_optimize() {
+        let mut file_system = FileSystem::load(
+            Database::open_in_memory().expect("Open in-memory database failed"),
+            true,
+        )
+        .expect("Creating filesystem failed");
+        for index in 0..10 {
+            File::create(
+                &mut file_system,
+                format!("file{}", index),
+                &[42u8; 64][..],
+                8,
+            )
+            .expect("File creation failed");
+        }
+
+        assert_eq!(file_system.optimize(), Ok(()));
+        let num_stats: i64 = file_system
+            .connection()
+            .query_row("SELECT COUNT(*) FROM sqlite_stat1", [], |row| row.get(0))
+            .expect("Querying statistics failed");
+        assert!(num_stats > 0);
+        File::load(&file_system, "file0").expect("Loading file failed");
+    }
+
+    #[cfg(feature = "bytes")]
+    #[test]
+    fn test_read_bytes() {
+        let mut file_system = FileSystem::load(
+            Database::open_in_memory().expect("Open in-memory database failed"),
+            true,
+        )
+        .expect("Creating filesystem failed");
+        let data: Vec<u8> = (0..10).collect();
+        let file =
+            File::create(&mut file_system, "file", &data[..], 4).expect("File creation failed");
+
+        let bytes = file.read_bytes().expect("Reading failed");
+        assert_eq!(&bytes[..], &data[..]);
+        let clone = bytes.clone();
+        assert_eq!(clone.as_ptr(), bytes.as_ptr());
+    }
+
+    #[test]
+    fn test_hash_with() {
+        let mut file_system = FileSystem::load(
+            Database::open_in_memory().expect("Open in-memory database failed"),
+            true,
+        )
+        .expect("Creating filesystem failed");
+        let data: Vec<u8> = (0..100).collect();
+        let file =
+            File::create(&mut file_system, "file", &data[..], 8).expect("File creation failed");
+
+        let mut file_hasher = DefaultHasher::new();
+        file.hash_with(&mut file_hasher).expect("Hashing failed");
+        let mut data_hasher = DefaultHasher::new();
+        data_hasher.write(&data);
+        assert_eq!(file_hasher.finish(), data_hasher.finish());
+    }
+
+    #[test_case(false; "plain")]
+    #[test_case(true; "compressed")]
+    fn test_compact(compressed: bool) {
+        let mut file_system = FileSystem::load(
+            Database::open_in_memory().expect("Open in-memory database failed"),
+            true,
+        )
+        .expect("Creating filesystem failed");
+        let data: Vec<u8> = (0..10).collect();
+        let handle = match compressed {
+            true => File::create_compressed(&mut file_system, "file", &data[..], 3),
+            false => File::create(&mut file_system, "file", &data[..], 3),
+        }
+        .expect("File creation failed")
+        .handle();
+
+        // Fragment the file by pretending its chunks are undersized
+        file_system
+            .connection()
+            .execute(
+                &format!(
+                    "UPDATE {} SET chunk_size = 4 WHERE id = ?",
+                    super::constants::MATRYOSHKA_TABLE
+                ),
+                [handle.0],
+            )
+            .expect("Changing chunk size failed");
+        let chunk_sizes = |file_system: &FileSystem<Database>| -> Vec<usize> {
+            let file = File::load(file_system, "file").expect("Loading file failed");
+            file.chunks()
+                .map(|chunk| chunk.expect("Reading chunk failed").len())
+                .collect()
+        };
+        assert_eq!(chunk_sizes(&file_system), vec![3, 3, 3, 1]);
+
+        let mut file = File::load(&file_system, "file").expect("Loading file failed");
+        file.compact().expect("Compaction failed");
+        assert_eq!(chunk_sizes(&file_system), vec![4, 4, 2]);
+
+        let mut buffer = Vec::new();
+        let file = File::load(&file_system, "file").expect("Loading file failed");
+        assert_eq!(file.len(), data.len());
+        file.random_read(&mut buffer, 0, data.len())
+            .expect("Reading failed");
+        assert_eq!(buffer, data);
+    }
+
+    #[test]
+    fn test_copy_range() {
+        let mut file_system = FileSystem::load(
+            Database::open_in_memory().expect("Open in-memory database failed"),
+            true,
+        )
+        .expect("Creating filesystem failed");
+        let source: Vec<u8> = (0..10).collect();
+        File::create(&mut file_system, "source", &source[..], 3).expect("File creation failed");
+        File::create(&mut file_system, "destination", &[0u8; 8][..], 4)
+            .expect("File creation failed");
+        let read = |file_system: &FileSystem<Database>, path: &str| {
+            let mut buffer = Vec::new();
+            File::load(file_system, path)
+                .expect("Loading file failed")
+                .read_to_end(&mut buffer)
+                .expect("Reading failed");
+            buffer
+        };
+
+        // Spanning chunks on both sides
+        assert_eq!(
+            file_system
+                .copy_range("source", 2, 5, "destination", 3)
+                .expect("Copying range failed"),
+            5
+        );
+        assert_eq!(
+            read(&file_system, "destination"),
+            vec![0, 0, 0, 2, 3, 4, 5, 6]
+        );
+        assert_eq!(read(&file_system, "source"), source);
+
+        // Overlapping ranges of the same file
+        file_system
+            .copy_range("source", 0, 6, "source", 2)
+            .expect("Copying range failed");
+        assert_eq!(
+            read(&file_system, "source"),
+            vec![0, 1, 0, 1, 2, 3, 4, 5, 8, 9]
+        );
+
+        // Ranges must not exceed the files
+        for (source_offset, length, destination_offset) in
+            [(6, 5, 0), (0, 5, 4), (0, usize::MAX, 0)].iter()
+        {
+            assert_eq!(
+                file_system
+                    .copy_range(
+                        "source",
+                        *source_offset,
+                        *length,
+                        "destination",
+                        *destination_offset
+                    )
+                    .expect_err("Copied out of bounds"),
+                MatryoshkaError::Read(ReadError::OutOfBounds)
+            );
+        }
+        assert_eq!(
+            read(&file_system, "destination"),
+            vec![0, 0, 0, 2, 3, 4, 5, 6]
+        );
+        assert_eq!(
+            file_system
+                .copy_range("missing", 0, 1, "destination", 0)
+                .expect_err("Copied missing file"),
+            MatryoshkaError::Loading(LoadingError::FileNotFound)
+        );
+    }
+
+    #[test]
+    fn test_modified_since() {
+        let mut file_system = FileSystem::load(
+            Database::open_in_memory().expect("Open in-memory database failed"),
+            true,
+        )
+        .expect("Creating filesystem failed");
+        let data = [1u8, 2, 3];
+        let pause = || std::thread::sleep(Duration::from_millis(20));
+
+        let start = SystemTime::now();
+        pause();
+        for path in ["file_1", "file_2"].iter() {
+            File::create(&mut file_system, path, &data[..], 3).expect("File creation failed");
+        }
+        pause();
+        let since = SystemTime::now();
+        pause();
+
+        // Replace one of the files
+        assert!(File::load(&file_system, "file_2")
+            .expect("Loading file failed")
+            .delete());
+        File::create(&mut file_system, "file_2", &data[..], 3).expect("File creation failed");
+
+        assert_eq!(
+            file_system
+                .modified_since(since)
+                .expect("Querying modified files failed"),
+            vec!["file_2"]
+        );
+        assert_eq!(
+            file_system
+                .modified_since(start)
+                .expect("Querying modified files failed"),
+            vec!["file_1", "file_2"]
+        );
+
+        // Modifications in place count as well
+        pause();
+        let since = SystemTime::now();
+        pause();
+        File::open_exclusive(&file_system, "file_1")
+            .expect("Opening file failed")
+            .write_at(1, &[4])
+            .expect("Writing failed");
+        assert_eq!(
+            file_system
+                .modified_since(since)
+                .expect("Querying modified files failed"),
+            vec!["file_1"]
+        );
+        file_system
+            .copy_range("file_1", 0, 2, "file_2", 1)
+            .expect("Copying failed");
+        assert_eq!(
+            file_system
+                .modified_since(since)
+                .expect("Querying modified files failed"),
+            vec!["file_1", "file_2"]
+        );
+    }
+
+    #[test]
+    fn test_touch() {
+        let mut file_system = FileSystem::load(
+            Database::open_in_memory().expect("Open in-memory database failed"),
+            true,
+        )
+        .expect("Creating filesystem failed");
+        let pause = || std::thread::sleep(Duration::from_millis(20));
+
+        // Touching creates an empty file
+        let handle = file_system.touch("lock").expect("Touching new file failed");
+        let file = File::load(&file_system, "lock").expect("Loading file failed");
+        assert_eq!(file.handle(), handle);
+        assert!(file.is_empty());
+
+        // Touching again keeps the content but updates the modification time
+        File::create(&mut file_system, "data", &[1u8, 2, 3][..], 2).expect("File creation failed");
+        pause();
+        let since = SystemTime::now();
+        pause();
+        assert!(file_system
+            .modified_since(since)
+            .expect("Querying modified files failed")
+            .is_empty());
+        assert_eq!(
+            file_system
+                .touch("lock")
+                .expect("Touching existing file failed"),
+            handle
+        );
+        let data_handle = file_system
+            .touch("data")
+            .expect("Touching existing file failed");
+        assert_eq!(
+            File::load(&file_system, "data")
+                .expect("Loading file failed")
+                .len(),
+            3
+        );
+        assert_eq!(
+            File::load(&file_system, "data")
+                .expect("Loading file failed")
+                .handle(),
+            data_handle
+        );
+        assert_eq!(
+            file_system
+                .modified_since(since)
+                .expect("Querying modified files failed"),
+            vec!["data", "lock"]
+        );
+    }
+
+    #[test]
+    fn test_modified_since_legacy() {
+        let mut connection = Database::open_in_memory().expect("Open in-memory database failed");
+        connection
+            .execute(
+                "CREATE TABLE Matryoshka_Meta_0 (id INTEGER PRIMARY KEY, path TEXT UNIQUE NOT NULL, type INTEGER, flags INTEGER, chunk_size INTEGER NOT NULL)",
+                [],
+            )
+            .expect("Creating legacy meta table failed");
+        connection
+            .execute(super::constants::SQL_CREATE_DATA, [])
+            .expect("Creating data table failed");
+
+        let file_system =
+            FileSystem::load(&mut connection, false).expect("Loading legacy filesystem failed");
+        file_system
+            .modified_since(SystemTime::now())
+            .expect_err("Legacy file system reported timestamps");
+    }
+
+    #[test]
+    fn test_transfer() {
+        let mut source = FileSystem::load(
+            Database::open_in_memory().expect("Open in-memory database failed"),
+            true,
+        )
+        .expect("Creating filesystem failed");
+        let mut destination = FileSystem::load(
+            Database::open_in_memory().expect("Open in-memory database failed"),
+            true,
+        )
+        .expect("Creating filesystem failed");
 
-        // Create file
-        {
-            let file = File::create(&mut file_system, path, &data[..], chunk_size)
-                .expect("Creating file failed");
-            assert_eq!(file.len(), data.len());
-        }
+        let data: Vec<_> = (0..100u8).collect();
+        File::create(&mut source, "folder/file", &data[..], 7).expect("File creation failed");
 
-        // Check that the file could not be overwritten
+        let handle = source
+            .transfer_to(&mut destination, "folder/file")
+            .expect("Transfer failed");
+
+        // The file moved to the destination
         assert_eq!(
-            File::create(&mut file_system, path, &data[..], chunk_size)
-                .expect_err("Able to write file a second time"),
-            CreationError::FileExists
+            File::load(&source, "folder/file").expect_err("Source still exists"),
+            LoadingError::FileNotFound
         );
+        let mut file = File::load(&destination, "folder/file").expect("Destination is missing");
+        assert_eq!(file.handle(), handle);
+        let mut buffer = Vec::new();
+        file.read_to_end(&mut buffer).expect("Reading failed");
+        assert_eq!(&buffer, &data);
 
-        // Load and read file
-        {
-            let file = File::load(&mut file_system, path).expect("Loading file failed");
-            assert_eq!(file.len(), data.len());
+        // Missing files could not be transferred
+        assert_eq!(
+            source
+                .transfer_to(&mut destination, "folder/file")
+                .expect_err("Missing file transferred"),
+            TransferError::Loading(LoadingError::FileNotFound)
+        );
 
-            let mut read_data = Vec::new();
-            if is_out_of_bounds {
-                assert_eq!(
-                    file.random_read(&mut read_data, index, length)
-                        .expect_err("Reading file content was successful despite out of bounds"),
-                    ReadError::OutOfBounds
-                );
-            } else {
-                assert_eq!(
-                    file.random_read(&mut read_data, index, length)
-                        .expect("Reading file content failed"),
-                    length
-                );
-                assert_eq!(read_data.len(), length);
-                if length > 0 {
-                    assert_eq!(&read_data, &data[index..(index + length)]);
-                }
-            }
-        }
+        // A failing creation leaves the source intact
+        File::create(&mut source, "folder/file", &data[..], 7).expect("File creation failed");
+        assert_eq!(
+            source
+                .transfer_to(&mut destination, "folder/file")
+                .expect_err("Existing file overwritten"),
+            TransferError::Creation(CreationError::FileExists)
+        );
+        File::load(&source, "folder/file").expect("Source was deleted");
     }
 
     #[test]
-    fn test_read_trait() {
-        let mut file_system = FileSystem::load(
+    fn test_transfer_layout() {
+        let mut source = FileSystem::load(
             Database::open_in_memory().expect("Open in-memory database failed"),
             true,
         )
         .expect("Creating filesystem failed");
+        let mut destination = FileSystem::load(
+            Database::open_in_memory().expect("Open in-memory database failed"),
+            true,
+        )
+        .expect("Creating filesystem failed");
+        let data: Vec<_> = (0..100u8).collect();
+        File::create(&mut source, "plain", &data[..], 7).expect("File creation failed");
+        File::create_compressed(&mut source, "compressed", &data[..], 30)
+            .expect("File creation failed");
+        source
+            .create_symlink("link", "plain")
+            .expect("Symlink creation failed");
 
-        let data = [1u8, 2, 3, 4, 5];
-
-        let mut file =
-            File::create(&mut file_system, "file", &data[..], 3).expect("File creation failed");
-        let mut buffer = vec![0u8; 3];
-        assert_eq!(file.read(&mut buffer[..]).expect("Successful read"), 3);
-        assert_eq!(&buffer, &[1u8, 2, 3]);
-
-        assert_eq!(file.read_to_end(&mut buffer).expect("Successful read"), 2);
-        assert_eq!(&buffer, &[4, 5]);
+        // The link itself is moved, while its target stays
+        source
+            .transfer_to(&mut destination, "link")
+            .expect("Transfer failed");
+        let mut paths = source.find("*").expect("Finding failed");
+        paths.sort();
+        assert_eq!(paths, vec!["compressed", "plain"]);
+        assert_eq!(
+            File::load(&source, "plain")
+                .expect("Target was deleted")
+                .read_to_string()
+                .map(String::into_bytes),
+            Ok(data.clone())
+        );
+        assert_eq!(
+            File::load(&destination, "link").expect_err("Dangling link was followed"),
+            LoadingError::FileNotFound
+        );
+        source
+            .transfer_to(&mut destination, "plain")
+            .expect("Transfer failed");
+        assert_eq!(
+            File::load(&destination, "link")
+                .expect("Link is broken")
+                .read_to_string()
+                .map(String::into_bytes),
+            Ok(data.clone())
+        );
 
-        // Test that it is safe to read at EOF
-        assert_eq!(file.read(&mut buffer[..]).expect("Successful read"), 0);
-        assert_eq!(file.read_to_end(&mut buffer).expect("Successful read"), 0);
+        // Chunk size and compression are kept
+        let layout = File::load(&destination, "plain")
+            .expect("Destination is missing")
+            .layout()
+            .expect("Querying layout failed");
+        assert_eq!(layout.len(), 15);
+        assert!(layout[..14].iter().all(|chunk| chunk.length() == 7));
+        let stored_size = File::load(&source, "compressed")
+            .expect("Loading file failed")
+            .stored_size()
+            .expect("Querying size failed");
+        assert_ne!(stored_size, data.len() as u64);
+        source
+            .transfer_to(&mut destination, "compressed")
+            .expect("Transfer failed");
+        let file = File::load(&destination, "compressed").expect("Destination is missing");
+        assert_eq!(file.layout().expect("Querying layout failed").len(), 4);
+        assert_eq!(file.stored_size(), Ok(stored_size));
+        let mut buffer = Vec::new();
+        file.random_read(&mut buffer, 0, file.len())
+            .expect("Reading failed");
+        assert_eq!(buffer, data);
     }
 
     #[test]
-    fn test_handle() {
+    fn test_trash() {
         let mut file_system = FileSystem::load(
             Database::open_in_memory().expect("Open in-memory database failed"),
             true,
         )
         .expect("Creating filesystem failed");
+        file_system.set_trash(true).expect("Enabling trash failed");
         let data = [1u8, 2, 3];
 
-        let handle = {
-            let file =
-                File::create(&mut file_system, "file", &data[..], 3).expect("File creation failed");
-            assert_eq!(file.len(), data.len());
-            file.handle
-        };
+        let handle = File::create(&mut file_system, "folder/file", &data[..], 2)
+            .expect("File creation failed")
+            .handle();
+        assert!(File::load(&file_system, "folder/file")
+            .expect("Loading file failed")
+            .delete());
 
-        // Create an invalid handle and check it is not equal to the "real" one
-        let invalid_handle: Handle = 42.into();
-        assert_ne!(handle, invalid_handle);
+        // The deleted file is gone, but not its path
+        assert_eq!(
+            File::load(&file_system, "folder/file").expect_err("Deleted file found"),
+            LoadingError::FileNotFound
+        );
+        assert!(file_system.find("*").expect("Finding failed").is_empty());
+        assert!(file_system
+            .roots()
+            .expect("Querying roots failed")
+            .is_empty());
 
-        // Re-open file from handle
-        {
-            let file: File<_> = (&file_system, handle)
-                .try_into()
-                .expect("Reconstructing file from handle failed");
-            assert_eq!(file.len(), data.len());
-        }
+        // Restoring is blocked by a new file at the same path
+        File::create(&mut file_system, "folder/file", &data[..2], 2).expect("File creation failed");
+        assert_eq!(
+            file_system
+                .restore("folder/file")
+                .expect_err("Restored over existing file"),
+            MatryoshkaError::Creation(CreationError::FileExists)
+        );
+        file_system
+            .set_trash(false)
+            .expect("Disabling trash failed");
+        assert!(File::load(&file_system, "folder/file")
+            .expect("Loading file failed")
+            .delete());
+        file_system.set_trash(true).expect("Enabling trash failed");
 
-        // Check that invalid handle is correctly identified
-        let invalid_file: Result<File<_>, _> = (&file_system, invalid_handle).try_into();
+        // Restore the older file again, including its content
         assert_eq!(
-            invalid_file.expect_err("Successful reconstruction of invalid handle"),
-            LoadingError::FileNotFound
+            file_system
+                .restore("folder/file")
+                .expect("Restoring file failed"),
+            handle
+        );
+        let mut file = File::load(&file_system, "folder/file").expect("Loading file failed");
+        assert_eq!(file.handle(), handle);
+        let mut buffer = Vec::new();
+        file.read_to_end(&mut buffer).expect("Reading failed");
+        assert_eq!(&buffer, &data);
+
+        // Files could be restored only once
+        assert_eq!(
+            file_system
+                .restore("missing")
+                .expect_err("Restored missing file"),
+            MatryoshkaError::Loading(LoadingError::FileNotFound)
         );
     }
 
-    #[test]
-    fn test_empty_file() {
+    #[test_case(false; "permanently")]
+    #[test_case(true; "into trash")]
+    fn test_delete_matching(use_trash: bool) {
         let mut file_system = FileSystem::load(
             Database::open_in_memory().expect("Open in-memory database failed"),
             true,
         )
-        .expect("Unable to create file system");
-        let data = Vec::new();
-
-        let handle = {
-            let file =
-                File::create(&mut file_system, "abc", &data[..], 3).expect("Unable to create file");
-            assert_eq!(file.len(), 0);
-            assert_eq!(file.is_empty(), true);
-            file.handle()
-        };
+        .expect("Creating filesystem failed");
+        file_system
+            .set_trash(use_trash)
+            .expect("Configuring trash failed");
+        for path in [
+            "folder/a.tmp",
+            "folder/b.tmp",
+            "folder/c.txt",
+            "other/d.tmp",
+        ]
+        .iter()
+        {
+            File::create(&mut file_system, path, &[1u8, 2, 3][..], 2)
+                .expect("File creation failed");
+        }
 
-        let reopened_file: File<_> = (&file_system, handle)
-            .try_into()
-            .expect("Unable to re-open empty file");
-        assert_eq!(reopened_file.len(), 0);
+        assert_eq!(
+            file_system
+                .delete_matching("folder/*.tmp")
+                .expect("Deleting files failed"),
+            2
+        );
+        let mut remaining = file_system.find("*").expect("Finding failed");
+        remaining.sort();
+        assert_eq!(remaining, vec!["folder/c.txt", "other/d.tmp"]);
+        assert_eq!(
+            file_system
+                .delete_matching("folder/*.tmp")
+                .expect("Deleting files failed"),
+            0
+        );
+        assert_eq!(file_system.restore("folder/a.tmp").is_ok(), use_trash);
     }
 
     #[test]
-    fn test_delete() {
+    fn test_empty_trash() {
         let mut file_system = FileSystem::load(
             Database::open_in_memory().expect("Open in-memory database failed"),
             true,
         )
         .expect("Creating filesystem failed");
+        file_system.set_trash(true).expect("Enabling trash failed");
         let data = [1u8, 2, 3];
-        let path = "abc";
-
-        // Create file
-        File::create(&mut file_system, path, &data[..], 3).expect("File creation failed");
 
-        // Check that the file exists
-        File::create(&mut file_system, path, &data[..], 3)
-            .expect_err("File created despite existent");
+        for path in ["file_1", "file_2"].iter() {
+            File::create(&mut file_system, path, &data[..], 2).expect("File creation failed");
+            assert!(File::load(&file_system, path)
+                .expect("Loading file failed")
+                .delete());
+        }
+        assert_eq!(
+            file_system
+                .storage_stats()
+                .expect("Querying stats failed")
+                .payload_size(),
+            2 * data.len() as u64
+        );
 
-        // Delete the file
-        let file = File::load(&mut file_system, path).expect("Existing file not found");
-        assert!(file.delete());
+        assert_eq!(file_system.empty_trash().expect("Emptying trash failed"), 2);
+        assert_eq!(
+            file_system
+                .storage_stats()
+                .expect("Querying stats failed")
+                .payload_size(),
+            0
+        );
+        assert_eq!(
+            file_system
+                .restore("file_1")
+                .expect_err("Restored permanently deleted file"),
+            MatryoshkaError::Loading(LoadingError::FileNotFound)
+        );
 
-        // Check the file does not longer exists
+        // Without trash, files are deleted permanently
+        file_system
+            .set_trash(false)
+            .expect("Disabling trash failed");
+        File::create(&mut file_system, "file_3", &data[..], 2).expect("File creation failed");
+        assert!(File::load(&file_system, "file_3")
+            .expect("Loading file failed")
+            .delete());
         assert_eq!(
-            File::load(&mut file_system, path).expect_err("Delete file still found"),
-            LoadingError::FileNotFound
+            file_system
+                .restore("file_3")
+                .expect_err("Restored permanently deleted file"),
+            MatryoshkaError::Loading(LoadingError::FileNotFound)
         );
+    }
 
-        // Check a new file can be created
-        File::create(&mut file_system, path, &data[..], 3).expect("File (re-)creation failed");
+    #[test]
+    fn test_prefix_uses_index() {
+        let file_system = FileSystem::load(
+            Database::open_in_memory().expect("Open in-memory database failed"),
+            true,
+        )
+        .expect("Creating filesystem failed");
+
+        let plan: Vec<String> = file_system
+            .connection()
+            .prepare(&format!(
+                "EXPLAIN QUERY PLAN {}",
+                super::constants::SQL_PREFIX
+            ))
+            .expect("Preparing query plan failed")
+            .query_map(
+                named_params! {
+                    ":type": super::constants::FILE_ID,
+                    ":lower": "folder/",
+                    ":upper": "folder0"
+                },
+                |row| row.get(3),
+            )
+            .expect("Querying plan failed")
+            .collect::<Result<_, _>>()
+            .expect("Querying plan failed");
+        assert!(
+            plan.iter()
+                .any(|step| step.contains("USING INDEX") && step.contains("path>? AND path<?")),
+            "Prefix query does not use the index: {:?}",
+            plan
+        );
     }
 
     #[test]
-    fn test_find() {
+    fn test_plan_delete_dir() {
         let mut file_system = FileSystem::load(
             Database::open_in_memory().expect("Open in-memory database failed"),
             true,
@@ -729,51 +7060,161 @@ mod tests {
         .expect("Creating filesystem failed");
 
         let paths = [
-            "folder/example_file_1.txt",
-            "folder/example_file_2.txt",
-            "folder/nested_folder1/file1.txt",
-            "folder/nested_folder1/file2.txt",
-            "folder/nested_folder2/file1.txt",
+            "folder/file_1.txt",
+            "folder/nested_folder/file_2.txt",
+            "folder_2/file.txt",
+            "folder*/file.txt",
         ];
         let data = [1u8, 2, 3];
         for path in paths.iter() {
             File::create(&mut file_system, path, &data[..], 42).expect("Creating file failed");
         }
 
-        // Check non-existing paths
-        assert_eq!(file_system.find("folder").expect("Finding failed").len(), 0);
-
-        // Check existing paths - makes no real sense, but...
-        assert_eq!(file_system.find(paths[0]).expect("Finding failed").len(), 1);
-
-        // Check single char wildcard
         assert_eq!(
             file_system
-                .find("folder/example_file_?.txt")
-                .expect("Finding failed")
-                .len(),
-            2
+                .plan_delete_dir("/folder/")
+                .expect("Planning failed"),
+            vec!["folder/file_1.txt", "folder/nested_folder/file_2.txt"]
+        );
+        assert_eq!(
+            file_system
+                .plan_delete_dir("folder/nested_folder")
+                .expect("Planning failed"),
+            vec!["folder/nested_folder/file_2.txt"]
         );
 
-        // Check multiple char wildcard
+        // Placeholders are not interpreted
         assert_eq!(
             file_system
-                .find("folder/example_*.txt")
-                .expect("Finding failed")
-                .len(),
-            2
+                .plan_delete_dir("folder*")
+                .expect("Planning failed"),
+            vec!["folder*/file.txt"]
         );
 
-        // Check multiple char wildcard in folders
+        // Files are no directories
+        assert!(file_system
+            .plan_delete_dir("folder/file_1.txt")
+            .expect("Planning failed")
+            .is_empty());
+
+        // The root contains everything
         assert_eq!(
             file_system
-                .find("folder/*/*")
-                .expect("Finding failed")
+                .plan_delete_dir("/")
+                .expect("Planning failed")
                 .len(),
-            3
+            paths.len()
         );
 
-        // Check general wildcard
-        assert_eq!(file_system.find("*").expect("Finding failed").len(), 5);
+        // Nothing was deleted
+        assert_eq!(
+            file_system.find("*").expect("Finding failed").len(),
+            paths.len()
+        );
+    }
+
+    #[test]
+    fn test_storage_stats() {
+        let mut file_system = FileSystem::load(
+            Database::open_in_memory().expect("Open in-memory database failed"),
+            true,
+        )
+        .expect("Creating filesystem failed");
+
+        let empty_stats = file_system.storage_stats().expect("Querying stats failed");
+        assert_eq!(empty_stats.payload_size(), 0);
+        assert_eq!(empty_stats.overhead_ratio(), None);
+
+        let data = vec![42u8; 10000];
+        File::create(&mut file_system, "file", &data[..], 1000).expect("File creation failed");
+
+        let stats = file_system.storage_stats().expect("Querying stats failed");
+        assert_eq!(stats.payload_size(), data.len() as u64);
+        assert!(stats.file_size() > 0);
+        assert!(stats.payload_size() <= stats.file_size());
+        assert!(stats.free_size() <= stats.file_size());
+        assert!(stats.overhead_ratio().expect("Missing ratio") >= 1.0);
+    }
+
+    #[test]
+    fn test_chunk_too_large() {
+        let mut connection = Database::open_in_memory().expect("Open in-memory database failed");
+        connection.set_limit(Limit::SQLITE_LIMIT_LENGTH, 1000);
+        let mut file_system =
+            FileSystem::load(&mut connection, true).expect("Creating filesystem failed");
+
+        // The default chunk size exceeds the lowered limit
+        let data = [42u8; 2000];
+        assert_eq!(
+            File::create(&mut file_system, "file", &data[..], 0)
+                .expect_err("Chunk exceeding the limit was written"),
+            CreationError::ChunkTooLarge(2000)
+        );
+
+        // Smaller chunks are fine
+        File::create(&mut file_system, "file", &data[..], 500).expect("File creation failed");
+    }
+
+    #[test_case(7, 3; "Partial last chunk")]
+    #[test_case(6, 3; "Full last chunk")]
+    #[test_case(0, 3; "Empty file")]
+    fn test_writer(file_size: u8, chunk_size: usize) {
+        let mut file_system = FileSystem::load(
+            Database::open_in_memory().expect("Open in-memory database failed"),
+            true,
+        )
+        .expect("Creating filesystem failed");
+        let data: Vec<_> = (0..file_size).collect();
+
+        let handle = {
+            let mut writer = file_system
+                .create_writer("file", chunk_size)
+                .expect("Creating writer failed");
+            for part in data.chunks(2) {
+                writer.write_all(part).expect("Writing failed");
+            }
+            writer.finish().expect("Finishing writer failed")
+        };
+
+        let mut file = File::load(&file_system, "file").expect("Loading file failed");
+        assert_eq!(file.handle(), handle);
+        assert_eq!(file.len(), data.len());
+
+        let mut buffer = Vec::new();
+        assert_eq!(
+            file.read_to_end(&mut buffer).expect("Reading failed"),
+            data.len()
+        );
+        assert_eq!(&buffer, &data);
+    }
+
+    #[test]
+    fn test_writer_abort() {
+        let mut file_system = FileSystem::load(
+            Database::open_in_memory().expect("Open in-memory database failed"),
+            true,
+        )
+        .expect("Creating filesystem failed");
+
+        {
+            let mut writer = file_system
+                .create_writer("file", 2)
+                .expect("Creating writer failed");
+            writer.write_all(&[1u8, 2, 3]).expect("Writing failed");
+        }
+
+        assert_eq!(
+            File::load(&file_system, "file").expect_err("Aborted file exists"),
+            LoadingError::FileNotFound
+        );
+
+        // Existing files are not overwritten
+        File::create(&mut file_system, "file", &[1u8, 2, 3][..], 2).expect("File creation failed");
+        assert_eq!(
+            file_system
+                .create_writer("file", 2)
+                .expect_err("Writer overwrote existing file"),
+            CreationError::FileExists
+        );
     }
 }
@@ -16,6 +16,11 @@ pub mod errors;
 mod file_system;
 mod util;
 
-pub use self::file_system::{File, FileSystem};
-pub use self::util::Handle;
-pub use rusqlite::Connection as Database;
+pub use self::file_system::{
+    BatchWriter, ExclusiveFile, File, FileRef, FileSystem, FileWriter, FindIter,
+};
+pub use self::util::{
+    CheckpointMode, ChunkLayout, ChunkStats, Handle, LoadOptions, PathCollation, PathKind,
+    StorageStats, VirtualPath,
+};
+pub use rusqlite::{Connection as Database, InterruptHandle};
@@ -0,0 +1,24 @@
+/// The mode of a checkpoint transferring the content of the write-ahead log into the database.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CheckpointMode {
+    /// Checkpoint as much as possible without waiting for readers or writers.
+    Passive,
+    /// Wait for writers and readers until the whole log is checkpointed.
+    Full,
+    /// Like Full, but additionally wait for readers until the log may be restarted.
+    Restart,
+    /// Like Restart, but additionally truncate the log file to zero bytes.
+    Truncate,
+}
+
+impl CheckpointMode {
+    /// Returns the name of the mode used by SQLite.
+    pub(crate) fn as_sql(self) -> &'static str {
+        match self {
+            CheckpointMode::Passive => "PASSIVE",
+            CheckpointMode::Full => "FULL",
+            CheckpointMode::Restart => "RESTART",
+            CheckpointMode::Truncate => "TRUNCATE",
+        }
+    }
+}
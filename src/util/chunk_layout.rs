@@ -0,0 +1,32 @@
+/// The location of a chunk of a file in the database.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ChunkLayout {
+    chunk_id: i64,
+    chunk_num: u32,
+    length: u64,
+}
+
+impl ChunkLayout {
+    pub(crate) fn new(chunk_id: i64, chunk_num: u32, length: u64) -> Self {
+        ChunkLayout {
+            chunk_id,
+            chunk_num,
+            length,
+        }
+    }
+
+    /// Returns the row ID of the chunk in the data table.
+    pub fn chunk_id(&self) -> i64 {
+        self.chunk_id
+    }
+
+    /// Returns the position of the chunk within the file, starting at zero.
+    pub fn chunk_num(&self) -> u32 {
+        self.chunk_num
+    }
+
+    /// Returns the number of bytes stored in the chunk.
+    pub fn length(&self) -> u64 {
+        self.length
+    }
+}
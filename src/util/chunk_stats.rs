@@ -0,0 +1,42 @@
+/// Aggregate statistics regarding the chunks stored by the virtual file system, i.e. for tuning their size.
+///
+/// The lengths are those stored in the database, i.e. after compression.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ChunkStats {
+    count: u64,
+    total_length: u64,
+    max_length: u64,
+}
+
+impl ChunkStats {
+    pub(crate) fn new(count: u64, total_length: u64, max_length: u64) -> Self {
+        ChunkStats {
+            count,
+            total_length,
+            max_length,
+        }
+    }
+
+    /// Returns the number of chunks stored, including the empty ones.
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+
+    /// Returns the summed length of all chunks in bytes.
+    pub fn total_length(&self) -> u64 {
+        self.total_length
+    }
+
+    /// Returns the length of the largest chunk in bytes, which is zero without any chunks.
+    pub fn max_length(&self) -> u64 {
+        self.max_length
+    }
+
+    /// Returns the average length of the chunks in bytes, if there are any chunks.
+    pub fn average_length(&self) -> Option<f64> {
+        match self.count {
+            0 => None,
+            count => Some(self.total_length as f64 / count as f64),
+        }
+    }
+}
@@ -0,0 +1,53 @@
+use std::io::{Read, Write};
+
+use flate2::{read::DeflateDecoder, write::DeflateEncoder, Compression};
+use rusqlite::{ffi, Error as RusqliteError};
+
+/// Compress a single chunk.
+pub fn compress(data: &[u8]) -> Vec<u8> {
+    let mut encoder = DeflateEncoder::new(Vec::with_capacity(data.len()), Compression::default());
+    encoder
+        .write_all(data)
+        .and_then(|_| encoder.finish())
+        .expect("Compressing into memory failed")
+}
+
+/// Decompress a single chunk. Invalid data is reported as a corrupt database.
+pub fn decompress(data: &[u8]) -> Result<Vec<u8>, RusqliteError> {
+    let mut buffer = Vec::new();
+    DeflateDecoder::new(data)
+        .read_to_end(&mut buffer)
+        .map_err(|_| {
+            RusqliteError::SqliteFailure(
+                ffi::Error::new(ffi::SQLITE_CORRUPT),
+                Some(String::from("Invalid compressed chunk")),
+            )
+        })?;
+    Ok(buffer)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{compress, decompress};
+    use rusqlite::{Error as RusqliteError, ErrorCode};
+
+    #[test]
+    fn test_round_trip() {
+        for data in [&[][..], &[42u8][..], &[7u8; 1000][..]].iter() {
+            assert_eq!(
+                &decompress(&compress(data)).expect("Decompressing failed"),
+                data
+            );
+        }
+    }
+
+    #[test]
+    fn test_invalid() {
+        match decompress(&[0xFF, 0xFF, 0xFF]) {
+            Err(RusqliteError::SqliteFailure(error, _)) => {
+                assert_eq!(error.code, ErrorCode::DatabaseCorrupt)
+            }
+            _ => panic!("Invalid data was decompressed"),
+        }
+    }
+}
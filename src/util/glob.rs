@@ -0,0 +1,97 @@
+use regex::Regex;
+
+#[derive(Debug, Clone)]
+/// A GLOB pattern with the semantic of a shell: '*' and '?' do not match '/', while '**' does.
+pub struct Glob {
+    pattern: String,
+    matcher: Regex,
+}
+
+impl Glob {
+    /// Compile the pattern.
+    pub fn new<T: Into<String>>(pattern: T) -> Self {
+        let pattern = pattern.into();
+        let mut expression = String::with_capacity(2 * pattern.len() + 2);
+        expression.push('^');
+
+        let mut chars = pattern.chars().peekable();
+        while let Some(value) = chars.next() {
+            match value {
+                '*' if chars.peek() == Some(&'*') => {
+                    chars.next();
+                    expression.push_str(".*");
+                }
+                '*' => expression.push_str("[^/]*"),
+                '?' => expression.push_str("[^/]"),
+                '[' => {
+                    // Like SQLite, a leading ']' is part of the set and unclosed sets match literally.
+                    let set: String = chars.clone().collect();
+                    let start = match set.strip_prefix('^') {
+                        Some(remaining) if remaining.starts_with(']') => 2,
+                        _ if set.starts_with(']') => 1,
+                        _ => 0,
+                    };
+                    match set[start..].find(']') {
+                        Some(end) => {
+                            let set = &set[..start + end];
+                            expression.push('[');
+                            for (index, value) in set.chars().enumerate() {
+                                match value {
+                                    '^' if index == 0 => expression.push('^'),
+                                    '-' => expression.push('-'),
+                                    _ => expression.push_str(&regex::escape(&value.to_string())),
+                                }
+                            }
+                            expression.push(']');
+                            for _ in 0..=set.chars().count() {
+                                chars.next();
+                            }
+                        }
+                        None => expression.push_str(r"\["),
+                    }
+                }
+                _ => expression.push_str(&regex::escape(&value.to_string())),
+            }
+        }
+        expression.push('$');
+
+        Glob {
+            pattern,
+            matcher: Regex::new(&expression).expect("Encounter invalid GLOB RegEx"),
+        }
+    }
+
+    /// Returns a pattern for SQLite matching a superset of the paths.
+    pub fn sql_pattern(&self) -> &str {
+        // The GLOB of SQLite matches '/' for '*' and '?' anyway.
+        self.pattern.as_str()
+    }
+
+    /// Check whether the path matches the pattern.
+    pub fn is_match<T: AsRef<str>>(&self, path: T) -> bool {
+        self.matcher.is_match(path.as_ref())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Glob;
+    use test_case::test_case;
+
+    #[test_case("folder/*", "folder/file", true)]
+    #[test_case("folder/*", "folder/sub/file", false)]
+    #[test_case("folder/**", "folder/sub/file", true)]
+    #[test_case("folder/**/file", "folder/sub/file", true)]
+    #[test_case("*/file", "folder/sub/file", false)]
+    #[test_case("folder/fil?", "folder/file", true)]
+    #[test_case("folder?file", "folder/file", false)]
+    #[test_case("file.[tc]xt", "file.txt", true)]
+    #[test_case("file.[^tc]xt", "file.txt", false)]
+    #[test_case("file[]]", "file]", true)]
+    #[test_case("file[a-c]", "fileb", true)]
+    #[test_case("file[", "file[", true)]
+    #[test_case("file.txt", "file_txt", false)]
+    fn test_match(pattern: &str, path: &str, expected: bool) {
+        assert_eq!(Glob::new(pattern).is_match(path), expected);
+    }
+}
@@ -0,0 +1,85 @@
+use super::PathCollation;
+
+/// Options for loading a virtual file system, used by [FileSystem::load_with](crate::FileSystem::load_with).
+///
+/// Like the `OpenOptions` of the standard library, the options are configured by chaining calls on a mutable reference.
+/// The page size, the incremental vacuuming and the collation only apply if the file system is created.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LoadOptions {
+    pub(crate) create_file_system: bool,
+    pub(crate) page_size: Option<u32>,
+    pub(crate) incremental_vacuum: bool,
+    pub(crate) collation: PathCollation,
+    pub(crate) resize_statement_cache: bool,
+    pub(crate) vfs: Option<String>,
+}
+
+impl LoadOptions {
+    /// Create the options for loading the most recent version of an existing file system, like [load](crate::FileSystem::load).
+    pub fn new() -> Self {
+        LoadOptions {
+            create_file_system: false,
+            page_size: None,
+            incremental_vacuum: false,
+            collation: PathCollation::Binary,
+            resize_statement_cache: true,
+            vfs: None,
+        }
+    }
+
+    /// Set whether the file system is created if the database does not contain one yet.
+    pub fn create(&mut self, create_file_system: bool) -> &mut Self {
+        self.create_file_system = create_file_system;
+        self
+    }
+
+    /// Set the page size of a created file system.
+    ///
+    /// SQLite only supports powers of two between 512 and 65536 and ignores other values. As the page size must be set
+    /// before the first table is created, it is a no-op for already initialized databases until they are vacuumed.
+    pub fn page_size(&mut self, page_size: u32) -> &mut Self {
+        self.page_size = Some(page_size);
+        self
+    }
+
+    /// Enable incremental vacuuming for a created file system.
+    ///
+    /// As the mode must be set before the first table is created, it is a no-op for already initialized databases until
+    /// they are vacuumed. Afterwards, free pages are reclaimed using
+    /// [incremental_vacuum](crate::FileSystem::incremental_vacuum).
+    pub fn incremental_vacuum(&mut self, incremental_vacuum: bool) -> &mut Self {
+        self.incremental_vacuum = incremental_vacuum;
+        self
+    }
+
+    /// Set the collation used for comparing the paths of a created file system.
+    ///
+    /// The collation is stored as part of the schema. Therefore, it is kept by already initialized databases and
+    /// reported by [path_collation](crate::FileSystem::path_collation). Patterns of [find](crate::FileSystem::find)
+    /// stay case-sensitive.
+    pub fn collation(&mut self, collation: PathCollation) -> &mut Self {
+        self.collation = collation;
+        self
+    }
+
+    /// Set whether the capacity of the statement cache is left untouched. By default, it is resized.
+    ///
+    /// Keeping it prevents statements cached by other users of a shared connection from being evicted. The capacity
+    /// should be large enough for the statements precompiled by the file system, though.
+    pub fn keep_statement_cache(&mut self, keep_statement_cache: bool) -> &mut Self {
+        self.resize_statement_cache = !keep_statement_cache;
+        self
+    }
+
+    /// Set the named SQLite VFS used for accessing the database file in [open_with](crate::FileSystem::open_with).
+    pub fn vfs<T: Into<String>>(&mut self, vfs: T) -> &mut Self {
+        self.vfs = Some(vfs.into());
+        self
+    }
+}
+
+impl Default for LoadOptions {
+    fn default() -> Self {
+        LoadOptions::new()
+    }
+}
@@ -1,7 +1,12 @@
+use std::convert::TryInto;
+
 use const_format::concatcp;
 use regex::Regex;
+use rusqlite::params;
 use rusqlite::Connection as Database;
-use rusqlite::Error as DatabaseError;
+use rusqlite::Error as RusqliteError;
+
+use super::super::errors::DatabaseError;
 
 /// Meta data associated with the virtual file system.
 #[derive(Debug, Clone, Copy, Ord, PartialOrd, Eq, PartialEq)]
@@ -30,17 +35,17 @@ impl MetaData {
             .prepare("SELECT name FROM sqlite_master WHERE type='table' AND name LIKE ?")
         {
             Ok(statement) => statement,
-            Err(err) => return Availability::Error(err),
+            Err(err) => return Availability::from(err),
         };
 
         let versions =
             match statement.query(&[concatcp!(MetaDataExtractor::META_TABLE_PREFIX, "%")]) {
                 Ok(versions) => versions,
-                Err(err) => return Availability::Error(err),
+                Err(err) => return Availability::from(err),
             };
 
         let version_extractor = MetaDataExtractor::default();
-        let last_version = versions
+        let versions: Result<Vec<Option<u32>>, RusqliteError> = versions
             .mapped(|row| {
                 match row
                     .get_ref(0)
@@ -50,15 +55,31 @@ impl MetaData {
                     Err(error) => Err(error),
                 }
             })
-            .filter_map(|value| match value {
-                Ok(Some(version)) => Some(MetaData(version)),
-                _ => None,
-            })
-            .max();
+            .collect();
+
+        match versions.map(|versions| versions.into_iter().flatten().max()) {
+            Ok(Some(version)) => Availability::Available(MetaData(version)),
+            Ok(None) => Availability::Missing,
+            Err(err) => Availability::from(err),
+        }
+    }
+
+    /// Queries a database for the meta data of a specific version.
+    pub fn from_database_version(database: &Database, version: u32) -> Availability {
+        let mut statement = match database
+            .prepare("SELECT COUNT(*) FROM sqlite_master WHERE type='table' AND name = ?")
+        {
+            Ok(statement) => statement,
+            Err(err) => return Availability::from(err),
+        };
 
-        match last_version {
-            Some(meta_data) => Availability::Available(meta_data),
-            None => Availability::Missing,
+        match statement.query_row(
+            params![MetaDataExtractor::generate_table_name(version)],
+            |row| row.get::<_, i64>(0),
+        ) {
+            Ok(count) if count > 0 => Availability::Available(MetaData(version)),
+            Ok(_) => Availability::Missing,
+            Err(err) => Availability::from(err),
         }
     }
 
@@ -68,6 +89,12 @@ impl MetaData {
     }
 }
 
+impl From<RusqliteError> for Availability {
+    fn from(error: RusqliteError) -> Self {
+        Availability::Error(error.try_into().expect(DatabaseError::LOGIC_ERROR_MESSAGE))
+    }
+}
+
 #[derive(Debug, Clone)]
 /// An internal extractor for the version of Matryoshka.
 struct MetaDataExtractor(Regex);
@@ -84,7 +111,6 @@ impl Default for MetaDataExtractor {
 impl MetaDataExtractor {
     const META_TABLE_PREFIX: &'static str = "Matryoshka_Meta_";
 
-    #[cfg(test)]
     pub fn generate_table_name(version: u32) -> String {
         format!("{}{}", MetaDataExtractor::META_TABLE_PREFIX, version)
     }
@@ -131,12 +157,61 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_invalid_database() {
+        let tmp_dir = tempfile::TempDir::new().expect("Unable to create temporary directory");
+        let database_path = tmp_dir.path().join("database.sqlite");
+        std::fs::write(&database_path, vec![42u8; 4096]).expect("Writing database failed");
+        let database = Database::open(&database_path).expect("Valid SQLite database");
+
+        for availability in [
+            MetaData::from_database(&database),
+            MetaData::from_database_version(&database, 0),
+        ]
+        .iter()
+        {
+            match availability {
+                Availability::Error(error) => assert!(error.to_string().contains("not a database")),
+                _ => panic!("Invalid database was queried"),
+            }
+        }
+    }
+
     #[test]
     fn test_missing_filesystem() {
         let database = Database::open_in_memory().expect("Valid SQLite database");
         assert_eq!(MetaData::from_database(&database), Availability::Missing);
     }
 
+    #[test]
+    fn test_specific_version() {
+        let database = Database::open_in_memory().expect("Valid SQLite database");
+        for version in [0, 42].iter() {
+            database
+                .execute(
+                    &format!(
+                        "CREATE TABLE {} (example TEXT)",
+                        MetaDataExtractor::generate_table_name(*version)
+                    ),
+                    [],
+                )
+                .expect("Create database failed");
+        }
+
+        assert_eq!(
+            MetaData::from_database_version(&database, 0),
+            Availability::Available(MetaData(0))
+        );
+        assert_eq!(
+            MetaData::from_database_version(&database, 42),
+            Availability::Available(MetaData(42))
+        );
+        assert_eq!(
+            MetaData::from_database_version(&database, 1),
+            Availability::Missing
+        );
+    }
+
     #[test]
     fn test_existing_filesystem() {
         let database = Database::open_in_memory().expect("Valid SQLite database");
@@ -1,7 +1,29 @@
+mod checkpoint_mode;
+mod chunk_layout;
+mod chunk_stats;
+mod compression;
+mod glob;
 mod handle;
+mod load_options;
 mod meta_data;
+mod path_collation;
+mod path_kind;
+#[cfg(feature = "serialize")]
+mod serialization;
+mod storage_stats;
 mod virtual_path;
 
+pub use self::checkpoint_mode::CheckpointMode;
+pub use self::chunk_layout::ChunkLayout;
+pub use self::chunk_stats::ChunkStats;
+pub use self::compression::{compress, decompress};
+pub use self::glob::Glob;
 pub use self::handle::Handle;
+pub use self::load_options::LoadOptions;
 pub use self::meta_data::{Availability, MetaData};
+pub use self::path_collation::PathCollation;
+pub use self::path_kind::PathKind;
+#[cfg(feature = "serialize")]
+pub use self::serialization::{deserialize, serialize};
+pub use self::storage_stats::StorageStats;
 pub use self::virtual_path::VirtualPath;
@@ -0,0 +1,18 @@
+/// The collation used for comparing the paths of a file system.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PathCollation {
+    /// Paths are compared byte by byte, i.e. they are case-sensitive.
+    Binary,
+    /// Paths are compared ignoring the case of ASCII characters.
+    NoCase,
+}
+
+impl PathCollation {
+    /// Parse the name of the collation used by SQLite, treating unknown ones as binary.
+    pub(crate) fn from_sql<T: AsRef<str>>(name: T) -> Self {
+        match name.as_ref() {
+            name if name.eq_ignore_ascii_case("NOCASE") => PathCollation::NoCase,
+            _ => PathCollation::Binary,
+        }
+    }
+}
@@ -0,0 +1,10 @@
+/// The kind of entry a path refers to in the virtual file system, where directories only exist implicitly.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PathKind {
+    /// A file is stored at the path.
+    File,
+    /// The path is a prefix of at least one stored file.
+    Directory,
+    /// Neither a file nor a directory exists at the path.
+    Missing,
+}
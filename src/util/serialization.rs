@@ -0,0 +1,57 @@
+use std::ffi::CStr;
+use std::os::raw::{c_char, c_uchar};
+use std::ptr;
+
+use rusqlite::{ffi, Connection as Database, Error as RusqliteError};
+
+const MAIN_SCHEMA: &[u8] = b"main\0";
+
+fn main_schema() -> *const c_char {
+    CStr::from_bytes_with_nul(MAIN_SCHEMA)
+        .expect("Invalid schema name")
+        .as_ptr()
+}
+
+fn create_error(code: i32) -> RusqliteError {
+    RusqliteError::SqliteFailure(ffi::Error::new(code), None)
+}
+
+/// Copy the content of the main database into a byte vector, as it would be stored on disk.
+pub fn serialize(database: &Database) -> Result<Vec<u8>, RusqliteError> {
+    let mut size: ffi::sqlite3_int64 = 0;
+    // SAFETY: SQLite allocates a copy of the database which is freed after copying it into the vector.
+    unsafe {
+        let data = ffi::sqlite3_serialize(database.handle(), main_schema(), &mut size, 0);
+        if data.is_null() {
+            return Err(create_error(ffi::SQLITE_NOMEM));
+        }
+        let result = std::slice::from_raw_parts(data, size as usize).to_vec();
+        ffi::sqlite3_free(data.cast());
+        Ok(result)
+    }
+}
+
+/// Replace the main database of a connection with the serialized database. The content is kept in memory.
+pub fn deserialize(database: &mut Database, data: &[u8]) -> Result<(), RusqliteError> {
+    let size = data.len() as ffi::sqlite3_int64;
+    // SAFETY: The buffer is allocated by SQLite, which takes its ownership and frees it on close or failure.
+    unsafe {
+        let buffer = ffi::sqlite3_malloc64(std::cmp::max(data.len(), 1) as ffi::sqlite3_uint64)
+            .cast::<c_uchar>();
+        if buffer.is_null() {
+            return Err(create_error(ffi::SQLITE_NOMEM));
+        }
+        ptr::copy_nonoverlapping(data.as_ptr(), buffer, data.len());
+        match ffi::sqlite3_deserialize(
+            database.handle(),
+            main_schema(),
+            buffer,
+            size,
+            size,
+            (ffi::SQLITE_DESERIALIZE_FREEONCLOSE | ffi::SQLITE_DESERIALIZE_RESIZEABLE) as u32,
+        ) {
+            ffi::SQLITE_OK => Ok(()),
+            code => Err(create_error(code)),
+        }
+    }
+}
@@ -0,0 +1,47 @@
+/// Statistics regarding the storage used by the virtual file system.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StorageStats {
+    payload_size: u64,
+    page_size: u64,
+    page_count: u64,
+    free_pages: u64,
+}
+
+impl StorageStats {
+    pub(crate) fn new(payload_size: u64, page_size: u64, page_count: u64, free_pages: u64) -> Self {
+        StorageStats {
+            payload_size,
+            page_size,
+            page_count,
+            free_pages,
+        }
+    }
+
+    /// Returns the raw size of all the files stored in bytes.
+    pub fn payload_size(&self) -> u64 {
+        self.payload_size
+    }
+
+    /// Returns the size of the whole database in bytes.
+    pub fn file_size(&self) -> u64 {
+        self.page_size * self.page_count
+    }
+
+    /// Returns the number of unused pages in the database.
+    pub fn free_pages(&self) -> u64 {
+        self.free_pages
+    }
+
+    /// Returns the size of the unused pages in bytes, which could be reclaimed by vacuuming.
+    pub fn free_size(&self) -> u64 {
+        self.page_size * self.free_pages
+    }
+
+    /// Returns the ratio between the size of the database and the payload, if there is any payload.
+    pub fn overhead_ratio(&self) -> Option<f64> {
+        match self.payload_size {
+            0 => None,
+            payload_size => Some(self.file_size() as f64 / payload_size as f64),
+        }
+    }
+}
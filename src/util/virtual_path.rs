@@ -39,6 +39,58 @@ where
     }
 }
 
+impl VirtualPath {
+    /// Returns the path of the parent directory, which is empty for files in the root. An empty path has no parent.
+    pub fn parent(&self) -> Option<VirtualPath> {
+        self.parent_with_separator('/')
+    }
+
+    /// Like [parent](Self::parent), but splitting the path at another separator stored within the components.
+    pub fn parent_with_separator(&self, separator: char) -> Option<VirtualPath> {
+        if self.0.is_empty() {
+            return None;
+        }
+        Some(VirtualPath(match self.0.rfind(separator) {
+            Some(index) => self.0[..index].to_string(),
+            None => String::new(),
+        }))
+    }
+
+    /// Check whether the path is located below the given directory. The directory itself is not below.
+    pub fn is_below(&self, directory: &VirtualPath) -> bool {
+        if directory.0.is_empty() {
+            return !self.0.is_empty();
+        }
+        matches!(
+            self.0.strip_prefix(directory.0.as_str()),
+            Some(remaining) if remaining.starts_with('/')
+        )
+    }
+
+    /// Returns the last component of the path, if any.
+    pub fn file_name(&self) -> Option<&str> {
+        self.file_name_with_separator('/')
+    }
+
+    /// Like [file_name](Self::file_name), but splitting the path at another separator stored within the components.
+    pub fn file_name_with_separator(&self, separator: char) -> Option<&str> {
+        match self.0.rfind(separator) {
+            Some(index) => Some(&self.0[index + separator.len_utf8()..]),
+            None if self.0.is_empty() => None,
+            None => Some(self.0.as_str()),
+        }
+    }
+
+    /// Returns the extension of the file name, if any. Like in [std::path::Path], dotfiles have no extension.
+    pub fn extension(&self) -> Option<&str> {
+        let file_name = self.file_name()?;
+        match file_name.rfind('.') {
+            Some(0) | None => None,
+            Some(index) => Some(&file_name[index + 1..]),
+        }
+    }
+}
+
 impl AsRef<str> for VirtualPath {
     fn as_ref(&self) -> &str {
         self.0.as_str()
@@ -89,6 +141,59 @@ mod tests {
         assert_eq!(VirtualPath::from("/42/./PI"), "42/PI");
     }
 
+    #[test]
+    fn test_parent() {
+        assert_eq!(
+            VirtualPath::from("a/b/c").parent().expect("Parent missing"),
+            "a/b"
+        );
+        assert_eq!(VirtualPath::from("a").parent().expect("Parent missing"), "");
+        assert!(VirtualPath::from("").parent().is_none());
+    }
+
+    #[test]
+    fn test_separator() {
+        let path = VirtualPath::from("a/b:c:d");
+        assert_eq!(
+            path.parent_with_separator(':').expect("Parent missing"),
+            "a/b:c"
+        );
+        assert_eq!(path.file_name_with_separator(':'), Some("d"));
+        assert_eq!(
+            VirtualPath::from("a").parent_with_separator(':'),
+            Some(VirtualPath::from(""))
+        );
+        assert_eq!(VirtualPath::from("").file_name_with_separator(':'), None);
+    }
+
+    #[test]
+    fn test_is_below() {
+        let base = VirtualPath::from("uploads");
+        assert!(VirtualPath::from("uploads/sub/file").is_below(&base));
+        assert!(VirtualPath::from("uploads/file").is_below(&base));
+        assert!(!VirtualPath::from("uploads").is_below(&base));
+        assert!(!VirtualPath::from("uploads_other/file").is_below(&base));
+        assert!(!VirtualPath::from("uploads/../secret").is_below(&base));
+        assert!(VirtualPath::from("file").is_below(&VirtualPath::from("")));
+        assert!(!VirtualPath::from("").is_below(&VirtualPath::from("")));
+    }
+
+    #[test]
+    fn test_file_name() {
+        assert_eq!(VirtualPath::from("a/b/c").file_name(), Some("c"));
+        assert_eq!(VirtualPath::from("a").file_name(), Some("a"));
+        assert_eq!(VirtualPath::from("").file_name(), None);
+    }
+
+    #[test]
+    fn test_extension() {
+        assert_eq!(VirtualPath::from("a/file.txt").extension(), Some("txt"));
+        assert_eq!(VirtualPath::from("archive.tar.gz").extension(), Some("gz"));
+        assert_eq!(VirtualPath::from("a/.gitignore").extension(), None);
+        assert_eq!(VirtualPath::from("a.b/file").extension(), None);
+        assert_eq!(VirtualPath::from("").extension(), None);
+    }
+
     #[test]
     fn test_parent_dir() {
         assert_eq!(VirtualPath::from("/42/.."), "");